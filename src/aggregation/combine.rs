@@ -0,0 +1,277 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// How the outputs of a parent's subtasks become the parent's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationMode {
+    /// Join the subtasks' `result`s, in subtask order, under their names.
+    #[default]
+    Concatenate,
+    /// Combine the outputs with a `MergeFunction`.
+    Merge,
+    /// Ask an agent to combine them, with the `aggregate` template.
+    Agent,
+}
+
+/// Built-in functions for `AggregationMode::Merge`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeFunction {
+    /// Deep-merge the output objects in subtask order: later values win, arrays are appended.
+    #[default]
+    Deep,
+    /// Collect each subtask's `result` into an object keyed by subtask name.
+    Collect,
+    /// Take the output of the last subtask (the final stage of a pipeline).
+    Last,
+}
+
+impl AggregationMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AggregationMode::Concatenate => "concatenate",
+            AggregationMode::Merge => "merge",
+            AggregationMode::Agent => "agent",
+        }
+    }
+}
+
+impl MergeFunction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MergeFunction::Deep => "deep",
+            MergeFunction::Collect => "collect",
+            MergeFunction::Last => "last",
+        }
+    }
+}
+
+impl fmt::Display for AggregationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AggregationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "concatenate" | "concat" => Ok(AggregationMode::Concatenate),
+            "merge" => Ok(AggregationMode::Merge),
+            "agent" => Ok(AggregationMode::Agent),
+            other => Err(format!(
+                "Unknown aggregation mode: {other} (use concatenate, merge, agent)"
+            )),
+        }
+    }
+}
+
+/// How parent tasks combine their subtasks' outputs once all of them have completed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregationConfig {
+    pub mode: AggregationMode,
+    /// Merge function for `mode = "merge"`.
+    pub merge: MergeFunction,
+    /// Agent that aggregates for `mode = "agent"`; unset picks an agent like any task.
+    pub agent: Option<String>,
+    /// Text between results for `mode = "concatenate"`.
+    pub separator: String,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self {
+            mode: AggregationMode::default(),
+            merge: MergeFunction::default(),
+            agent: None,
+            separator: "\n\n".to_string(),
+        }
+    }
+}
+
+impl AggregationConfig {
+    /// The settings for one parent: its `metadata.aggregation` — a mode name, or a table
+    /// overriding some of these fields — applied on top of this config.
+    pub fn for_task(&self, metadata: &Value) -> Result<Self, String> {
+        match metadata.get("aggregation") {
+            None | Some(Value::Null) => Ok(self.clone()),
+            Some(Value::String(mode)) => Ok(Self {
+                mode: mode.parse()?,
+                ..self.clone()
+            }),
+            Some(Value::Object(overrides)) => {
+                let mut merged = serde_json::to_value(self).map_err(|e| e.to_string())?;
+                for (key, value) in overrides {
+                    merged[key] = value.clone();
+                }
+                serde_json::from_value(merged)
+                    .map_err(|e| format!("Invalid metadata.aggregation: {e}"))
+            }
+            Some(other) => Err(format!("Invalid metadata.aggregation: {other}")),
+        }
+    }
+}
+
+/// The output of one subtask, in subtask order.
+#[derive(Debug, Clone)]
+pub struct SubtaskOutput {
+    pub name: String,
+    pub output: Value,
+}
+
+/// Combine subtask outputs without an agent (`Concatenate` or `Merge`). The result always
+/// has a `result` and a `summary` key, like the output of a leaf task.
+pub fn combine(config: &AggregationConfig, outputs: &[SubtaskOutput]) -> Value {
+    let names: Vec<&str> = outputs.iter().map(|o| o.name.as_str()).collect();
+    let summary = format!(
+        "Combined the results of {} subtask(s): {}",
+        outputs.len(),
+        names.join(", ")
+    );
+
+    let mut combined = match (config.mode, config.merge) {
+        (AggregationMode::Merge, MergeFunction::Deep) => {
+            let mut merged = Value::Object(Map::new());
+            for output in outputs {
+                deep_merge(&mut merged, &output.output);
+            }
+            merged
+        }
+        (AggregationMode::Merge, MergeFunction::Collect) => {
+            let results: Map<String, Value> = outputs
+                .iter()
+                .map(|o| (o.name.clone(), result_of(&o.output).clone()))
+                .collect();
+            json!({ "result": results })
+        }
+        (AggregationMode::Merge, MergeFunction::Last) => outputs
+            .last()
+            .map(|o| o.output.clone())
+            .unwrap_or_else(|| json!({})),
+        _ => {
+            let sections: Vec<String> = outputs
+                .iter()
+                .map(|o| format!("## {}\n\n{}", o.name, result_text(result_of(&o.output))))
+                .collect();
+            json!({ "result": sections.join(&config.separator) })
+        }
+    };
+
+    if !combined.is_object() {
+        combined = json!({ "result": combined });
+    }
+    if combined.get("summary").is_none() || config.mode == AggregationMode::Concatenate {
+        combined["summary"] = json!(summary);
+    }
+    combined
+}
+
+/// A subtask's `result`, or its whole output when it has none.
+fn result_of(output: &Value) -> &Value {
+    output.get("result").unwrap_or(output)
+}
+
+/// A result as text: strings as-is, anything else as pretty JSON.
+fn result_text(result: &Value) -> String {
+    match result {
+        Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
+
+/// Merge `from` into `into`: objects recursively, arrays appended, anything else replaced.
+fn deep_merge(into: &mut Value, from: &Value) {
+    match (into, from) {
+        (Value::Object(into), Value::Object(from)) => {
+            for (key, value) in from {
+                match into.get_mut(key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        into.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(into), Value::Array(from)) => into.extend(from.iter().cloned()),
+        (into, from) => *into = from.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outputs() -> Vec<SubtaskOutput> {
+        vec![
+            SubtaskOutput {
+                name: "fetch".to_string(),
+                output: json!({"result": "rows", "summary": "fetched", "sources": ["a"]}),
+            },
+            SubtaskOutput {
+                name: "count".to_string(),
+                output: json!({"result": {"rows": 3}, "summary": "counted", "sources": ["b"]}),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_concatenate() {
+        let combined = combine(&AggregationConfig::default(), &outputs());
+        let result = combined["result"].as_str().unwrap();
+        assert!(result.starts_with("## fetch\n\nrows\n\n## count\n\n{"));
+        assert!(result.contains("\"rows\": 3"));
+        assert!(
+            combined["summary"]
+                .as_str()
+                .unwrap()
+                .contains("fetch, count")
+        );
+    }
+
+    #[test]
+    fn test_merge_functions() {
+        let config = |merge| AggregationConfig {
+            mode: AggregationMode::Merge,
+            merge,
+            ..Default::default()
+        };
+
+        let deep = combine(&config(MergeFunction::Deep), &outputs());
+        assert_eq!(deep["result"], json!({"rows": 3}));
+        assert_eq!(deep["sources"], json!(["a", "b"]));
+        assert_eq!(deep["summary"], json!("counted"));
+
+        let collect = combine(&config(MergeFunction::Collect), &outputs());
+        assert_eq!(
+            collect["result"],
+            json!({"fetch": "rows", "count": {"rows": 3}})
+        );
+        assert!(collect["summary"].is_string());
+
+        let last = combine(&config(MergeFunction::Last), &outputs());
+        assert_eq!(last["result"], json!({"rows": 3}));
+    }
+
+    #[test]
+    fn test_metadata_overrides() {
+        let config = AggregationConfig::default();
+        let agent = config.for_task(&json!({"aggregation": "agent"})).unwrap();
+        assert_eq!(agent.mode, AggregationMode::Agent);
+
+        let merge = config
+            .for_task(&json!({"aggregation": {"mode": "merge", "merge": "collect"}}))
+            .unwrap();
+        assert_eq!(merge.mode, AggregationMode::Merge);
+        assert_eq!(merge.merge, MergeFunction::Collect);
+        assert_eq!(merge.separator, "\n\n");
+
+        assert!(config.for_task(&json!({"aggregation": "vote"})).is_err());
+        assert_eq!(config.for_task(&json!({})).unwrap(), config);
+    }
+}
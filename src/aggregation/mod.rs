@@ -0,0 +1,3 @@
+pub mod combine;
+
+pub use combine::*;
@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Bid, BidEvaluator, ContractBuildError, ContractBuilder, RFP, ScoredBid};
+use crate::optimizer::DelegationOverhead;
+use crate::types::{
+    Agent, DelegationContract, DisputeResolutionTerms, MonitoringTerms, PaymentTerms,
+    ResourceUsage, Task,
+};
+
+/// Principal that delegates tasks on the user's behalf: the delegator of every contract the
+/// coordinator awards.
+pub const COORDINATOR_ID: Uuid = Uuid::nil();
+
+/// How tasks are put out to tender: the RFP budget, how bids are estimated and weighed, and
+/// the monitoring terms written into the awarded contracts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarketConfig {
+    /// Most a single task may cost, in US dollars; bids above it are rejected.
+    pub max_cost: f64,
+    /// Cost an agent bids for a task when it has no completed tasks to go by.
+    pub default_cost: f64,
+    /// Duration an agent bids for a task when it has no completed tasks to go by.
+    pub default_duration_secs: u64,
+    pub cost_weight: f64,
+    pub quality_weight: f64,
+    pub confidence_weight: f64,
+    /// Monitoring terms of awarded contracts: how often the agent is checked on, the longest
+    /// a run may take (0 for no limit), the lowest acceptable quality, and the most resources
+    /// a run may consume (0 for no limit).
+    pub checkpoint_interval_secs: u64,
+    pub max_latency_ms: u64,
+    pub min_quality_score: f64,
+    pub max_resource_budget: f64,
+    /// What delegating a task costs on top of its bid, in US dollars: putting it out to
+    /// tender, signing its contract, and each monitoring checkpoint. Only used to forecast
+    /// delegation overhead (`/execute --dry-run`).
+    pub negotiation_cost: f64,
+    pub contract_cost: f64,
+    pub checkpoint_cost: f64,
+}
+
+impl Default for MarketConfig {
+    fn default() -> Self {
+        let evaluator = BidEvaluator::default();
+        Self {
+            max_cost: 5.0,
+            default_cost: 0.1,
+            default_duration_secs: 120,
+            cost_weight: evaluator.cost_weight,
+            quality_weight: evaluator.quality_weight,
+            confidence_weight: evaluator.confidence_weight,
+            checkpoint_interval_secs: 30,
+            max_latency_ms: 0,
+            min_quality_score: 0.5,
+            max_resource_budget: 0.0,
+            negotiation_cost: 0.0,
+            contract_cost: 0.0,
+            checkpoint_cost: 0.0,
+        }
+    }
+}
+
+impl MarketConfig {
+    pub fn evaluator(&self) -> BidEvaluator {
+        BidEvaluator::new(
+            self.cost_weight,
+            self.quality_weight,
+            self.confidence_weight,
+        )
+    }
+
+    pub fn monitoring_terms(&self) -> MonitoringTerms {
+        MonitoringTerms {
+            checkpoint_interval_secs: self.checkpoint_interval_secs,
+            max_latency_ms: self.max_latency_ms,
+            min_quality_score: self.min_quality_score,
+            max_resource_budget: self.max_resource_budget,
+        }
+    }
+
+    /// The delegation overhead of a task expected to run for `duration_secs`, checked on every
+    /// `checkpoint_interval_secs`. A task that keeps the contract it holds is not negotiated
+    /// or contracted again.
+    pub fn overhead(&self, duration_secs: u64, under_contract: bool) -> DelegationOverhead {
+        let (negotiation_cost, contract_cost) = if under_contract {
+            (0.0, 0.0)
+        } else {
+            (self.negotiation_cost, self.contract_cost)
+        };
+        DelegationOverhead {
+            negotiation_cost,
+            contract_cost,
+            verification_cost_per_checkpoint: self.checkpoint_cost,
+            expected_checkpoints: duration_secs
+                .checked_div(self.checkpoint_interval_secs)
+                .map_or(0, |n| n as u32),
+        }
+    }
+
+    /// The request for proposals for a task, due by its deadline.
+    pub fn rfp(&self, task: &Task) -> RFP {
+        let rfp = RFP::new(task.id, task.required_capabilities.clone(), self.max_cost);
+        match task.deadline {
+            Some(deadline) => rfp.with_deadline(deadline),
+            None => rfp,
+        }
+    }
+
+    /// The bid an agent makes on a task: its average cost and duration over `track_record`
+    /// (the usage of tasks it completed), or the configured defaults without one, and its
+    /// mean proficiency in the required capabilities as confidence.
+    pub fn bid(&self, agent: &Agent, task: &Task, track_record: &[ResourceUsage]) -> Bid {
+        let (cost, duration_secs) = if track_record.is_empty() {
+            (self.default_cost, self.default_duration_secs)
+        } else {
+            let n = track_record.len() as f64;
+            let cost = track_record.iter().map(|u| u.cost_usd).sum::<f64>() / n;
+            let duration_ms = track_record.iter().map(|u| u.duration_ms).sum::<u64>() as f64 / n;
+            (cost, (duration_ms / 1000.0).ceil() as u64)
+        };
+        let confidence = if task.required_capabilities.is_empty() {
+            let caps = &agent.capabilities.capabilities;
+            if caps.is_empty() {
+                0.5
+            } else {
+                caps.iter().map(|c| c.proficiency).sum::<f64>() / caps.len() as f64
+            }
+        } else {
+            task.required_capabilities
+                .iter()
+                .map(|cap| agent.capability_proficiency(cap))
+                .sum::<f64>()
+                / task.required_capabilities.len() as f64
+        };
+        Bid::new(
+            agent.id,
+            task.id,
+            cost,
+            duration_secs,
+            confidence.clamp(0.0, 1.0),
+        )
+    }
+
+    /// Draft the contract awarding `task` to the winner of its auction, unsigned. The winner
+    /// is paid its bid and may take the actions it is permitted; the contract expires at the
+    /// task's deadline.
+    pub fn contract(
+        &self,
+        task: &Task,
+        winner: &ScoredBid,
+        agent: &Agent,
+    ) -> Result<DelegationContract, ContractBuildError> {
+        let mut builder = ContractBuilder::new()
+            .task_id(task.id)
+            .delegator_id(COORDINATOR_ID)
+            .delegatee_id(winner.bid.agent_id)
+            .payment_terms(PaymentTerms {
+                total_amount: winner.bid.proposed_cost,
+                escrow_amount: 0.0,
+                milestone_payments: Vec::new(),
+                penalty_rate: 0.0,
+            })
+            .monitoring_terms(self.monitoring_terms())
+            .dispute_resolution_terms(DisputeResolutionTerms {
+                dispute_bond: 0.0,
+                resolution_timeout_secs: 3600,
+                panel_size: 1,
+                escalation_enabled: true,
+            })
+            .permitted_actions(agent.permissions.allowed_actions.clone())
+            .max_delegation_depth(agent.permissions.max_delegation_depth);
+        if let Some(deadline) = task.deadline {
+            builder = builder.expires_at(deadline);
+        }
+        builder.build()
+    }
+}
+
+/// A task's auction: the request, every bid received, and the bids within budget ranked
+/// best first.
+#[derive(Debug, Clone)]
+pub struct Auction {
+    pub rfp: RFP,
+    pub bids: Vec<Bid>,
+    pub ranking: Vec<ScoredBid>,
+}
+
+impl Auction {
+    /// Collect a bid from each candidate and rank them. `track_record` gives an agent's
+    /// completed-task usage and `quality` its predicted quality in [0, 1].
+    pub fn run(
+        config: &MarketConfig,
+        task: &Task,
+        candidates: &[Agent],
+        track_record: &dyn Fn(Uuid) -> Vec<ResourceUsage>,
+        quality: &dyn Fn(Uuid) -> f64,
+    ) -> Self {
+        let rfp = config.rfp(task);
+        let bids: Vec<Bid> = candidates
+            .iter()
+            .map(|agent| config.bid(agent, task, &track_record(agent.id)))
+            .collect();
+        let ranking = config.evaluator().evaluate(&bids, rfp.max_cost, quality);
+        Self { rfp, bids, ranking }
+    }
+
+    pub fn winner(&self) -> Option<&ScoredBid> {
+        self.ranking.first()
+    }
+
+    /// Where a bid ranked (0 is the winner), or `None` when it was over budget.
+    pub fn rank_of(&self, agent_id: Uuid) -> Option<usize> {
+        self.ranking.iter().position(|s| s.bid.agent_id == agent_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Capability;
+
+    fn agent(name: &str, caps: &[(&str, f64)]) -> Agent {
+        let mut agent = Agent::new(name);
+        agent.capabilities.capabilities = caps
+            .iter()
+            .map(|(n, p)| Capability {
+                name: n.to_string(),
+                proficiency: *p,
+                certified: false,
+                last_verified: None,
+            })
+            .collect();
+        agent.permissions.allowed_actions = vec!["read".into()];
+        agent
+    }
+
+    fn usage(cost_usd: f64, duration_ms: u64) -> ResourceUsage {
+        ResourceUsage {
+            cost_usd,
+            duration_ms,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bid_from_track_record() {
+        let config = MarketConfig::default();
+        let task = Task::new("t", "d").with_capabilities(vec!["code".into(), "docs".into()]);
+        let worker = agent("w", &[("code", 0.9), ("docs", 0.5)]);
+
+        let fresh = config.bid(&worker, &task, &[]);
+        assert_eq!(fresh.proposed_cost, config.default_cost);
+        assert_eq!(fresh.proposed_duration_secs, config.default_duration_secs);
+        assert!((fresh.confidence_score - 0.7).abs() < 1e-9);
+
+        let seasoned = config.bid(&worker, &task, &[usage(0.2, 1_000), usage(0.4, 2_500)]);
+        assert!((seasoned.proposed_cost - 0.3).abs() < 1e-9);
+        assert_eq!(seasoned.proposed_duration_secs, 2);
+    }
+
+    #[test]
+    fn test_auction_ranks_bids_and_drops_over_budget() {
+        let config = MarketConfig {
+            max_cost: 1.0,
+            ..Default::default()
+        };
+        let task = Task::new("t", "d").with_capabilities(vec!["code".into()]);
+        let cheap = agent("cheap", &[("code", 0.8)]);
+        let pricey = agent("pricey", &[("code", 0.9)]);
+        let greedy = agent("greedy", &[("code", 1.0)]);
+        let (cheap_id, pricey_id, greedy_id) = (cheap.id, pricey.id, greedy.id);
+
+        let auction = Auction::run(
+            &config,
+            &task,
+            &[pricey, cheap, greedy],
+            &|id| match id {
+                id if id == cheap_id => vec![usage(0.1, 1_000)],
+                id if id == pricey_id => vec![usage(0.9, 1_000)],
+                _ => vec![usage(2.0, 1_000)],
+            },
+            &|_| 0.5,
+        );
+        assert_eq!(auction.bids.len(), 3);
+        assert_eq!(auction.winner().unwrap().bid.agent_id, cheap_id);
+        assert_eq!(auction.rank_of(pricey_id), Some(1));
+        assert_eq!(auction.rank_of(greedy_id), None);
+    }
+
+    #[test]
+    fn test_contract_awards_winner() {
+        let config = MarketConfig::default();
+        let task = Task::new("t", "d");
+        let worker = agent("w", &[("general", 0.8)]);
+        let workers = std::slice::from_ref(&worker);
+        let auction = Auction::run(&config, &task, workers, &|_| Vec::new(), &|_| 0.5);
+
+        let contract = config
+            .contract(&task, auction.winner().unwrap(), &worker)
+            .unwrap();
+        assert_eq!(contract.delegator_id, COORDINATOR_ID);
+        assert_eq!(contract.delegatee_id, worker.id);
+        assert_eq!(contract.payment.total_amount, config.default_cost);
+        assert_eq!(contract.permitted_actions, vec!["read".to_string()]);
+        assert_eq!(contract.monitoring, config.monitoring_terms());
+        assert!(!contract.is_fully_signed());
+    }
+
+    #[test]
+    fn test_overhead() {
+        let config = MarketConfig {
+            negotiation_cost: 0.02,
+            contract_cost: 0.01,
+            checkpoint_cost: 0.001,
+            checkpoint_interval_secs: 30,
+            ..Default::default()
+        };
+        let fresh = config.overhead(125, false);
+        assert_eq!(fresh.expected_checkpoints, 4);
+        assert!((fresh.total() - 0.034).abs() < 1e-9);
+        assert!((config.overhead(125, true).total() - 0.004).abs() < 1e-9);
+
+        let unmonitored = MarketConfig {
+            checkpoint_interval_secs: 0,
+            ..config
+        };
+        assert_eq!(unmonitored.overhead(125, false).expected_checkpoints, 0);
+    }
+}
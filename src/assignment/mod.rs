@@ -1,7 +1,9 @@
 pub mod bid;
 pub mod contract_builder;
+pub mod market;
 pub mod matcher;
 
 pub use bid::*;
 pub use contract_builder::*;
+pub use market::*;
 pub use matcher::*;
@@ -1,3 +1,4 @@
+use crate::assignment::COORDINATOR_ID;
 use crate::reputation::ReputationEngine;
 use crate::types::{Agent, Capability};
 use anyhow::{Result, bail};
@@ -26,7 +27,7 @@ pub async fn handle(action: AgentAction, state: &AppState) -> Result<()> {
             let id = agent.id;
             println!("Registered agent: {} ({})", name, id);
             print_agent(&agent);
-            state.agents.insert(id, agent);
+            state.register_agent(agent, COORDINATOR_ID).await?;
         }
 
         AgentAction::List => {
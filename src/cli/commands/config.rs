@@ -1,6 +1,7 @@
 use anyhow::Result;
 use std::path::Path;
 
+use crate::aggregation::AggregationMode;
 use crate::cli::ConfigAction;
 use crate::config::PanopticonConfig;
 
@@ -38,6 +39,46 @@ fn print_config(config: &PanopticonConfig) {
     println!("  max_turns:               {}", config.max_turns);
     println!("  min_reputation_threshold: {:.2}", config.min_reputation_threshold);
     println!("  decomposition_strategy:  {}", config.decomposition_strategy);
+    println!("  executor:                {}", config.executor);
+    if let Some(fixtures) = &config.scripted_fixtures {
+        println!("  scripted_fixtures:       {}", fixtures);
+    }
+    if !config.command.is_empty() {
+        println!("  command:                 {}", config.command.join(" "));
+    }
+    if config.executor == "http" {
+        println!("  http.base_url:           {}", config.http.base_url);
+        println!("  http.api:                {}", config.http.api);
+        if let Some(model) = &config.http.model {
+            println!("  http.model:              {}", model);
+        }
+        if let Some(var) = &config.http.auth_env {
+            println!("  http.auth_env:           {}", var);
+        }
+        println!(
+            "  http timeouts:           {}s connect, {}s request",
+            config.http.connect_timeout_secs, config.http.request_timeout_secs
+        );
+    }
+    println!(
+        "  retry:                   {} attempt(s), {}-{}ms backoff",
+        config.retry.max_attempts, config.retry.initial_backoff_ms, config.retry.max_backoff_ms
+    );
+    println!("  schema_repair_rounds:    {}", config.schema_repair_rounds);
+    println!("  max_parallel_tasks:      {}", config.max_parallel_tasks);
+    println!(
+        "  failure:                 {} (max_retries: {})",
+        config.failure.policy, config.failure.max_retries
+    );
+    match config.aggregation.mode {
+        AggregationMode::Merge => println!(
+            "  aggregation:             merge ({})",
+            config.aggregation.merge.as_str()
+        ),
+        mode => println!("  aggregation:             {mode}"),
+    }
+    println!("  market.max_cost:         ${:.2}", config.market.max_cost);
+    println!("  allowed_actions:         {}", config.allowed_actions.join(", "));
     if config.allowed_tools.is_empty() {
         println!("  allowed_tools:           (all)");
     } else {
@@ -1,3 +1,4 @@
+use crate::assignment::COORDINATOR_ID;
 use crate::decomposition::{HybridStrategy, traits::DecompositionStrategy};
 use crate::types::*;
 use anyhow::Result;
@@ -93,17 +94,16 @@ pub async fn run(state: &AppState) -> Result<()> {
         TaskEvent::ExecutionComplete,
         TaskEvent::VerificationPassed,
     ];
+    let (task_id, mut prev) = (task.id, task.state);
+    state.create_task(task, COORDINATOR_ID).await?;
+    state.register_agent(analyst, COORDINATOR_ID).await?;
     for event in events {
-        let prev = task.state;
-        task.apply_event(event)?;
-        println!("    {prev:?} --({event:?})--> {:?}", task.state);
+        let new = state.transition(task_id, event, COORDINATOR_ID).await?;
+        println!("    {prev:?} --({event:?})--> {new:?}");
+        prev = new;
     }
 
     println!("\n    Task completed successfully!");
 
-    // Save to state
-    state.tasks.insert(task.id, task);
-    state.agents.insert(analyst.id, analyst);
-
     Ok(())
 }
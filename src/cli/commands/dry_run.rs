@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::execute::{configured_agents, default_agent, targets};
+use crate::assignment::CapabilityMatcher;
+use crate::cli::state::AppState;
+use crate::permissions::{ApprovalLevel, ApprovalRequirement};
+use crate::scheduler::{Forecast, Simulation, TaskForecast};
+use crate::types::{Agent, Task};
+
+/// Handle `/execute --dry-run`: walk the task trees a run would execute and print what it is
+/// expected to do — who takes each task, what it needs approved, and what it costs and how
+/// long it takes — followed by the critical path and the expected spend. No agent is called
+/// and nothing is stored.
+pub async fn handle(id: Option<Uuid>, all: bool, model: &str, state: &AppState) -> Result<()> {
+    let targets = targets(id, all, state)?;
+    if targets.is_empty() {
+        println!("No pending tasks to execute.");
+        return Ok(());
+    }
+
+    let (agents, default_agent) = if state.config.agents.is_empty() {
+        let agent = default_agent(state, model);
+        let id = agent.id;
+        (vec![agent], Some(id))
+    } else {
+        (configured_agents(state), None)
+    };
+    let tasks: HashMap<Uuid, Task> = state
+        .tasks
+        .iter()
+        .map(|e| (*e.key(), e.value().clone()))
+        .collect();
+    let contracts = state
+        .contracts
+        .iter()
+        .map(|e| (*e.key(), e.value().clone()))
+        .collect();
+    let forecast = Simulation {
+        tasks: &tasks,
+        agents: &agents,
+        contracts: &contracts,
+        default_agent,
+        market: &state.config.market,
+        matcher: CapabilityMatcher::new(state.config.min_reputation_threshold),
+        max_parallel: state.config.max_parallel_tasks,
+        track_record: &|id| state.track_record(id),
+        quality: &|id| {
+            state
+                .reputation_engine
+                .get_composite_score(id)
+                .unwrap_or(0.5)
+        },
+        now: Utc::now(),
+    }
+    .run(&targets);
+
+    println!("Dry run: no agent is called and nothing is changed.");
+    let agent_name = |id: Uuid| {
+        agents
+            .iter()
+            .find(|a| a.id == id)
+            .map_or_else(|| id.to_string(), |a| a.name.clone())
+    };
+    for task in &forecast.tasks {
+        print_task(task, &agent_name);
+    }
+    print_summary(&forecast, &agents, state);
+    Ok(())
+}
+
+/// One task of the forecast: a parent with its tree's totals, or a leaf with its assignee,
+/// approval, spend, and timing. Risks follow, one per line.
+fn print_task(task: &TaskForecast, agent_name: &dyn Fn(Uuid) -> String) {
+    let depth = task.number.matches('.').count() + usize::from(!task.number.is_empty());
+    let indent = "  ".repeat(depth);
+    let label = if task.number.is_empty() {
+        format!("\n{}Task {} ({})", indent, task.name, task.task_id)
+    } else {
+        format!("{}[{}] {}", indent, task.number, task.name)
+    };
+
+    if task.done {
+        println!("{label}: already completed");
+    } else if task.is_parent {
+        println!(
+            "{label}: ${:.4} + ${:.4} overhead, {} to {}",
+            task.cost,
+            task.overhead,
+            format_secs(task.start_secs),
+            format_secs(task.finish_secs)
+        );
+    } else if let Some(agent_id) = task.agent_id {
+        println!(
+            "{label} — {}{}, approval: {}",
+            agent_name(agent_id),
+            if task.under_contract {
+                " (under contract)"
+            } else {
+                ""
+            },
+            task.approval
+                .as_ref()
+                .map(describe_approval)
+                .unwrap_or_default()
+        );
+        println!(
+            "{indent}    ${:.4} + ${:.4} overhead ({} checkpoint{}), ~{} ({} to {})",
+            task.cost,
+            task.overhead,
+            task.checkpoints,
+            if task.checkpoints == 1 { "" } else { "s" },
+            format_secs(task.duration_secs()),
+            format_secs(task.start_secs),
+            format_secs(task.finish_secs)
+        );
+    } else {
+        println!("{label} — unassigned");
+    }
+    for risk in &task.risks {
+        println!("{indent}    ! {risk}");
+    }
+}
+
+/// The critical path, the expected duration and spend, and any agent expected to run out of
+/// budget.
+fn print_summary(forecast: &Forecast, agents: &[Agent], state: &AppState) {
+    let name = |id: &Uuid| {
+        forecast
+            .tasks
+            .iter()
+            .find(|t| t.task_id == *id)
+            .map_or_else(|| id.to_string(), |t| t.name.clone())
+    };
+    println!();
+    if forecast.critical_path.is_empty() {
+        println!("Critical path: nothing left to run");
+    } else {
+        let path: Vec<String> = forecast.critical_path.iter().map(name).collect();
+        println!(
+            "Critical path ({}): {}",
+            format_secs(forecast.critical_path_secs),
+            path.join(" → ")
+        );
+    }
+    println!(
+        "Expected duration: {} (up to {} subtask{} at once)",
+        format_secs(forecast.duration_secs),
+        state.config.max_parallel_tasks,
+        if state.config.max_parallel_tasks == 1 {
+            ""
+        } else {
+            "s"
+        }
+    );
+    println!(
+        "Expected spend: ${:.4} (${:.4} in bids + ${:.4} delegation overhead)",
+        forecast.total_cost(),
+        forecast.cost,
+        forecast.overhead
+    );
+
+    for agent in agents {
+        let expected: f64 = forecast
+            .tasks
+            .iter()
+            .filter(|t| !t.is_parent && t.agent_id == Some(agent.id))
+            .map(|t| t.cost)
+            .sum();
+        let spent = state.agent_usage(agent.id).cost_usd;
+        let budget = agent.permissions.max_cost_budget;
+        if expected > 0.0 && spent + expected > budget {
+            println!(
+                "Warning: {} is expected to spend ${:.4} on top of ${:.4} spent, over its ${:.2} budget",
+                agent.name, expected, spent, budget
+            );
+        }
+    }
+    match forecast.at_risk() {
+        0 => {}
+        n => println!("{n} task(s) at risk (marked !)."),
+    }
+}
+
+fn describe_approval(approval: &ApprovalRequirement) -> String {
+    match approval.level {
+        ApprovalLevel::StandingPermission => "standing".to_string(),
+        ApprovalLevel::ContextualPermission => format!(
+            "contextual ({} approver{})",
+            approval.required_approvers,
+            if approval.required_approvers == 1 {
+                ""
+            } else {
+                "s"
+            }
+        ),
+        ApprovalLevel::JustInTimePermission => format!(
+            "just-in-time ({} approvers{})",
+            approval.required_approvers,
+            if approval.human_required {
+                " + human"
+            } else {
+                ""
+            }
+        ),
+    }
+}
+
+/// A number of seconds as `45s` or `2m 05s`.
+fn format_secs(secs: u64) -> String {
+    if secs < 60 {
+        format!("{secs}s")
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
@@ -1,182 +1,1483 @@
 use anyhow::{Result, bail};
 use chrono::Utc;
+use futures::future::LocalBoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use crate::cli::state::AppState;
-use crate::executor::{AgentExecutor, ClaudeExecutor, ExecutionContext};
+use crate::cli::{TaskAction, console};
+use crate::aggregation::{AggregationConfig, AggregationMode, SubtaskOutput, combine};
+use crate::assignment::{Auction, COORDINATOR_ID, CapabilityMatcher};
+use crate::coordination::{
+    AT_RISK_PRIORITY, CoordinationTrigger, Coordinator, InternalTrigger, TERMINATED_PREFIX,
+};
+use crate::executor::{
+    self, AgentExecutor, CancellationToken, ExecutionPolicy, ExecutorBinding, ExecutorRegistry,
+    RetryingExecutor, TemplateStore, UpstreamOutput, ValidatingExecutor, Workspace,
+    deadline_passed, execution_timeout,
+};
+use crate::ledger::LedgerEntryKind;
+use crate::monitoring::{Checkpoint, MonitoringEvent, watch_execution};
 use crate::reputation::score::{ReputationDimension, ReputationObservation};
-use crate::types::{Agent, Capability, TaskEvent, TaskState};
-use crate::verification::{VerificationOutcome, Verifier, verifiers::DirectInspectionVerifier};
+use crate::scheduler::{
+    DeadlineStatus, FailurePolicy, NodeStatus, TaskDag, concurrency_limit, urgency_order,
+};
+use crate::types::error::{ExecutorErrorKind, PanopticonError};
+use crate::types::{Agent, Capability, DelegationContract, Task, TaskEvent, TaskState};
+use crate::verification::{
+    TaskResult, VerificationOutcome, Verifier,
+    verifiers::{DirectInspectionVerifier, SchemaVerifier},
+};
+
+/// Agents available for this run and the backends they execute on.
+struct Fleet {
+    registry: ExecutorRegistry,
+    /// Agent that takes every task when no agents are configured.
+    default_agent: Option<Uuid>,
+    /// Number of tasks each agent is running right now.
+    running: Mutex<HashMap<Uuid, u32>>,
+    /// Signalled whenever a slot is released.
+    freed: tokio::sync::Notify,
+}
+
+/// A claimed execution slot on an agent, released when dropped.
+struct Slot<'a> {
+    fleet: &'a Fleet,
+    agent_id: Uuid,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let mut running = self.fleet.running.lock().unwrap();
+        if let Some(count) = running.get_mut(&self.agent_id) {
+            *count = count.saturating_sub(1);
+        }
+        self.fleet.freed.notify_waiters();
+    }
+}
+
+impl Fleet {
+    /// Bind configured agents to their backends, or fall back to a single default agent on the
+    /// top-level executor when no agents are configured. Agents whose backend fails its health
+    /// check are left out.
+    async fn build(model: &str, state: &AppState) -> Result<Self> {
+        let state_dir = state.state_dir.as_deref();
+        let (mut registry, default_agent) = if state.config.agents.is_empty() {
+            let executor = executor::from_config(&state.config, model, state_dir)?;
+            let agent_id = ensure_default_agent(state, model).await?;
+            let mut registry = ExecutorRegistry::new();
+            registry.bind_agent(
+                agent_id,
+                ExecutorBinding::new(executor, model).with_retry(state.config.retry.clone()),
+            );
+            (registry, Some(agent_id))
+        } else {
+            ensure_configured_agents(state).await?;
+            (
+                ExecutorRegistry::from_config(&state.config, model, state_dir)?,
+                None,
+            )
+        };
+
+        let agents: Vec<Agent> = state.agents.iter().map(|e| e.value().clone()).collect();
+        let mut available = 0;
+        for agent in &agents {
+            let Some(binding) = registry.resolve(agent) else {
+                continue;
+            };
+            match binding.executor.health_check().await {
+                Ok(true) => available += 1,
+                _ => {
+                    println!(
+                        "Warning: {} for agent {} is not available.",
+                        binding.executor.name(),
+                        agent.name
+                    );
+                    registry.unbind(agent);
+                }
+            }
+        }
+        if available == 0 {
+            bail!("No agent has an available executor backend.");
+        }
+
+        Ok(Self {
+            registry,
+            default_agent,
+            running: Mutex::new(HashMap::new()),
+            freed: tokio::sync::Notify::new(),
+        })
+    }
+
+    /// Whether the agent runs fewer tasks than its `max_concurrent_tasks` (at least one), and,
+    /// when it runs in a configured `working_dir`, nothing else runs in that directory: the
+    /// artifacts of a task are the files that changed there while it ran, so tasks sharing a
+    /// directory run one at a time.
+    fn has_capacity(&self, agent_id: Uuid, state: &AppState) -> bool {
+        let working_dir = |id: Uuid| {
+            state
+                .agents
+                .get(&id)
+                .and_then(|a| self.registry.resolve(a.value())?.working_dir.clone())
+        };
+        let max = state
+            .agents
+            .get(&agent_id)
+            .map(|a| a.max_concurrent_tasks)
+            .unwrap_or(1)
+            .max(1);
+        let running = self.running.lock().unwrap();
+        if running.get(&agent_id).copied().unwrap_or(0) >= max {
+            return false;
+        }
+        let Some(dir) = working_dir(agent_id) else {
+            return true;
+        };
+        !running
+            .iter()
+            .any(|(id, count)| *count > 0 && working_dir(*id).as_ref() == Some(&dir))
+    }
+
+    /// Wait until a slot is released anywhere in the fleet, or a short while at most (a slot
+    /// released just before waiting would otherwise go unnoticed).
+    async fn slot_freed(&self) {
+        let _ = tokio::time::timeout(
+            std::time::Duration::from_millis(250),
+            self.freed.notified(),
+        )
+        .await;
+    }
+
+    /// Agents bound to an executor backend: the only ones a task can be moved to.
+    fn bound_agents(&self, state: &AppState) -> Vec<Uuid> {
+        state
+            .agents
+            .iter()
+            .filter(|a| self.registry.resolve(a.value()).is_some())
+            .map(|a| *a.key())
+            .collect()
+    }
+
+    /// Claim an execution slot on the agent for the lifetime of the returned guard.
+    fn claim(&self, agent_id: Uuid) -> Slot<'_> {
+        *self.running.lock().unwrap().entry(agent_id).or_insert(0) += 1;
+        Slot {
+            fleet: self,
+            agent_id,
+        }
+    }
+
+    /// Pick the agent for a task. A task under a signed, unexpired contract stays with its
+    /// contractor; otherwise it is put out to tender: the candidates (the default agent if
+    /// there is one, else the task's assignee if it has a backend, else every capable agent
+    /// with a backend) that have a free slot each bid, and the best-scoring bid within budget
+    /// wins. Agents in `exclude` are never picked.
+    /// Returns `None` when the contractor (or every candidate) is busy.
+    fn select_agent(
+        &self,
+        task: &Task,
+        exclude: &[Uuid],
+        state: &AppState,
+    ) -> Result<Option<Selection>> {
+        let bound = |id: Uuid| {
+            state
+                .agents
+                .get(&id)
+                .and_then(|a| self.registry.resolve(a.value()).cloned())
+        };
+
+        if let Some(id) = self.default_agent {
+            if exclude.contains(&id) {
+                bail!("No other agent can take '{}'", task.name);
+            }
+        }
+        let contract = task
+            .contract_id
+            .and_then(|id| state.contracts.get(&id).map(|c| c.value().clone()))
+            .filter(|c| c.is_fully_signed() && !exclude.contains(&c.delegatee_id))
+            .filter(|c| c.expires_at.is_none_or(|at| at > Utc::now()));
+        if let Some(contract) = contract {
+            let agent_id = contract.delegatee_id;
+            if let Some(binding) = bound(agent_id) {
+                return Ok(self.has_capacity(agent_id, state).then_some(Selection {
+                    agent_id,
+                    binding,
+                    auction: None,
+                }));
+            }
+        }
+
+        let assignee = task
+            .assigned_agent_id
+            .filter(|id| !exclude.contains(id) && bound(*id).is_some());
+        let candidates: Vec<Agent> = match self.default_agent.or(assignee) {
+            Some(id) => state.agents.get(&id).map(|a| a.value().clone()).into_iter().collect(),
+            None => {
+                let bound: Vec<Agent> = state
+                    .agents
+                    .iter()
+                    .map(|e| e.value().clone())
+                    .filter(|a| !exclude.contains(&a.id) && self.registry.resolve(a).is_some())
+                    .collect();
+                let matcher = CapabilityMatcher::new(state.config.min_reputation_threshold);
+                matcher.filter_agents(task, &bound)
+            }
+        };
+        if candidates.is_empty() {
+            bail!(
+                "No {}available agent can take '{}' (requires: {})",
+                if exclude.is_empty() { "" } else { "other " },
+                task.name,
+                if task.required_capabilities.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    task.required_capabilities.join(", ")
+                }
+            );
+        }
+        let free: Vec<Agent> = candidates
+            .into_iter()
+            .filter(|a| self.has_capacity(a.id, state))
+            .collect();
+        if free.is_empty() {
+            return Ok(None);
+        }
+
+        let auction = Auction::run(
+            &state.config.market,
+            task,
+            &free,
+            &|id| state.track_record(id),
+            &|id| state.reputation_engine.get_composite_score(id).unwrap_or(0.5),
+        );
+        let Some(winner) = auction.winner() else {
+            bail!(
+                "No bid for '{}' is within its ${:.2} budget",
+                task.name,
+                auction.rfp.max_cost
+            );
+        };
+        let agent_id = winner.bid.agent_id;
+        let binding = bound(agent_id).ok_or_else(|| anyhow::anyhow!("Agent not found: {agent_id}"))?;
+        Ok(Some(Selection {
+            agent_id,
+            binding,
+            auction: Some(auction),
+        }))
+    }
+}
+
+/// The agent picked for a task and how: the auction it won, or `None` when the task stays
+/// with the agent holding its contract.
+struct Selection {
+    agent_id: Uuid,
+    binding: ExecutorBinding,
+    auction: Option<Auction>,
+}
+
+/// Prefix for a task's output lines, so the interleaved output of concurrent subtasks
+/// stays attributable.
+#[derive(Clone)]
+struct TaskLog {
+    prefix: String,
+}
+
+impl TaskLog {
+    /// Indented lines for a task running on its own.
+    fn plain() -> Self {
+        Self {
+            prefix: "  ".to_string(),
+        }
+    }
+
+    /// Lines tagged with the subtask's (hierarchical) number.
+    fn tagged(number: &str) -> Self {
+        Self {
+            prefix: format!("  [{number}] "),
+        }
+    }
+
+    fn line(&self, message: std::fmt::Arguments<'_>) {
+        println!("{}{}", self.prefix, message);
+    }
+}
+
+/// Per-run options for the `execute` command.
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions {
+    /// How task trees react to a failing subtask; `None` uses the parent's
+    /// `metadata.failure_policy`, else `[failure] policy` from the config.
+    pub on_failure: Option<FailurePolicy>,
+    /// Forecast the run instead of executing it (see `dry_run::handle`).
+    pub dry_run: bool,
+}
 
 /// Handle the `execute` command.
 pub async fn handle(
     id: Option<Uuid>,
     all: bool,
     model: &str,
+    options: &ExecuteOptions,
     state: &AppState,
 ) -> Result<()> {
-    let executor = ClaudeExecutor::default().with_model(model);
+    if options.dry_run {
+        return super::dry_run::handle(id, all, model, state).await;
+    }
+    let fleet = Fleet::build(model, state).await?;
+
+    // Ctrl-C cancels the running execution instead of killing the whole process.
+    let cancel = CancellationToken::new();
+    let interrupt = {
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                println!("\nInterrupted, cancelling execution...");
+                cancel.cancel();
+            }
+        })
+    };
 
-    // Check health.
-    match executor.health_check().await {
-        Ok(true) => {}
-        _ => bail!("claude CLI is not available."),
+    // Priority changes typed while the tasks run reorder the subtasks still waiting.
+    let stop = CancellationToken::new();
+    let (result, ()) = tokio::join!(
+        async {
+            let result = run(id, all, options, &fleet, &cancel, state).await;
+            stop.cancel();
+            result
+        },
+        accept_priority_changes(&stop, state),
+    );
+    interrupt.abort();
+    result
+}
+
+/// Apply the `/task priority` commands typed while tasks run (see `console::lines`) until
+/// `stop` is cancelled. Running trees read priorities afresh before every dispatch, so a
+/// change reorders the subtasks still waiting for a slot.
+async fn accept_priority_changes(stop: &CancellationToken, state: &AppState) {
+    let mut lines = console::lines();
+    loop {
+        let line = tokio::select! {
+            _ = stop.cancelled() => break,
+            line = lines.recv() => line,
+        };
+        let Some(line) = line else {
+            // Nothing can be typed here (stdin is not a terminal, or it was closed).
+            stop.cancelled().await;
+            break;
+        };
+        if line.is_empty() {
+            continue;
+        }
+        if let Err(e) = change_priority(&line, state).await {
+            println!("{e:#}");
+        }
     }
+}
 
-    // Ensure a default Claude agent exists.
-    let agent_id = ensure_default_agent(state, model);
+/// Run a `/task priority` command typed during a run; other commands wait for the prompt.
+async fn change_priority(line: &str, state: &AppState) -> Result<()> {
+    let Some(args) = line.strip_prefix("/task priority ") else {
+        bail!("Only /task priority <uuid> <0.0-1.0> can be entered while tasks run");
+    };
+    let (id, priority) = crate::repl::slash::parse_priority_args(args)?;
+    super::task::handle(TaskAction::Priority { id, priority }, state).await
+}
 
-    if let Some(task_id) = id {
-        // Execute a single task (which may be a parent with subtasks).
-        let task = state
+/// Reopen a finished leaf task with a follow-up request and run it again. The agent's
+/// session is continued when the same agent takes the task, with `message` as the next turn.
+pub async fn followup(id: Uuid, message: String, model: &str, state: &AppState) -> Result<()> {
+    {
+        let entry = state
+            .tasks
+            .get(&id)
+            .ok_or_else(|| anyhow::anyhow!("Task not found: {id}"))?;
+        if !entry.subtask_ids.is_empty() {
+            bail!("Follow-ups go to leaf tasks; '{}' has subtasks", entry.name);
+        }
+    }
+    state.transition(id, TaskEvent::FollowUp, COORDINATOR_ID).await?;
+    state
+        .update_task(id, COORDINATOR_ID, serde_json::json!({}), |task| {
+            task.feedback = Some(message);
+        })
+        .await?;
+    handle(Some(id), false, model, &ExecuteOptions::default(), state).await
+}
+
+/// Execute the selected task(s) until done or cancelled.
+async fn run(
+    id: Option<Uuid>,
+    all: bool,
+    options: &ExecuteOptions,
+    fleet: &Fleet,
+    cancel: &CancellationToken,
+    state: &AppState,
+) -> Result<()> {
+    let mut targets = targets(id, all, state)?;
+    if targets.is_empty() {
+        println!("No pending tasks to execute.");
+        return Ok(());
+    }
+
+    // The most urgent target is taken anew each time, so priorities changed while a tree ran
+    // reorder the ones still waiting.
+    while let Some(task_id) = take_most_urgent(&mut targets, state) {
+        let task = state.tasks.get(&task_id).unwrap().clone();
+        let execution = async {
+            if task.subtask_ids.is_empty() {
+                // Leaf task — execute directly.
+                execute_single_task(task_id, Vec::new(), fleet, cancel, state).await
+            } else {
+                // Parent task — execute all subtasks in dependency order.
+                execute_task_tree(&task, "", Vec::new(), options, fleet, cancel, state).await
+            }
+        };
+        // Tasks already at risk are reported (and moved up) before anything starts.
+        let mut deadlines = DeadlineWatch::default();
+        check_deadlines(task_id, &mut deadlines, fleet, state).await;
+        let stop = CancellationToken::new();
+        let (result, deadlines) = tokio::join!(
+            async {
+                let result = execution.await;
+                stop.cancel();
+                result
+            },
+            watch_deadlines(task_id, deadlines, &stop, fleet, state),
+        );
+        restore_priorities(deadlines.raised, state).await?;
+        result?;
+    }
+
+    Ok(())
+}
+
+/// Remove and return the most urgent of `targets` by their current priorities (see
+/// `urgency_order`); equally urgent targets keep their order.
+fn take_most_urgent(targets: &mut Vec<Uuid>, state: &AppState) -> Option<Uuid> {
+    let tasks: Vec<Option<Task>> = targets
+        .iter()
+        .map(|id| state.tasks.get(id).map(|t| t.value().clone()))
+        .collect();
+    let mut best = 0;
+    for (idx, task) in tasks.iter().enumerate().skip(1) {
+        if let (Some(task), Some(current)) = (task, &tasks[best])
+            && urgency_order(task, current) == std::cmp::Ordering::Less
+        {
+            best = idx;
+        }
+    }
+    (!targets.is_empty()).then(|| targets.remove(best))
+}
+
+/// What the deadline checks of one run have done.
+#[derive(Default)]
+struct DeadlineWatch {
+    /// The status each task was last reported with.
+    reported: HashMap<Uuid, DeadlineStatus>,
+    /// The priority each task had before it was raised for being at risk.
+    raised: HashMap<Uuid, f64>,
+}
+
+/// Give the tasks whose priority a run raised for a deadline at risk their own priority back,
+/// unless it was changed again in the meantime: the raise only orders that run.
+async fn restore_priorities(raised: HashMap<Uuid, f64>, state: &AppState) -> Result<()> {
+    for (task_id, original) in raised {
+        let Some(name) = state
             .tasks
             .get(&task_id)
-            .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?
-            .clone();
+            .filter(|t| t.priority == AT_RISK_PRIORITY && original != AT_RISK_PRIORITY)
+            .map(|t| t.name.clone())
+        else {
+            continue;
+        };
+        state
+            .update_task(
+                task_id,
+                COORDINATOR_ID,
+                serde_json::json!({ "reason": "deadline priority raise ended with the run" }),
+                |task| {
+                    task.priority = original;
+                    task.updated_at = Utc::now();
+                },
+            )
+            .await?;
+        println!("Restored the priority of '{name}' to {original:.2}");
+    }
+    Ok(())
+}
 
-        if task.subtask_ids.is_empty() {
-            // Leaf task — execute directly.
-            execute_single_task(task_id, agent_id, &executor, state).await?;
-        } else {
-            // Parent task — execute all subtasks in dependency order.
-            execute_task_tree(&task, agent_id, &executor, state).await?;
+/// The tasks to execute: the given one, or with `all` every pending root task, most urgent
+/// first.
+pub(crate) fn targets(id: Option<Uuid>, all: bool, state: &AppState) -> Result<Vec<Uuid>> {
+    if let Some(task_id) = id {
+        // Execute a single task (which may be a parent with subtasks).
+        if !state.tasks.contains_key(&task_id) {
+            bail!("Task not found: {task_id}");
         }
+        Ok(vec![task_id])
     } else if all {
-        // Execute all pending root tasks.
-        let root_tasks: Vec<Uuid> = state
+        let mut root_tasks: Vec<Task> = state
             .tasks
             .iter()
             .filter(|e| e.value().parent_id.is_none() && e.value().state == TaskState::Pending)
-            .map(|e| *e.key())
+            .map(|e| e.value().clone())
             .collect();
+        root_tasks.sort_by(urgency_order);
+        Ok(root_tasks.into_iter().map(|t| t.id).collect())
+    } else {
+        bail!("Specify a task ID or use --all");
+    }
+}
 
-        if root_tasks.is_empty() {
-            println!("No pending tasks to execute.");
-            return Ok(());
+/// Check the deadlines of a task and its subtasks every second until `stop` is cancelled
+/// (see `check_deadlines`), and return what the checks did.
+async fn watch_deadlines(
+    root: Uuid,
+    mut watch: DeadlineWatch,
+    stop: &CancellationToken,
+    fleet: &Fleet,
+    state: &AppState,
+) -> DeadlineWatch {
+    loop {
+        tokio::select! {
+            _ = stop.cancelled() => break,
+            _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {}
         }
+        check_deadlines(root, &mut watch, fleet, state).await;
+    }
+    watch
+}
 
-        for task_id in root_tasks {
-            let task = state.tasks.get(&task_id).unwrap().clone();
-            if task.subtask_ids.is_empty() {
-                execute_single_task(task_id, agent_id, &executor, state).await?;
-            } else {
-                execute_task_tree(&task, agent_id, &executor, state).await?;
-            }
+/// Report the unfinished tasks of a tree that have come within `deadlines.warning_secs` of
+/// their deadlines, or missed them, unless already `reported` as such. Each raises a
+/// `DeadlineAtRisk` trigger and the coordinator's response is carried out: a task at risk has
+/// its priority raised, so it starts ahead of the subtasks waiting with it, and is escalated
+/// to the operator. The priority it had before is kept in `watch.raised`.
+async fn check_deadlines(
+    root: Uuid,
+    watch: &mut DeadlineWatch,
+    fleet: &Fleet,
+    state: &AppState,
+) {
+    let now = Utc::now();
+    let mut due = Vec::new();
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        let Some(task) = state.tasks.get(&id) else {
+            continue;
+        };
+        stack.extend(task.subtask_ids.iter().copied());
+        if matches!(task.state, TaskState::Completed | TaskState::Failed) {
+            continue;
+        }
+        let status = state.config.deadlines.status(&task, now);
+        if matches!(status, DeadlineStatus::AtRisk | DeadlineStatus::Missed)
+            && watch.reported.get(&id) != Some(&status)
+        {
+            let deadline = task.deadline.unwrap_or(now);
+            due.push((id, task.name.clone(), deadline, status, task.priority));
         }
-    } else {
-        bail!("Specify a task ID or use --all");
     }
 
-    Ok(())
+    for (task_id, name, deadline, status, priority) in due {
+        watch.reported.insert(task_id, status);
+        let missed = status == DeadlineStatus::Missed;
+        if !missed {
+            watch.raised.entry(task_id).or_insert(priority);
+        }
+        if missed {
+            println!("\nDeadline missed: '{name}' was due {}", deadline.format("%H:%M:%S UTC"));
+        } else {
+            let remaining = (deadline - now).num_seconds().max(0);
+            println!(
+                "\nDeadline at risk: '{name}' is due in {}m {:02}s",
+                remaining / 60,
+                remaining % 60
+            );
+        }
+        let trigger = CoordinationTrigger::Internal(InternalTrigger::DeadlineAtRisk {
+            task_id,
+            deadline,
+            missed,
+        });
+        respond(&trigger, fleet, &TaskLog::plain(), state).await;
+    }
 }
 
-/// Execute subtasks of a parent task in dependency order.
+/// The subtasks ready to start, most urgent first (see `urgency_order`). Priorities are read
+/// afresh on every call, so one changed while the tree runs (typed with `/task priority`, or
+/// raised for a deadline at risk) reorders the subtasks still waiting.
+fn ready_by_urgency(dag: &TaskDag, subtask_ids: &[Uuid], state: &AppState) -> Vec<usize> {
+    let mut ready: Vec<(usize, Option<Task>)> = dag
+        .ready()
+        .into_iter()
+        .map(|idx| (idx, state.tasks.get(&subtask_ids[idx]).map(|t| t.value().clone())))
+        .collect();
+    ready.sort_by(|(_, a), (_, b)| match (a, b) {
+        (Some(a), Some(b)) => urgency_order(a, b),
+        _ => std::cmp::Ordering::Equal,
+    });
+    ready.into_iter().map(|(idx, _)| idx).collect()
+}
+
+/// A running subtask (or nested tree): its index, the agent it runs on (`None` for a nested
+/// tree), and how it ended.
+type SubtaskRun<'a> = LocalBoxFuture<'a, (usize, Option<Uuid>, Result<()>)>;
+
+/// Execute the subtasks of a parent task as a DAG: every subtask whose dependencies have
+/// completed is started, up to the parent's concurrency limit and each agent's capacity.
+/// Subtasks with subtasks of their own are executed the same way, recursively.
+/// Subtasks completed by an earlier run are not repeated. What happens after a subtask
+/// fails is decided by the tree's `FailurePolicy`; the outcome is printed and stored in the
+/// parent's `metadata.run_summary`, and the parent's state is derived from its children.
+///
+/// `number` is the parent's hierarchical number (`""` for a root, else e.g. `"2.1"`), and
+/// `inherited` the dependency outputs handed to the parent, passed on to the subtasks that
+/// have no dependencies of their own.
 async fn execute_task_tree(
-    parent: &crate::types::Task,
-    agent_id: Uuid,
-    executor: &ClaudeExecutor,
+    parent: &Task,
+    number: &str,
+    inherited: Vec<UpstreamOutput>,
+    options: &ExecuteOptions,
+    fleet: &Fleet,
+    cancel: &CancellationToken,
     state: &AppState,
 ) -> Result<()> {
     let subtask_ids = &parent.subtask_ids;
     let total = subtask_ids.len();
+    let label = |idx: usize| subtask_number(number, idx);
+    let mut dag = TaskDag::from_metadata(total, &parent.metadata);
+    let limit = concurrency_limit(&parent.metadata, state.config.max_parallel_tasks);
+    let policy = state
+        .config
+        .failure
+        .policy_for(&parent.metadata, options.on_failure);
+    if limit > 1 && total > 1 {
+        println!("Running up to {limit} subtasks at once.");
+    }
+    if policy != FailurePolicy::default() {
+        println!("On failure: {policy}");
+    }
 
-    // Parse dependencies from parent metadata.
-    let deps: Vec<(usize, usize)> = parent
-        .metadata
-        .get("dependencies")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|d| {
-                    let a = d.as_array()?;
-                    Some((a.first()?.as_u64()? as usize, a.get(1)?.as_u64()? as usize))
-                })
-                .collect()
-        })
-        .unwrap_or_default();
+    for (idx, task_id) in subtask_ids.iter().enumerate() {
+        if let Some(task) = state.tasks.get(task_id).filter(|t| t.state == TaskState::Completed) {
+            println!("Subtask [{}/{total}] already completed: {}", label(idx), task.name);
+            dag.complete(idx);
+        }
+    }
+
+    // Cancelled on Ctrl-C, or by `fail_fast` to stop the subtasks still running.
+    let tree_cancel = cancel.child();
+    let mut running: FuturesUnordered<SubtaskRun<'_>> = FuturesUnordered::new();
+    let mut halted = false;
+    let mut interrupted = None;
+    // Per subtask: failed runs on its current agent, agents it was taken away from, and the
+    // latest failure (agent and reason).
+    let mut attempts = vec![0u32; total];
+    let mut excluded: Vec<Vec<Uuid>> = vec![Vec::new(); total];
+    let mut failures: HashMap<usize, (Option<Uuid>, String)> = HashMap::new();
+    let mut cancelled = Vec::new();
+
+    loop {
+        let mut failed: Vec<(usize, Option<Uuid>, String)> = Vec::new();
+        // Whether a ready subtask had to wait for an agent slot held outside this tree.
+        let mut deferred = false;
 
-    // Simple topological execution: track which indices are completed.
-    let mut completed = vec![false; total];
-    let mut execution_order = Vec::new();
+        if !halted && !tree_cancel.is_cancelled() {
+            for idx in ready_by_urgency(&dag, subtask_ids, state) {
+                if running.len() >= limit {
+                    break;
+                }
+                let task_id = subtask_ids[idx];
+                let Some(task) = state.tasks.get(&task_id).map(|t| t.value().clone()) else {
+                    dag.start(idx);
+                    failed.push((idx, None, "subtask no longer exists".to_string()));
+                    continue;
+                };
+
+                // Hand the results of completed direct dependencies to this subtask; subtasks
+                // without dependencies get what was handed to the parent.
+                let upstream: Vec<UpstreamOutput> = if dag.prerequisites(idx).is_empty() {
+                    inherited.clone()
+                } else {
+                    dag.prerequisites(idx)
+                        .iter()
+                        .filter_map(|&from| state.tasks.get(&subtask_ids[from]))
+                        .filter(|t| t.state == TaskState::Completed)
+                        .map(|t| UpstreamOutput::from_task(&t))
+                        .collect()
+                };
+                let upstream = state.config.upstream.apply(upstream);
+                let show_inputs = |log: &TaskLog| {
+                    if !upstream.is_empty() {
+                        let names: Vec<&str> = upstream.iter().map(|u| u.name.as_str()).collect();
+                        log.line(format_args!("Inputs from: {}", names.join(", ")));
+                    }
+                };
+                let tree_cancel = &tree_cancel;
+
+                if !task.subtask_ids.is_empty() {
+                    println!(
+                        "\nExecuting subtask [{}/{total}]: {} ({} subtasks)",
+                        label(idx),
+                        task.name,
+                        task.subtask_ids.len()
+                    );
+                    show_inputs(&TaskLog::plain());
+                    dag.start(idx);
+                    running.push(
+                        async move {
+                            let number = label(idx);
+                            let result = execute_task_tree(
+                                &task,
+                                &number,
+                                upstream,
+                                options,
+                                fleet,
+                                tree_cancel,
+                                state,
+                            )
+                            .await;
+                            (idx, None, result)
+                        }
+                        .boxed_local(),
+                    );
+                    continue;
+                }
 
-    // Kahn's algorithm for topological sort.
-    let mut in_degree = vec![0usize; total];
-    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); total];
-    for &(from, to) in &deps {
-        if from < total && to < total {
-            adj[from].push(to);
-            in_degree[to] += 1;
+                let selection = match fleet.select_agent(&task, &excluded[idx], state) {
+                    Ok(Some(selection)) => selection,
+                    // Every agent that can take it is busy; try again when a slot frees up.
+                    Ok(None) => {
+                        deferred = true;
+                        continue;
+                    }
+                    Err(e) => {
+                        println!("\nSubtask [{}/{total}] {}: {e}", label(idx), task.name);
+                        dag.start(idx);
+                        failed.push((idx, None, e.to_string()));
+                        continue;
+                    }
+                };
+
+                println!("\nExecuting subtask [{}/{total}]: {}", label(idx), task.name);
+                // Tag lines when siblings (or the subtrees around a nested tree) may interleave.
+                let log = if limit > 1 || !number.is_empty() {
+                    TaskLog::tagged(&label(idx))
+                } else {
+                    TaskLog::plain()
+                };
+                show_inputs(&log);
+
+                dag.start(idx);
+                let agent_id = selection.agent_id;
+                let slot = fleet.claim(agent_id);
+                running.push(
+                    async move {
+                        let _slot = slot;
+                        let result = run_leaf(
+                            task_id,
+                            selection,
+                            upstream,
+                            &log,
+                            fleet,
+                            tree_cancel,
+                            state,
+                        )
+                        .await;
+                        (idx, Some(agent_id), result)
+                    }
+                    .boxed_local(),
+                );
+            }
         }
-    }
 
-    let mut queue: Vec<usize> = (0..total).filter(|&i| in_degree[i] == 0).collect();
-    while let Some(idx) = queue.pop() {
-        execution_order.push(idx);
-        for &next in &adj[idx] {
-            in_degree[next] -= 1;
-            if in_degree[next] == 0 {
-                queue.push(next);
+        if failed.is_empty() {
+            // The slots a deferred subtask waits for may be held by sibling trees rather than
+            // by this tree's own subtasks, so also wake up when any slot is released.
+            let next = if deferred {
+                tokio::select! {
+                    next = running.next(), if !running.is_empty() => next,
+                    () = fleet.slot_freed() => None,
+                }
+            } else {
+                running.next().await
+            };
+            let Some((idx, agent_id, result)) = next else {
+                if deferred {
+                    continue;
+                }
+                break;
+            };
+            let task = state.tasks.get(&subtask_ids[idx]).map(|t| t.value().clone());
+            let completed = task.as_ref().is_some_and(|t| t.state == TaskState::Completed);
+            // Only leaves are redelegated; a nested tree is never left `Pending` by a run.
+            let redelegated = agent_id.is_some()
+                && task.as_ref().is_some_and(|t| t.state == TaskState::Pending);
+            match result {
+                Ok(()) if completed => dag.complete(idx),
+                // The coordinator took it from its agent; run it again on another one.
+                Ok(()) if redelegated => {
+                    println!("\nSubtask [{}/{total}] was redelegated", label(idx));
+                    excluded[idx].extend(agent_id);
+                    dag.requeue(idx);
+                }
+                Ok(()) => {
+                    let reason = task
+                        .and_then(|t| t.failure_reason)
+                        .unwrap_or_else(|| "failed".to_string());
+                    failed.push((idx, agent_id, reason));
+                }
+                Err(e) if cancel.is_cancelled() => {
+                    dag.fail(idx);
+                    interrupted.get_or_insert(e);
+                }
+                Err(_) if tree_cancel.is_cancelled() => {
+                    dag.fail(idx);
+                    let reason = "cancelled: another subtask failed";
+                    set_failure_reason(state, subtask_ids[idx], reason).await?;
+                    cancelled.push(idx);
+                }
+                Err(e) => {
+                    println!("  [{}] Failed: {e}", label(idx));
+                    set_failure_reason(state, subtask_ids[idx], &e.to_string()).await?;
+                    failed.push((idx, agent_id, e.to_string()));
+                }
+            }
+        }
+        refresh_parent_state(state, parent).await?;
+
+        for (idx, agent_id, reason) in failed {
+            dag.fail(idx);
+            // A subtask the coordinator terminated is not tried again.
+            let terminated = reason.starts_with(TERMINATED_PREFIX);
+            failures.insert(idx, (agent_id, reason));
+            let number = format!("[{}/{total}]", label(idx));
+
+            if policy == FailurePolicy::RetryThenRedelegate && !terminated {
+                if let Some(agent_id) = agent_id {
+                    attempts[idx] += 1;
+                    if attempts[idx] <= state.config.failure.max_retries {
+                        println!(
+                            "\nRetrying subtask {number} ({} of {})",
+                            attempts[idx], state.config.failure.max_retries
+                        );
+                        dag.requeue(idx);
+                        continue;
+                    }
+                    if excluded[idx].is_empty() {
+                        println!("\nRedelegating subtask {number} to another agent");
+                        excluded[idx].push(agent_id);
+                        attempts[idx] = 0;
+                        dag.requeue(idx);
+                        continue;
+                    }
+                }
+            }
+
+            match policy {
+                FailurePolicy::FailFast => {
+                    if !halted && !running.is_empty() {
+                        println!("\nSubtask {number} failed; cancelling running subtasks.");
+                    }
+                    halted = true;
+                    tree_cancel.cancel();
+                }
+                FailurePolicy::ContinueIndependent => {
+                    for dependent in dag.dependents(idx) {
+                        if dag.status(dependent) == NodeStatus::Waiting {
+                            block_on(state, subtask_ids[dependent], subtask_ids[idx]).await?;
+                        }
+                    }
+                }
+                FailurePolicy::SkipDependents | FailurePolicy::RetryThenRedelegate => {
+                    for dependent in dag.skip_dependents(idx) {
+                        block_on(state, subtask_ids[dependent], subtask_ids[idx]).await?;
+                        let name = state
+                            .tasks
+                            .get(&subtask_ids[dependent])
+                            .map(|t| t.name.clone())
+                            .unwrap_or_default();
+                        println!(
+                            "Skipping subtask [{}/{total}] {name}: depends on {number}",
+                            label(dependent)
+                        );
+                        state
+                            .record(
+                                LedgerEntryKind::ExecutionCancelled,
+                                parent.id,
+                                subtask_ids[dependent],
+                                serde_json::json!({
+                                    "reason": "dependency_failed",
+                                    "blocked_by": subtask_ids[idx],
+                                    "policy": policy,
+                                }),
+                            )
+                            .await?;
+                    }
+                }
             }
         }
     }
 
-    // Add any remaining (in case of cycles or disconnected nodes).
-    for i in 0..total {
-        if !execution_order.contains(&i) {
-            execution_order.push(i);
+    if let Some(e) = interrupted {
+        return Err(e);
+    }
+
+    let not_started = if halted { dag.skip_waiting() } else { Vec::new() };
+    let stuck = if halted {
+        Vec::new()
+    } else {
+        // Dependents of failed subtasks are waiting on them, not on a cycle.
+        let mut graph = dag.clone();
+        for idx in failures.keys() {
+            graph.skip_dependents(*idx);
         }
+        graph.stuck()
+    };
+    if !stuck.is_empty() {
+        let numbers: Vec<String> = stuck.iter().map(|&i| format!("[{}]", label(i))).collect();
+        println!(
+            "\nSubtasks {} were not run: their dependencies form a cycle.",
+            numbers.join(", ")
+        );
     }
 
-    let mut all_passed = true;
+    let mut summary = run_summary(
+        parent,
+        number,
+        &dag,
+        &failures,
+        &cancelled,
+        &not_started,
+        state,
+    );
+    summary["policy"] = serde_json::json!(policy);
+    print_run_summary(&summary);
+    let completed = dag.count(NodeStatus::Completed);
+    state
+        .update_task(parent.id, COORDINATOR_ID, serde_json::json!({}), |task| {
+            if !task.metadata.is_object() {
+                task.metadata = serde_json::json!({});
+            }
+            task.metadata["run_summary"] = summary;
+            task.failure_reason = (completed < total)
+                .then(|| format!("{} of {total} subtasks did not complete", total - completed));
+        })
+        .await?;
+    refresh_parent_state(state, parent).await?;
 
-    for (step, &idx) in execution_order.iter().enumerate() {
-        if idx >= subtask_ids.len() {
-            continue;
-        }
-        let task_id = subtask_ids[idx];
-        let task_name = state
+    let scope = if number.is_empty() {
+        String::new()
+    } else {
+        format!(" of [{number}]")
+    };
+    if completed == total {
+        println!("\nAll subtasks{scope} completed successfully.");
+        let aggregated = state
             .tasks
-            .get(&task_id)
-            .map(|t| t.value().name.clone())
-            .unwrap_or_default();
+            .get(&parent.id)
+            .is_some_and(|t| t.state == TaskState::Completed);
+        if !aggregated {
+            aggregate(parent.id, fleet, cancel, state).await?;
+        }
+    } else {
+        println!("\nSome subtasks{scope} failed. Use `panopticon status` to review.");
+    }
+    println!("Usage: {}", state.tree_usage(parent.id));
 
-        println!(
-            "\nExecuting subtask [{}/{}]: {}",
-            step + 1,
-            total,
-            task_name
-        );
+    Ok(())
+}
 
-        match execute_single_task(task_id, agent_id, executor, state).await {
-            Ok(()) => {
-                completed[idx] = true;
+/// Combine the outputs of a parent's completed subtasks into the parent's output
+/// (`metadata.output`) and verify it like a leaf's, taking the parent from `InProgress` to
+/// `Completed` or `Failed`.
+async fn aggregate(
+    parent_id: Uuid,
+    fleet: &Fleet,
+    cancel: &CancellationToken,
+    state: &AppState,
+) -> Result<()> {
+    let parent = state
+        .tasks
+        .get(&parent_id)
+        .ok_or_else(|| anyhow::anyhow!("Task not found: {parent_id}"))?
+        .clone();
+    let children: Vec<Task> = parent
+        .subtask_ids
+        .iter()
+        .filter_map(|id| state.tasks.get(id).map(|t| t.value().clone()))
+        .collect();
+    let config = match state.config.aggregation.for_task(&parent.metadata) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Aggregation: {e}");
+            state
+                .transition(parent_id, TaskEvent::TaskFailed, COORDINATOR_ID)
+                .await?;
+            set_failure_reason(state, parent_id, &e).await?;
+            return Ok(());
+        }
+    };
+    println!(
+        "Aggregating the results of {} subtasks ({})...",
+        children.len(),
+        match config.mode {
+            AggregationMode::Merge => format!("merge: {}", config.merge.as_str()),
+            mode => mode.to_string(),
+        }
+    );
+
+    let mut result = match config.mode {
+        AggregationMode::Agent => {
+            match aggregate_with_agent(&parent, &children, &config, fleet, cancel, state).await? {
+                Ok(result) => result,
+                Err(e) => {
+                    println!("  Failed: {e}");
+                    let actor = parent.assigned_agent_id.unwrap_or(COORDINATOR_ID);
+                    state
+                        .transition(parent_id, TaskEvent::TaskFailed, actor)
+                        .await?;
+                    set_failure_reason(state, parent_id, &format!("aggregation failed: {e}")).await?;
+                    if cancel.is_cancelled() {
+                        bail!("Execution interrupted");
+                    }
+                    return Ok(());
+                }
             }
-            Err(e) => {
-                println!("  Failed: {e}");
-                all_passed = false;
-                // Don't continue with dependents.
-                break;
+        }
+        _ => {
+            let outputs: Vec<SubtaskOutput> = children
+                .iter()
+                .map(|t| SubtaskOutput {
+                    name: t.name.clone(),
+                    output: t.output(),
+                })
+                .collect();
+            TaskResult {
+                task_id: parent_id,
+                agent_id: Uuid::nil(),
+                output: combine(&config, &outputs),
+                completed_at: Utc::now(),
+                resource_consumed: 0.0,
+                usage: Default::default(),
+                artifacts: Vec::new(),
+                session_id: None,
             }
         }
+    };
+    result.task_id = parent_id;
+    if !result.usage.is_empty() {
+        state.record_usage(parent_id, result.usage);
     }
 
-    if all_passed {
-        println!("\nAll subtasks completed successfully.");
+    // Combined by an agent, or else by the coordinator itself.
+    let actor = match result.agent_id {
+        id if id.is_nil() => COORDINATOR_ID,
+        id => id,
+    };
+    state
+        .update_task(parent_id, actor, serde_json::json!({}), |task| {
+            task.metadata["output"] = result.output.clone();
+            task.feedback = None;
+        })
+        .await?;
+    state
+        .transition(parent_id, TaskEvent::ExecutionComplete, actor)
+        .await?;
+
+    // Verify like a leaf: against the parent's output schema, or else check for a `result` key.
+    let verifier: Box<dyn Verifier> = if parent.output_schema.is_some() {
+        Box::new(SchemaVerifier)
     } else {
-        println!("\nSome subtasks failed. Use `panopticon status` to review.");
+        Box::new(DirectInspectionVerifier::new(vec!["result".to_string()]))
+    };
+    let outcome = verifier
+        .verify(&parent, &result)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    state
+        .record(
+            LedgerEntryKind::VerificationResult,
+            result.agent_id,
+            parent_id,
+            serde_json::json!({
+                "verifier": verifier.name(),
+                "aggregation": config.mode,
+                "outcome": outcome,
+            }),
+        )
+        .await?;
+
+    let event = match &outcome {
+        VerificationOutcome::Passed { .. } | VerificationOutcome::Inconclusive => {
+            TaskEvent::VerificationPassed
+        }
+        _ => TaskEvent::VerificationFailed,
+    };
+    state.transition(parent_id, event, COORDINATOR_ID).await?;
+    let (failure_reason, feedback) = match &outcome {
+        VerificationOutcome::Passed { .. } | VerificationOutcome::Inconclusive => {
+            match &outcome {
+                VerificationOutcome::Passed { confidence } => {
+                    println!("  Verification: Passed (confidence: {:.1})", confidence)
+                }
+                _ => println!("  Verification: Inconclusive"),
+            }
+            (None, None)
+        }
+        VerificationOutcome::Failed { reason } => {
+            println!("  Aggregation failed verification: {reason}");
+            (
+                Some(format!("aggregation failed verification: {reason}")),
+                Some(format!(
+                    "Your previous combined result did not pass verification: {reason}"
+                )),
+            )
+        }
+        VerificationOutcome::SchemaMismatch { violations } => {
+            println!("  Aggregation does not match the output schema:");
+            let mut feedback = String::from(
+                "Your previous combined result does not match the required output schema:",
+            );
+            for violation in violations {
+                println!("    {violation}");
+                feedback.push_str(&format!("\n- {violation}"));
+            }
+            (
+                Some("aggregated output does not match its schema".to_string()),
+                Some(feedback),
+            )
+        }
+    };
+    state
+        .update_task(parent_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+            task.failure_reason = failure_reason;
+            if feedback.is_some() {
+                task.feedback = feedback;
+            }
+        })
+        .await
+}
+
+/// Run the `aggregate` template on an agent, with the subtasks' outputs as its inputs: the
+/// configured aggregation agent, or else whichever agent would be picked for the parent.
+/// The outer error is for failures of the run itself; the inner one for the agent's.
+async fn aggregate_with_agent(
+    parent: &Task,
+    children: &[Task],
+    config: &AggregationConfig,
+    fleet: &Fleet,
+    cancel: &CancellationToken,
+    state: &AppState,
+) -> Result<std::result::Result<TaskResult, String>> {
+    let (agent_id, binding) = loop {
+        let selection = match &config.agent {
+            Some(name) => {
+                let agent = state
+                    .agents
+                    .iter()
+                    .find(|a| &a.name == name)
+                    .map(|a| a.value().clone());
+                match agent.and_then(|a| fleet.registry.resolve(&a).cloned().map(|b| (a.id, b))) {
+                    Some((id, binding)) => fleet.has_capacity(id, state).then_some((id, binding)),
+                    None => return Ok(Err(format!("aggregation agent {name} is not available"))),
+                }
+            }
+            None => match fleet.select_agent(parent, &[], state) {
+                Ok(selection) => selection.map(|s| (s.agent_id, s.binding)),
+                Err(e) => return Ok(Err(e.to_string())),
+            },
+        };
+        match selection {
+            Some(selection) => break selection,
+            // Busy with sibling subtrees; wait for one of them to release a slot.
+            None => tokio::select! {
+                () = fleet.slot_freed() => {}
+                () = cancel.cancelled() => return Ok(Err("cancelled".to_string())),
+            },
+        }
+    };
+    let _slot = fleet.claim(agent_id);
+    let agent = state
+        .agents
+        .get(&agent_id)
+        .map(|a| a.value().clone())
+        .ok_or_else(|| anyhow::anyhow!("Agent not found: {agent_id}"))?;
+    println!("  Agent: {} ({} {})", agent.name, binding.executor.name(), binding.model);
+
+    let configured_tools = state.config.backend_for(&agent.name).allowed_tools;
+    let policy = match ExecutionPolicy::resolve(&agent, parent, None, &configured_tools, None)
+        .and_then(|policy| policy.enforceable_by(binding.executor.as_ref()))
+    {
+        Ok(policy) => policy,
+        Err(e) => return Ok(Err(e.to_string())),
+    };
+    state
+        .record(
+            LedgerEntryKind::PermissionGranted,
+            agent_id,
+            parent.id,
+            serde_json::json!({
+                "task_name": parent.name,
+                "aggregation": true,
+                "policy": policy,
+            }),
+        )
+        .await?;
+    let template =
+        TemplateStore::new(state.state_dir.as_deref()).source(&state.config.templates.aggregate)?;
+    let inputs: Vec<UpstreamOutput> = children.iter().map(UpstreamOutput::from_task).collect();
+
+    let retrying = RetryingExecutor::new(binding.executor.clone(), binding.retry.clone())
+        .with_ledger(state.ledger.clone());
+    let executor = ValidatingExecutor::new(Arc::new(retrying), state.config.schema_repair_rounds);
+    let mut ctx = binding
+        .context()
+        .with_cancellation(cancel.clone())
+        .with_upstream(state.config.upstream.apply(inputs))
+        .with_policy(policy)
+        .with_prompt_template(template);
+    if let Some(feedback) = &parent.feedback {
+        ctx = ctx.with_feedback(feedback.clone());
     }
+    state.assign(parent.id, Some(agent_id), COORDINATOR_ID).await?;
 
-    Ok(())
+    let start = std::time::Instant::now();
+    if deadline_passed(parent) {
+        println!("  Warning: the deadline has passed; aggregating without a time limit");
+    }
+    let timeout = execution_timeout(parent, None);
+    match executor::execute_with_limits(&executor, parent, &ctx, timeout).await {
+        Ok(mut result) => {
+            result.agent_id = agent_id;
+            if result.usage.duration_ms == 0 {
+                result.usage.duration_ms = start.elapsed().as_millis() as u64;
+            }
+            println!("  Done ({:.1}s)", start.elapsed().as_secs_f64());
+            Ok(Ok(result))
+        }
+        Err(e) => Ok(Err(e.to_string())),
+    }
+}
+
+/// Hierarchical number of a parent's subtask: `"3"` under a root, `"2.1.3"` under `"2.1"`.
+fn subtask_number(parent_number: &str, idx: usize) -> String {
+    if parent_number.is_empty() {
+        (idx + 1).to_string()
+    } else {
+        format!("{parent_number}.{}", idx + 1)
+    }
+}
+
+/// Set a parent's state from its children's states (see `TaskState::rollup`); a parent
+/// whose children have all completed stays `InProgress` until it is aggregated.
+async fn refresh_parent_state(state: &AppState, parent: &Task) -> Result<()> {
+    let children: Vec<TaskState> = parent
+        .subtask_ids
+        .iter()
+        .filter_map(|id| state.tasks.get(id).map(|t| t.state))
+        .collect();
+    let Some(current) = state.tasks.get(&parent.id).map(|t| t.state) else {
+        return Ok(());
+    };
+    let derived = match TaskState::rollup(children) {
+        // Done once its subtasks' outputs are aggregated and verified.
+        TaskState::Completed if current != TaskState::Completed => TaskState::InProgress,
+        derived => derived,
+    };
+    state
+        .set_state(parent.id, derived, COORDINATOR_ID, "derived from its subtasks")
+        .await
+}
+
+/// Record why a task failed.
+async fn set_failure_reason(state: &AppState, task_id: Uuid, reason: &str) -> Result<()> {
+    state
+        .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+            task.failure_reason = Some(reason.to_string());
+        })
+        .await
+}
+
+/// Mark a task as waiting on a failed sibling.
+async fn block_on(state: &AppState, task_id: Uuid, failed_id: Uuid) -> Result<()> {
+    state
+        .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+            task.blocked_by = Some(failed_id);
+        })
+        .await
+}
+
+/// What happened to each subtask of a tree run: which failed (on which agent, and why),
+/// which were skipped or left blocked behind them, and which were cancelled or never started.
+fn run_summary(
+    parent: &Task,
+    number: &str,
+    dag: &TaskDag,
+    failures: &HashMap<usize, (Option<Uuid>, String)>,
+    cancelled: &[usize],
+    not_started: &[usize],
+    state: &AppState,
+) -> serde_json::Value {
+    let blocked_by = |idx: usize| {
+        state
+            .tasks
+            .get(&parent.subtask_ids[idx])
+            .and_then(|t| t.blocked_by)
+    };
+    let describe = |idx: usize| {
+        let id = parent.subtask_ids[idx];
+        let task = state.tasks.get(&id);
+        serde_json::json!({
+            "number": subtask_number(number, idx),
+            "task_id": id,
+            "name": task.as_ref().map(|t| t.name.clone()),
+            "blocked_by": task.as_ref().and_then(|t| t.blocked_by),
+        })
+    };
+    let agent_name = |id: Uuid| state.agents.get(&id).map(|a| a.name.clone());
+
+    let mut failed = Vec::new();
+    let mut skipped = Vec::new();
+    let mut blocked = Vec::new();
+    for idx in 0..dag.len() {
+        match dag.status(idx) {
+            NodeStatus::Failed if !cancelled.contains(&idx) => {
+                let mut entry = describe(idx);
+                if let Some((agent_id, reason)) = failures.get(&idx) {
+                    entry["agent"] = serde_json::json!(agent_id.and_then(agent_name));
+                    entry["reason"] = serde_json::json!(reason);
+                }
+                failed.push(entry);
+            }
+            NodeStatus::Skipped if !not_started.contains(&idx) => skipped.push(describe(idx)),
+            NodeStatus::Waiting if blocked_by(idx).is_some() => blocked.push(describe(idx)),
+            _ => {}
+        }
+    }
+
+    serde_json::json!({
+        "finished_at": Utc::now(),
+        "total": dag.len(),
+        "completed": dag.count(NodeStatus::Completed),
+        "failed": failed,
+        "skipped": skipped,
+        "blocked": blocked,
+        "cancelled": cancelled.iter().map(|&i| describe(i)).collect::<Vec<_>>(),
+        "not_started": not_started.iter().map(|&i| describe(i)).collect::<Vec<_>>(),
+    })
+}
+
+/// Print the failure part of a run summary (nothing when every subtask completed).
+fn print_run_summary(summary: &serde_json::Value) {
+    let list = |key: &str| summary[key].as_array().cloned().unwrap_or_default();
+    let label = |entry: &serde_json::Value| {
+        format!(
+            "[{}] {}",
+            entry["number"].as_str().unwrap_or("?"),
+            entry["name"].as_str().unwrap_or("(missing)")
+        )
+    };
+    let failed = list("failed");
+    if failed.is_empty() {
+        return;
+    }
+
+    println!("\nFailed ({}):", summary["policy"].as_str().unwrap_or(""));
+    for entry in &failed {
+        let reason = entry["reason"].as_str().unwrap_or("");
+        match entry["agent"].as_str() {
+            Some(agent) => println!("  {} on {agent}: {reason}", label(entry)),
+            None => println!("  {}: {reason}", label(entry)),
+        }
+    }
+    let number_of = |id: &serde_json::Value| {
+        summary["failed"]
+            .as_array()
+            .and_then(|f| f.iter().find(|e| &e["task_id"] == id))
+            .map(|e| format!("[{}]", e["number"].as_str().unwrap_or("?")))
+            .unwrap_or_else(|| "a failed subtask".to_string())
+    };
+    for (key, heading) in [("skipped", "Skipped"), ("blocked", "Blocked")] {
+        for entry in list(key) {
+            println!(
+                "  {heading} {}: depends on {}",
+                label(&entry),
+                number_of(&entry["blocked_by"])
+            );
+        }
+    }
+    for entry in list("cancelled") {
+        println!("  Cancelled {}", label(&entry));
+    }
+    for entry in list("not_started") {
+        println!("  Not started {}", label(&entry));
+    }
 }
 
-/// Execute a single leaf task through the full lifecycle.
+/// Execute a single leaf task on the best available agent.
 async fn execute_single_task(
     task_id: Uuid,
-    agent_id: Uuid,
-    executor: &ClaudeExecutor,
+    upstream: Vec<UpstreamOutput>,
+    fleet: &Fleet,
+    cancel: &CancellationToken,
+    state: &AppState,
+) -> Result<()> {
+    let task = state
+        .tasks
+        .get(&task_id)
+        .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?
+        .clone();
+    let selection = fleet
+        .select_agent(&task, &[], state)?
+        .ok_or_else(|| anyhow::anyhow!("Every agent that can take '{}' is busy", task.name))?;
+    let _slot = fleet.claim(selection.agent_id);
+    run_leaf(
+        task_id,
+        selection,
+        upstream,
+        &TaskLog::plain(),
+        fleet,
+        cancel,
+        state,
+    )
+    .await
+}
+
+/// Execute a leaf task on the selected agent through the full lifecycle, given the results
+/// of its completed dependencies: the agent's contract is negotiated (or the held one kept)
+/// and the task runs under it. Output lines go through `log`.
+async fn run_leaf(
+    task_id: Uuid,
+    selection: Selection,
+    upstream: Vec<UpstreamOutput>,
+    log: &TaskLog,
+    fleet: &Fleet,
+    cancel: &CancellationToken,
     state: &AppState,
 ) -> Result<()> {
+    let task = state
+        .tasks
+        .get(&task_id)
+        .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?
+        .clone();
+    let Selection {
+        agent_id,
+        binding,
+        auction,
+    } = selection;
+    let previous_agent = task.assigned_agent_id;
+    let retrying = RetryingExecutor::new(binding.executor.clone(), binding.retry.clone())
+        .with_ledger(state.ledger.clone());
+    let executor = ValidatingExecutor::new(Arc::new(retrying), state.config.schema_repair_rounds);
+
     // Get agent info for display.
     let agent_name = state
         .agents
@@ -188,74 +1489,348 @@ async fn execute_single_task(
         .get_composite_score(agent_id)
         .unwrap_or(0.5);
 
-    println!("  Agent: {} (reputation: {:.3})", agent_name, reputation);
+    if let Some(auction) = &auction {
+        print_auction(auction, log, state);
+    }
+    log.line(format_args!(
+        "Agent: {} (reputation: {:.3}, {} {})",
+        agent_name,
+        reputation,
+        executor.name(),
+        binding.model
+    ));
 
-    // Walk through state machine.
-    // Pending → AwaitingAssignment (skip decomposition for leaf tasks).
+    // Refuse to run once the agent has exhausted its cost budget.
+    let budget = state
+        .agents
+        .get(&agent_id)
+        .map(|a| a.value().permissions.max_cost_budget)
+        .unwrap_or(f64::INFINITY);
+    let spent = state.agent_usage(agent_id).cost_usd;
+    if spent >= budget {
+        bail!(
+            "{} has spent ${:.4} of its ${:.2} cost budget",
+            agent_name,
+            spent,
+            budget
+        );
+    }
+
+    // Pick the prompt template for this task and agent; a broken template fails before any state changes.
+    let template_name = state.config.templates.select(&task, &agent_name);
+    let template = TemplateStore::new(state.state_dir.as_deref()).source(template_name)?;
+    if template_name != state.config.templates.task {
+        log.line(format_args!("Template: {template_name}"));
+    }
+
+    // Run in the agent's configured directory, or else in the task's own workspace.
+    let workspace = match (&binding.working_dir, &state.state_dir) {
+        (Some(dir), _) => Some(Workspace::at(dir)),
+        (None, Some(state_dir)) => Some(Workspace::for_task(state_dir, task_id)?),
+        (None, None) => None,
+    }
+    .map(|ws| ws.with_config(&state.config.workspace));
+
+    let agent = state
+        .agents
+        .get(&agent_id)
+        .map(|a| a.value().clone())
+        .ok_or_else(|| anyhow::anyhow!("Agent not found: {agent_id}"))?;
+
+    // The contract the task runs under: a draft awarding it to the auction's winner (signed
+    // once negotiation completes), or the one it already holds.
+    let contract = match &auction {
+        Some(auction) => {
+            let winner = auction
+                .winner()
+                .ok_or_else(|| anyhow::anyhow!("No bid won '{}'", task.name))?;
+            state
+                .config
+                .market
+                .contract(&task, winner, &agent)
+                .map_err(|e| anyhow::anyhow!("Cannot award '{}' to {}: {e}", task.name, agent_name))?
+        }
+        None => task
+            .contract_id
+            .and_then(|id| state.contracts.get(&id).map(|c| c.value().clone()))
+            .ok_or_else(|| anyhow::anyhow!("'{}' holds no contract", task.name))?,
+    };
+
+    // Map the agent's permissions, narrowed by the contract, onto executor controls; a
+    // conflict, or a restriction the backend cannot enforce, fails before any state changes.
+    let configured_tools = state.config.backend_for(&agent.name).allowed_tools;
+    let workspace_dir = workspace
+        .as_ref()
+        .map(|ws| ws.root().to_string_lossy().into_owned());
+    let policy = match ExecutionPolicy::resolve(
+        &agent,
+        &task,
+        Some(&contract),
+        &configured_tools,
+        workspace_dir.as_deref(),
+    )
+    .and_then(|policy| policy.enforceable_by(binding.executor.as_ref()))
     {
-        let mut entry = state
-            .tasks
-            .get_mut(&task_id)
-            .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?;
-        if entry.state == TaskState::Pending {
-            entry.apply_event(TaskEvent::SkipDecomposition)?;
+        Ok(policy) => policy,
+        Err(e) => {
+            state
+                .record(
+                    LedgerEntryKind::SecurityAlert,
+                    agent_id,
+                    task_id,
+                    serde_json::json!({
+                        "reason": "policy_conflict",
+                        "task_name": task.name,
+                        "error": e.to_string(),
+                    }),
+                )
+                .await?;
+            bail!("Refusing to run '{}' on {}: {e}", task.name, agent_name);
+        }
+    };
+    state
+        .record(
+            LedgerEntryKind::PermissionGranted,
+            agent_id,
+            task_id,
+            serde_json::json!({
+                "task_name": task.name,
+                "contract_id": contract.id,
+                "policy": policy,
+            }),
+        )
+        .await?;
+    log.line(format_args!(
+        "Actions: {}",
+        if policy.allowed_actions.is_empty() {
+            "none".to_string()
+        } else {
+            policy.allowed_actions.join(", ")
         }
+    ));
+
+    let before = match &workspace {
+        Some(ws) => ws.snapshot()?,
+        None => Default::default(),
+    };
+    if let Some(ws) = &workspace {
+        let root = ws.root().to_string_lossy().into_owned();
+        state
+            .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+                task.workspace = Some(root);
+            })
+            .await?;
+    }
+
+    // Walk through state machine.
+    // Failed → Pending (re-running a failed task retries it).
+    // Pending → AwaitingAssignment (skip decomposition for leaf tasks).
+    let current = |state: &AppState| state.tasks.get(&task_id).map(|t| t.state);
+    if current(state) == Some(TaskState::Failed) {
+        state.transition(task_id, TaskEvent::Retry, COORDINATOR_ID).await?;
+    }
+    state
+        .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+            task.blocked_by = None;
+        })
+        .await?;
+    if current(state) == Some(TaskState::Pending) {
+        state
+            .transition(task_id, TaskEvent::SkipDecomposition, COORDINATOR_ID)
+            .await?;
     }
 
     // AwaitingAssignment → Negotiating → Contracted → InProgress
-    {
-        let mut entry = state.tasks.get_mut(&task_id).unwrap();
-        entry.assigned_agent_id = Some(agent_id);
-        if entry.state == TaskState::AwaitingAssignment {
-            entry.apply_event(TaskEvent::StartNegotiation)?;
-        }
+    state.assign(task_id, Some(agent_id), COORDINATOR_ID).await?;
+    if current(state) == Some(TaskState::AwaitingAssignment) {
+        state
+            .transition(task_id, TaskEvent::StartNegotiation, COORDINATOR_ID)
+            .await?;
     }
-    {
-        let mut entry = state.tasks.get_mut(&task_id).unwrap();
-        if entry.state == TaskState::Negotiating {
-            entry.apply_event(TaskEvent::NegotiationComplete)?;
+    let contract = match &auction {
+        Some(auction) => negotiate(&task, auction, contract, log, state).await?,
+        None => {
+            log.line(format_args!("Contract: {} (held)", contract.id));
+            contract
         }
+    };
+    state
+        .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+            task.contract_id = Some(contract.id);
+        })
+        .await?;
+    if current(state) == Some(TaskState::Negotiating) {
+        state
+            .transition(task_id, TaskEvent::NegotiationComplete, COORDINATOR_ID)
+            .await?;
     }
-    // ContractSigned to get to Contracted is not needed — NegotiationComplete already goes to Contracted.
-    {
-        let mut entry = state.tasks.get_mut(&task_id).unwrap();
-        if entry.state == TaskState::Contracted {
-            entry.apply_event(TaskEvent::StartExecution)?;
-        }
+    if current(state) == Some(TaskState::Contracted) {
+        state
+            .transition(task_id, TaskEvent::StartExecution, agent_id)
+            .await?;
     }
 
     // Actually execute.
     let task = state.tasks.get(&task_id).unwrap().clone();
-    let ctx = ExecutionContext::default();
 
-    print!("  Running...");
+    // Stream live checkpoints from the executor, shown as progress lines and watched under
+    // the contract's monitoring terms. The execution is cancelled on its own when the
+    // coordinator takes the task away from the agent.
+    let (checkpoint_tx, checkpoint_rx) = mpsc::channel::<Checkpoint>(64);
+    let task_cancel = cancel.child();
+    let mut ctx = binding
+        .context()
+        .with_checkpoints(agent_id, checkpoint_tx)
+        .with_cancellation(task_cancel.clone())
+        .with_upstream(upstream)
+        .with_contract(contract.clone())
+        .with_policy(policy)
+        .with_prompt_template(template);
+    ctx.working_dir = task.workspace.clone();
+    // Hand over feedback from the previous run, continuing the agent's session when the same
+    // agent takes the task again (sessions belong to the backend that created them).
+    if let Some(feedback) = &task.feedback {
+        ctx = ctx.with_feedback(feedback.clone());
+        match task
+            .session_id
+            .as_ref()
+            .filter(|_| previous_agent == Some(agent_id))
+        {
+            Some(session_id) => {
+                log.line(format_args!("Resuming session {session_id} with feedback"));
+                ctx = ctx.with_session(session_id.clone());
+            }
+            None => log.line(format_args!("With feedback from the previous run")),
+        }
+    }
+    if deadline_passed(&task) {
+        log.line(format_args!(
+            "Warning: the deadline has passed; running without a deadline limit"
+        ));
+    }
+    let timeout = execution_timeout(&task, Some(&contract.monitoring));
+    let progress_log = log.clone();
+    let events = watch_execution(
+        agent_id,
+        &contract.monitoring,
+        &state.config.monitoring,
+        executor.reports_checkpoints(),
+        checkpoint_rx,
+        move |checkpoint| {
+            progress_log.line(format_args!(
+                "  [{:>3.0}%] {}",
+                checkpoint.progress_pct * 100.0,
+                checkpoint.status_message
+            ))
+        },
+        state.ledger.clone(),
+    );
+
+    match timeout {
+        Some(limit) => log.line(format_args!("Running (timeout {:.0}s)...", limit.as_secs_f64())),
+        None => log.line(format_args!("Running...")),
+    }
     let _ = std::io::stdout().flush();
     let start = std::time::Instant::now();
-    let exec_result = executor.execute(&task, &ctx).await;
-    let elapsed = start.elapsed();
+    let ((exec_result, elapsed), ()) = tokio::join!(
+        async {
+            let result = executor::execute_with_limits(&executor, &task, &ctx, timeout).await;
+            // Dropping the context closes the checkpoint channel, which ends the watch once
+            // every checkpoint has been checked.
+            drop(ctx);
+            (result, start.elapsed())
+        },
+        respond_to_monitoring(task_id, events, &task_cancel, log, fleet, state),
+    );
+
+    // The coordinator terminated or redelegated the task while it ran.
+    if state.tasks.get(&task_id).is_some_and(|t| t.state != TaskState::InProgress) {
+        if let Ok(result) = &exec_result {
+            state.record_usage(task_id, result.usage);
+        }
+        log.line(format_args!(
+            "Stopped by the coordinator ({:.1}s)",
+            elapsed.as_secs_f64()
+        ));
+        return Ok(());
+    }
 
     match exec_result {
         Ok(mut result) => {
             result.agent_id = agent_id;
-            println!(" done ({:.1}s)", elapsed.as_secs_f64());
+            log.line(format_args!("Done ({:.1}s)", elapsed.as_secs_f64()));
 
-            // ExecutionComplete.
-            {
-                let mut entry = state.tasks.get_mut(&task_id).unwrap();
-                entry.metadata = result.output.clone();
-                entry.apply_event(TaskEvent::ExecutionComplete)?;
+            if result.usage.duration_ms == 0 {
+                result.usage.duration_ms = elapsed.as_millis() as u64;
+            }
+            if let Some(ws) = &workspace {
+                match ws.artifacts_since(&before) {
+                    Ok(artifacts) => result.artifacts = artifacts,
+                    Err(e) => log.line(format_args!("Warning: could not capture artifacts: {e}")),
+                }
             }
+            if !result.artifacts.is_empty() {
+                log.line(format_args!("Artifacts ({}):", result.artifacts.len()));
+                for artifact in &result.artifacts {
+                    log.line(format_args!("  {artifact}"));
+                }
+            }
+            state.record_usage(task_id, result.usage);
+            log.line(format_args!("Usage: {}", result.usage));
+            let spent = state.agent_usage(agent_id).cost_usd;
+            if spent > budget {
+                log.line(format_args!(
+                    "Warning: {} is over its cost budget (${:.4} of ${:.2})",
+                    agent_name, spent, budget
+                ));
+            }
+
+            // ExecutionComplete.
+            state
+                .update_task(task_id, agent_id, serde_json::json!({}), |task| {
+                    task.metadata = result.output.clone();
+                    task.artifacts = result.artifacts.clone();
+                    task.feedback = None;
+                    if result.session_id.is_some() {
+                        task.session_id = result.session_id.clone();
+                    }
+                })
+                .await?;
+            state
+                .transition(task_id, TaskEvent::ExecutionComplete, agent_id)
+                .await?;
 
-            // Verify.
-            let verifier = DirectInspectionVerifier::new(vec!["result".to_string()]);
+            // Verify against the task's output schema, or else check for a `result` key.
+            let verifier: Box<dyn Verifier> = if task.output_schema.is_some() {
+                Box::new(SchemaVerifier)
+            } else {
+                Box::new(DirectInspectionVerifier::new(vec!["result".to_string()]))
+            };
             let outcome = verifier.verify(&task, &result).await.map_err(|e| anyhow::anyhow!("{e}"))?;
+            state
+                .record(
+                    LedgerEntryKind::VerificationResult,
+                    agent_id,
+                    task_id,
+                    serde_json::json!({
+                        "verifier": verifier.name(),
+                        "outcome": outcome,
+                    }),
+                )
+                .await?;
 
             match &outcome {
                 VerificationOutcome::Passed { confidence } => {
-                    println!("  Verification: Passed (confidence: {:.1})", confidence);
+                    log.line(format_args!("Verification: Passed (confidence: {:.1})", confidence));
 
-                    let mut entry = state.tasks.get_mut(&task_id).unwrap();
-                    entry.apply_event(TaskEvent::VerificationPassed)?;
+                    state
+                        .transition(task_id, TaskEvent::VerificationPassed, COORDINATOR_ID)
+                        .await?;
+                    state
+                        .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+                            task.failure_reason = None;
+                        })
+                        .await?;
 
                     // Update reputation positively.
                     let obs = ReputationObservation {
@@ -276,11 +1851,39 @@ async fn execute_single_task(
                     };
                     let _ = state.reputation_engine.update_reputation(obs).await;
                 }
-                VerificationOutcome::Failed { reason } => {
-                    println!("  Verification: Failed ({})", reason);
+                VerificationOutcome::Failed { .. } | VerificationOutcome::SchemaMismatch { .. } => {
+                    let (reason, feedback) = match &outcome {
+                        VerificationOutcome::SchemaMismatch { violations } => {
+                            log.line(format_args!("Verification: Failed (output does not match its schema)"));
+                            let mut feedback = String::from(
+                                "Your previous result does not match the required output schema:",
+                            );
+                            for violation in violations {
+                                log.line(format_args!("  {violation}"));
+                                feedback.push_str(&format!("\n- {violation}"));
+                            }
+                            ("output does not match its schema".to_string(), feedback)
+                        }
+                        VerificationOutcome::Failed { reason } => {
+                            log.line(format_args!("Verification: Failed ({})", reason));
+                            (
+                                format!("verification failed: {reason}"),
+                                format!("Your previous result did not pass verification: {reason}"),
+                            )
+                        }
+                        _ => Default::default(),
+                    };
 
-                    let mut entry = state.tasks.get_mut(&task_id).unwrap();
-                    entry.apply_event(TaskEvent::VerificationFailed)?;
+                    state
+                        .transition(task_id, TaskEvent::VerificationFailed, COORDINATOR_ID)
+                        .await?;
+                    state
+                        .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+                            task.failure_reason = Some(reason);
+                            // Sent to the agent when the task is retried.
+                            task.feedback = Some(feedback);
+                        })
+                        .await?;
 
                     // Update reputation negatively.
                     let obs = ReputationObservation {
@@ -292,62 +1895,551 @@ async fn execute_single_task(
                     };
                     let _ = state.reputation_engine.update_reputation(obs).await;
 
-                    println!("  Hint: Use `panopticon task transition {} --event Retry` to retry", task_id);
+                    log.line(format_args!(
+                        "Hint: `/execute {task_id}` retries with this feedback; \
+                         `/task followup {task_id} <message>` adds your own"
+                    ));
                 }
                 VerificationOutcome::Inconclusive => {
-                    println!("  Verification: Inconclusive");
+                    log.line(format_args!("Verification: Inconclusive"));
 
-                    let mut entry = state.tasks.get_mut(&task_id).unwrap();
-                    entry.apply_event(TaskEvent::VerificationPassed)?;
+                    state
+                        .transition(task_id, TaskEvent::VerificationPassed, COORDINATOR_ID)
+                        .await?;
+                    state
+                        .update_task(task_id, COORDINATOR_ID, serde_json::json!({}), |task| {
+                            task.failure_reason = None;
+                        })
+                        .await?;
                 }
             }
         }
         Err(e) => {
-            println!(" failed ({:.1}s)", elapsed.as_secs_f64());
-            println!("  Error: {e}");
+            log.line(format_args!("Failed ({:.1}s)", elapsed.as_secs_f64()));
+            log.line(format_args!("Error: {e}"));
 
-            let mut entry = state.tasks.get_mut(&task_id).unwrap();
-            entry.apply_event(TaskEvent::TaskFailed)?;
+            state
+                .transition(task_id, TaskEvent::TaskFailed, agent_id)
+                .await?;
+            set_failure_reason(state, task_id, &e.to_string()).await?;
+
+            // Record why a timed-out or cancelled execution was abandoned.
+            let reason = match &e {
+                PanopticonError::Executor {
+                    kind: ExecutorErrorKind::TimedOut,
+                    ..
+                } => Some("timeout"),
+                PanopticonError::Executor {
+                    kind: ExecutorErrorKind::Cancelled,
+                    ..
+                } => Some("interrupted"),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                state
+                    .record(
+                        LedgerEntryKind::ExecutionCancelled,
+                        agent_id,
+                        task_id,
+                        serde_json::json!({
+                            "reason": reason,
+                            "task_name": task.name,
+                            "elapsed_ms": elapsed.as_millis() as u64,
+                            "timeout_ms": timeout.map(|t| t.as_millis() as u64),
+                            "new_state": TaskState::Failed,
+                        }),
+                    )
+                    .await?;
+            }
+            if cancel.is_cancelled() {
+                bail!("Execution interrupted");
+            }
         }
     }
 
     Ok(())
 }
 
-/// Ensure a default Claude agent is registered in the state.
-fn ensure_default_agent(state: &AppState, model: &str) -> Uuid {
-    let agent_name = format!("claude-{model}");
+/// Raise a coordination trigger for each problem the watch of a task's execution reports and
+/// carry out the coordinator's response plan. Each problem is acted on once (an unresponsive
+/// agent again after it reports back), and the execution is cancelled once the response takes
+/// the task away from its agent.
+async fn respond_to_monitoring(
+    task_id: Uuid,
+    mut events: mpsc::Receiver<MonitoringEvent>,
+    task_cancel: &CancellationToken,
+    log: &TaskLog,
+    fleet: &Fleet,
+    state: &AppState,
+) {
+    let mut handled = HashSet::new();
+    while let Some(event) = events.recv().await {
+        let problem = match &event {
+            MonitoringEvent::CheckpointReceived { agent_id, .. } => {
+                handled.remove(&agent_id.to_string());
+                continue;
+            }
+            MonitoringEvent::SloViolation { violation, .. } => {
+                violation.definition.metric_name.clone()
+            }
+            MonitoringEvent::AgentUnresponsive { agent_id, .. } => agent_id.to_string(),
+            MonitoringEvent::TaskTimeout { .. } => continue,
+        };
+        if task_cancel.is_cancelled() || !handled.insert(problem) {
+            continue;
+        }
+        let Some(trigger) = CoordinationTrigger::from_monitoring(&event) else {
+            continue;
+        };
 
-    // Check if already registered.
-    for entry in state.agents.iter() {
-        if entry.value().name == agent_name {
-            return *entry.key();
+        respond(&trigger, fleet, log, state).await;
+        if state
+            .tasks
+            .get(&task_id)
+            .is_some_and(|t| t.state != TaskState::InProgress)
+        {
+            task_cancel.cancel();
         }
     }
+}
 
-    // Register a new default Claude agent.
-    let mut agent = Agent::new(&agent_name);
-    agent.capabilities.capabilities.push(Capability {
-        name: "general".to_string(),
-        proficiency: 0.8,
-        certified: false,
-        last_verified: Some(Utc::now()),
-    });
-    agent.capabilities.capabilities.push(Capability {
-        name: "code".to_string(),
-        proficiency: 0.9,
-        certified: false,
-        last_verified: Some(Utc::now()),
-    });
-    agent.capabilities.capabilities.push(Capability {
-        name: "analysis".to_string(),
-        proficiency: 0.85,
-        certified: false,
-        last_verified: Some(Utc::now()),
+/// Have the coordinator respond to a trigger, and show its response and what it changed.
+/// Tasks are only redelegated to agents the fleet can run them on.
+async fn respond(trigger: &CoordinationTrigger, fleet: &Fleet, log: &TaskLog, state: &AppState) {
+    let plan = Coordinator::handle_trigger(trigger);
+    log.line(format_args!("Coordinator: {}", plan.justification));
+    let executor = state
+        .response_executor()
+        .with_eligible_agents(fleet.bound_agents(state));
+    match executor.execute(&plan).await {
+        Ok(outcomes) => {
+            for outcome in &outcomes {
+                log.line(format_args!("  {}", outcome.summary));
+            }
+        }
+        Err(e) => log.line(format_args!("  Response failed: {e}")),
+    }
+}
+
+/// Show a task's auction: the request, each bid with its score (or why it was rejected),
+/// and the winner.
+fn print_auction(auction: &Auction, log: &TaskLog, state: &AppState) {
+    let name = |id: Uuid| {
+        state
+            .agents
+            .get(&id)
+            .map(|a| a.value().name.clone())
+            .unwrap_or_else(|| id.to_string())
+    };
+    log.line(format_args!(
+        "RFP: budget ${:.2}, requires {}; {} bid{}",
+        auction.rfp.max_cost,
+        if auction.rfp.required_capabilities.is_empty() {
+            "nothing".to_string()
+        } else {
+            auction.rfp.required_capabilities.join(", ")
+        },
+        auction.bids.len(),
+        if auction.bids.len() == 1 { "" } else { "s" }
+    ));
+    for bid in &auction.bids {
+        let standing = match auction.rank_of(bid.agent_id) {
+            Some(rank) => format!("score {:.3}", auction.ranking[rank].total_score),
+            None => "over budget".to_string(),
+        };
+        log.line(format_args!(
+            "  Bid from {}: ${:.4}, ~{}s, confidence {:.2} ({standing})",
+            name(bid.agent_id),
+            bid.proposed_cost,
+            bid.proposed_duration_secs,
+            bid.confidence_score
+        ));
+    }
+    if let Some(winner) = auction.winner() {
+        log.line(format_args!("Awarded to {}", name(winner.bid.agent_id)));
+    }
+}
+
+/// Record a task's auction in the ledger, then sign the drafted contract on both sides and
+/// store it. Returns the signed contract.
+async fn negotiate(
+    task: &Task,
+    auction: &Auction,
+    mut contract: DelegationContract,
+    log: &TaskLog,
+    state: &AppState,
+) -> Result<DelegationContract> {
+    state
+        .record(
+            LedgerEntryKind::DelegationRequested,
+            COORDINATOR_ID,
+            task.id,
+            serde_json::json!({
+                "task_name": task.name,
+                "rfp": auction.rfp,
+            }),
+        )
+        .await?;
+    for bid in &auction.bids {
+        let rank = auction.rank_of(bid.agent_id);
+        state
+            .record(
+                LedgerEntryKind::BidSubmitted,
+                bid.agent_id,
+                task.id,
+                serde_json::json!({
+                    "bid": bid,
+                    "rank": rank,
+                    "score": rank.map(|r| auction.ranking[r].total_score),
+                }),
+            )
+            .await?;
+    }
+    state
+        .record_mutation(
+            LedgerEntryKind::ContractCreated,
+            COORDINATOR_ID,
+            contract.id,
+            serde_json::Value::Null,
+            serde_json::to_value(&contract)?,
+            serde_json::json!({
+                "task_id": task.id,
+                "task_name": task.name,
+            }),
+        )
+        .await?;
+
+    contract.signed_by_delegator = true;
+    contract.signed_by_delegatee = true;
+    for (party, signer) in [
+        ("delegator", contract.delegator_id),
+        ("delegatee", contract.delegatee_id),
+    ] {
+        state
+            .record(
+                LedgerEntryKind::ContractSigned,
+                signer,
+                contract.id,
+                serde_json::json!({
+                    "task_id": task.id,
+                    "party": party,
+                }),
+            )
+            .await?;
+    }
+    log.line(format_args!(
+        "Contract: {} signed (${:.4}{})",
+        contract.id,
+        contract.payment.total_amount,
+        match contract.expires_at {
+            Some(at) => format!(", expires {}", at.format("%Y-%m-%d %H:%M UTC")),
+            None => String::new(),
+        }
+    ));
+    state.contracts.insert(contract.id, contract.clone());
+    Ok(contract)
+}
+
+/// The default Claude agent as a run would have it: the registered one if there is one, else
+/// a new one. Either way it is permitted the configured actions.
+pub(crate) fn default_agent(state: &AppState, model: &str) -> Agent {
+    let agent_name = format!("claude-{model}");
+    let existing = state
+        .agents
+        .iter()
+        .find(|e| e.value().name == agent_name)
+        .map(|e| e.value().clone());
+    let mut agent = existing.unwrap_or_else(|| {
+        let mut agent = Agent::new(&agent_name);
+        for (name, proficiency) in [("general", 0.8), ("code", 0.9), ("analysis", 0.85)] {
+            agent.capabilities.capabilities.push(Capability {
+                name: name.to_string(),
+                proficiency,
+                certified: false,
+                last_verified: Some(Utc::now()),
+            });
+        }
+        agent
     });
+    agent.permissions.allowed_actions = state.config.allowed_actions.clone();
+    agent
+}
 
+/// Ensure a default Claude agent is registered in the state, permitted the configured actions.
+async fn ensure_default_agent(state: &AppState, model: &str) -> Result<Uuid> {
+    let agent = default_agent(state, model);
     let id = agent.id;
-    state.agents.insert(id, agent);
+    if state.agents.contains_key(&id) {
+        // Already registered: keep its permissions in sync with the config.
+        state.update_agent(agent, COORDINATOR_ID).await?;
+        return Ok(id);
+    }
+    let agent_name = agent.name.clone();
+    state.register_agent(agent, COORDINATOR_ID).await?;
     println!("Auto-registered agent: {} ({})", agent_name, id);
-    id
+    Ok(id)
+}
+
+/// Every agent from `[[agents]]` in the config as a run would have it: existing agents with
+/// their capabilities, permitted actions, and capacity synced from the config, and new agents
+/// for the rest.
+pub(crate) fn configured_agents(state: &AppState) -> Vec<Agent> {
+    state
+        .config
+        .agents
+        .iter()
+        .map(|config| {
+            let capabilities: Vec<Capability> = config
+                .capabilities
+                .iter()
+                .map(|(name, proficiency)| Capability {
+                    name: name.clone(),
+                    proficiency: proficiency.clamp(0.0, 1.0),
+                    certified: false,
+                    last_verified: Some(Utc::now()),
+                })
+                .collect();
+            let mut agent = state
+                .agents
+                .iter()
+                .find(|e| e.value().name == config.name)
+                .map(|e| e.value().clone())
+                .unwrap_or_else(|| Agent::new(&config.name));
+            agent.capabilities.capabilities = capabilities;
+            agent.permissions.allowed_actions = config
+                .allowed_actions
+                .clone()
+                .unwrap_or_else(|| state.config.allowed_actions.clone());
+            if let Some(max) = config.max_concurrent_tasks {
+                agent.max_concurrent_tasks = max;
+            }
+            agent
+        })
+        .collect()
+}
+
+/// Register every agent from `[[agents]]` in the config, syncing capabilities, permitted
+/// actions, and capacity of agents that already exist.
+async fn ensure_configured_agents(state: &AppState) -> Result<()> {
+    for agent in configured_agents(state) {
+        if state.agents.contains_key(&agent.id) {
+            state.update_agent(agent, COORDINATOR_ID).await?;
+        } else {
+            println!("Registered configured agent: {} ({})", agent.name, agent.id);
+            state.register_agent(agent, COORDINATOR_ID).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentConfig;
+    use crate::executor::{ScriptedFixtures, ScriptedRule};
+
+    fn subtask(parent: &mut Task, name: &str) -> Task {
+        let mut task = Task::new(name, name);
+        task.parent_id = Some(parent.id);
+        parent.subtask_ids.push(task.id);
+        task
+    }
+
+    /// State whose default agent runs on the scripted executor with `rules`.
+    fn scripted_state(dir: &std::path::Path, rules: Vec<ScriptedRule>) -> AppState {
+        let fixtures_path = dir.join("fixtures.json");
+        let fixtures = ScriptedFixtures { rules };
+        std::fs::write(&fixtures_path, serde_json::to_string(&fixtures).unwrap()).unwrap();
+
+        let mut state = AppState::new();
+        state.config.executor = "scripted".to_string();
+        state.config.scripted_fixtures = Some(fixtures_path.display().to_string());
+        state
+    }
+
+    /// Names of the tasks in the order their executions started.
+    async fn start_order(state: &AppState) -> Vec<String> {
+        state
+            .ledger
+            .query_by_kind(LedgerEntryKind::TaskStateChanged)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|e| e.payload["event"] == "StartExecution")
+            .map(|e| e.payload["task_name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_nested_tree_waits_for_slots_held_by_siblings() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = scripted_state(
+            dir.path(),
+            vec![
+                ScriptedRule::new("nested 2").with_failure("broken"),
+                ScriptedRule::new("*").with_delay_ms(50),
+            ],
+        );
+        // The default agent runs 3 tasks at once, fewer than the tree may start.
+        state.config.max_parallel_tasks = 4;
+
+        // Three leaves take every slot of the default agent before the nested tree starts.
+        let mut root = Task::new("root", "r");
+        let mut leaves: Vec<Task> = ["a", "b", "c"].iter().map(|n| subtask(&mut root, n)).collect();
+        let mut nested = subtask(&mut root, "nested");
+        leaves.push(subtask(&mut nested, "nested 1"));
+        leaves.push(subtask(&mut nested, "nested 2"));
+        let (root_id, nested_id) = (root.id, nested.id);
+        for task in leaves.into_iter().chain([root, nested]) {
+            state.tasks.insert(task.id, task);
+        }
+
+        let options = ExecuteOptions {
+            on_failure: Some(FailurePolicy::ContinueIndependent),
+            ..Default::default()
+        };
+        let run = handle(Some(root_id), false, "sonnet", &options, &state);
+        tokio::time::timeout(std::time::Duration::from_secs(10), run)
+            .await
+            .expect("the run stalled")
+            .unwrap();
+
+        let state_of = |name: &str| {
+            state
+                .tasks
+                .iter()
+                .find(|t| t.name == name)
+                .map(|t| t.state)
+                .unwrap()
+        };
+        for name in ["a", "b", "c", "nested 1"] {
+            assert_eq!(state_of(name), TaskState::Completed, "{name}");
+        }
+        assert_eq!(state_of("nested 2"), TaskState::Failed);
+
+        // Failures are reported under their hierarchical numbers.
+        let nested = state.tasks.get(&nested_id).unwrap().clone();
+        let summary = &nested.metadata["run_summary"];
+        assert_eq!(summary["completed"], 1);
+        assert_eq!(summary["failed"][0]["number"], "4.2");
+        assert_eq!(summary["not_started"], serde_json::json!([]));
+        let root = state.tasks.get(&root_id).unwrap().clone();
+        assert_eq!(root.metadata["run_summary"]["failed"][0]["number"], "4");
+
+        // The summaries and failure reasons are in the ledger, not only in memory.
+        let updates = state
+            .ledger
+            .query_by_kind(LedgerEntryKind::TaskUpdated)
+            .await
+            .unwrap();
+        let updated = |id: Uuid, field: &str| {
+            updates
+                .iter()
+                .any(|e| e.subject_id == id && !e.payload["new"][field].is_null())
+        };
+        assert!(updated(nested_id, "failure_reason"));
+        assert!(updated(nested_id, "metadata"));
+        let failed_id = state.tasks.iter().find(|t| t.name == "nested 2").unwrap().id;
+        assert!(updated(failed_id, "failure_reason"));
+    }
+
+    #[tokio::test]
+    async fn test_leaves_sharing_a_working_dir_keep_their_own_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let workdir = tempfile::tempdir().unwrap();
+        let mut state = scripted_state(
+            dir.path(),
+            vec![
+                ScriptedRule::new("a")
+                    .with_delay_ms(300)
+                    .with_file("a.txt", "a"),
+                ScriptedRule::new("b")
+                    .with_delay_ms(100)
+                    .with_file("b.txt", "b"),
+                ScriptedRule::new("*"),
+            ],
+        );
+        state.config.max_parallel_tasks = 2;
+        state.config.agents = vec![AgentConfig {
+            working_dir: Some(workdir.path().display().to_string()),
+            ..AgentConfig::new("worker")
+        }];
+
+        let mut root = Task::new("root", "r");
+        let leaves: Vec<Task> = ["a", "b"].iter().map(|n| subtask(&mut root, n)).collect();
+        let ids: Vec<Uuid> = leaves.iter().map(|t| t.id).collect();
+        let root_id = root.id;
+        for task in leaves.into_iter().chain([root]) {
+            state.tasks.insert(task.id, task);
+        }
+
+        let options = ExecuteOptions::default();
+        handle(Some(root_id), false, "sonnet", &options, &state)
+            .await
+            .unwrap();
+
+        for (id, file) in ids.iter().zip(["a.txt", "b.txt"]) {
+            let task = state.tasks.get(id).unwrap();
+            let paths: Vec<&str> = task.artifacts.iter().map(|a| a.path.as_str()).collect();
+            assert_eq!(paths, vec![file], "artifacts of '{}'", task.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_priority_change_during_run_reorders_waiting_subtasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = scripted_state(dir.path(), vec![ScriptedRule::new("*").with_delay_ms(300)]);
+        state.config.max_parallel_tasks = 1;
+
+        let mut root = Task::new("root", "r");
+        let subtasks: Vec<Task> = ["a", "b", "c"]
+            .iter()
+            .map(|n| subtask(&mut root, n))
+            .collect();
+        let (root_id, c_id) = (root.id, subtasks[2].id);
+        for task in subtasks.into_iter().chain([root]) {
+            state.tasks.insert(task.id, task);
+        }
+
+        let options = ExecuteOptions::default();
+        let run = handle(Some(root_id), false, "sonnet", &options, &state);
+        let (result, ()) = tokio::join!(run, async {
+            // While 'a' runs, 'c' is moved ahead of 'b'.
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            change_priority(&format!("/task priority {c_id} 0.9"), &state)
+                .await
+                .unwrap();
+        });
+        result.unwrap();
+
+        assert_eq!(start_order(&state).await, vec!["a", "c", "b"]);
+        assert!(change_priority("/status", &state).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_raise_lasts_only_for_the_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = scripted_state(dir.path(), vec![ScriptedRule::new("*")]);
+        let mut task = Task::new("due soon", "d");
+        task.priority = 0.3;
+        task.deadline = Some(Utc::now() + chrono::Duration::seconds(60));
+        let task_id = task.id;
+        state.tasks.insert(task_id, task);
+
+        let options = ExecuteOptions::default();
+        handle(Some(task_id), false, "sonnet", &options, &state)
+            .await
+            .unwrap();
+
+        let priorities: Vec<f64> = state
+            .ledger
+            .query_by_subject(task_id)
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(|e| e.payload["new"]["priority"].as_f64())
+            .collect();
+        assert_eq!(priorities, vec![AT_RISK_PRIORITY, 0.3]);
+        assert_eq!(state.tasks.get(&task_id).unwrap().priority, 0.3);
+    }
 }
@@ -1,7 +1,10 @@
 pub mod agent;
 pub mod config;
 pub mod demo;
+pub mod dry_run;
 pub mod execute;
 pub mod plan;
+pub mod recover;
 pub mod status;
 pub mod task;
+pub mod template;
@@ -2,23 +2,26 @@ use anyhow::Result;
 use std::io::{self, Write};
 use uuid::Uuid;
 
+use crate::assignment::COORDINATOR_ID;
 use crate::cli::state::AppState;
-use crate::executor::{ClaudeExecutor, AgentExecutor, ExecutionContext};
+use crate::executor::{self, ExecutionContext, TemplateStore};
 use crate::types::{Task, TaskCharacteristics};
+use crate::verification::CompiledSchema;
 
 /// Handle the `plan` command: decompose a goal into subtasks using Claude.
 pub async fn handle(goal: &str, model: &str, state: &AppState) -> Result<()> {
-    println!("Planning with Claude ({model})...");
+    let executor = executor::from_config(&state.config, model, state.state_dir.as_deref())?;
 
-    let executor = ClaudeExecutor::default().with_model(model);
+    println!("Planning with {} ({model})...", executor.name());
 
-    // Check if claude CLI is available.
+    // Check if the executor backend is available.
     match executor.health_check().await {
         Ok(true) => {}
         _ => {
             anyhow::bail!(
-                "claude CLI is not available. Install it from https://claude.ai/code\n\
-                 Falling back is not supported yet."
+                "{} is not available. Install the claude CLI from https://claude.ai/code\n\
+                 or set `executor = \"scripted\"` in config.toml to run offline.",
+                executor.name()
             );
         }
     }
@@ -29,29 +32,11 @@ pub async fn handle(goal: &str, model: &str, state: &AppState) -> Result<()> {
         format!("Goal: {goal}"),
     );
 
-    let system_prompt = "\
-        You are a task planning assistant. Given a goal, decompose it into concrete subtasks.\n\n\
-        Respond with ONLY a JSON object (no markdown, no code fences, no explanation) with this exact structure:\n\
-        {\n  \
-          \"subtasks\": [\n    \
-            {\n      \
-              \"name\": \"short task name\",\n      \
-              \"description\": \"detailed description of what to do\",\n      \
-              \"complexity\": 0.5,\n      \
-              \"criticality\": 0.5,\n      \
-              \"verifiability\": 0.5,\n      \
-              \"reversibility\": 0.5,\n      \
-              \"capabilities\": [\"cap1\", \"cap2\"]\n    \
-            }\n  \
-          ],\n  \
-          \"dependencies\": [[0, 1], [1, 2]]\n\
-        }\n\n\
-        The dependencies array contains [from_index, to_index] pairs meaning \
-        subtask at from_index must complete before subtask at to_index can start.\n\
-        Keep the number of subtasks between 2 and 8.";
+    let system_prompt = TemplateStore::new(state.state_dir.as_deref())
+        .render(&state.config.templates.plan, &serde_json::json!({ "goal": goal }))?;
 
     let ctx = ExecutionContext {
-        system_prompt: Some(system_prompt.to_string()),
+        system_prompt: Some(system_prompt),
         ..Default::default()
     };
     let result = executor
@@ -76,7 +61,7 @@ pub async fn handle(goal: &str, model: &str, state: &AppState) -> Result<()> {
     // Create a parent task.
     let mut parent = Task::new(goal, goal);
     let parent_id = parent.id;
-    let mut subtask_ids = Vec::new();
+    let mut subtasks = Vec::new();
 
     println!(
         "Decomposed into {} subtasks:",
@@ -126,6 +111,12 @@ pub async fn handle(goal: &str, model: &str, state: &AppState) -> Result<()> {
                 .map(|s| s.to_string())
                 .collect();
         }
+        if let Some(schema) = st.get("output_schema").filter(|v| v.is_object()) {
+            match CompiledSchema::compile(schema) {
+                Ok(_) => task.output_schema = Some(schema.clone()),
+                Err(e) => println!("  Dropped the output schema of '{name}': {e}"),
+            }
+        }
 
         println!(
             "  [{}] {} (complexity={:.1}, criticality={:.1})",
@@ -135,8 +126,7 @@ pub async fn handle(goal: &str, model: &str, state: &AppState) -> Result<()> {
             criticality,
         );
 
-        subtask_ids.push(task.id);
-        state.tasks.insert(task.id, task);
+        subtasks.push(task);
     }
 
     if !dependencies.is_empty() {
@@ -162,22 +152,21 @@ pub async fn handle(goal: &str, model: &str, state: &AppState) -> Result<()> {
     if !input.is_empty() && !input.eq_ignore_ascii_case("y") && !input.eq_ignore_ascii_case("yes")
     {
         println!("Cancelled.");
-        // Clean up subtasks.
-        for id in &subtask_ids {
-            state.tasks.remove(id);
-        }
         return Ok(());
     }
 
     // Store dependency info in parent metadata.
-    parent.subtask_ids = subtask_ids;
+    parent.subtask_ids = subtasks.iter().map(|t| t.id).collect();
     parent.metadata = serde_json::json!({
         "dependencies": dependencies,
         "goal": goal,
     });
 
     println!("\nCreated task tree: {} ({} subtasks)", parent_id, parent.subtask_ids.len());
-    state.tasks.insert(parent_id, parent);
+    state.create_task(parent, COORDINATOR_ID).await?;
+    for task in subtasks {
+        state.create_task(task, COORDINATOR_ID).await?;
+    }
 
     Ok(())
 }
@@ -206,10 +195,12 @@ pub async fn plan_offline(
 
     for (i, sub) in proposal.subtasks.iter().enumerate() {
         println!("  [{}] {}", i + 1, sub.name);
-        parent.subtask_ids.push(sub.id);
-        state.tasks.insert(sub.id, sub.clone());
     }
+    proposal.apply_to(&mut parent);
 
-    state.tasks.insert(parent_id, parent);
+    state.create_task(parent, COORDINATOR_ID).await?;
+    for sub in proposal.subtasks {
+        state.create_task(sub, COORDINATOR_ID).await?;
+    }
     Ok(parent_id)
 }
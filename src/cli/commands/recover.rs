@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Write};
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::assignment::COORDINATOR_ID;
+use crate::cli::state::AppState;
+use crate::scheduler::{Orphan, RecoveryAction, find_orphans};
+use crate::types::{Task, TaskState};
+
+/// Next turn of a resumed session whose run was interrupted.
+const RESUME_FEEDBACK: &str =
+    "Your previous run was interrupted before it finished. Continue where you left off.";
+
+/// Failure reason of a task given up on after its run was interrupted.
+const INTERRUPTED_REASON: &str = "interrupted: the run executing it ended before it finished";
+
+/// Recover the tasks an interrupted run left in flight (see `find_orphans`). Each one is
+/// resumed, retried, or marked failed: as chosen at a prompt when the session is
+/// interactive, else by the configured `recovery.policy`. The parents of recovered tasks are
+/// then settled from their subtasks, so `/execute --all` picks their trees up again.
+pub async fn handle(state: &AppState) -> Result<()> {
+    let tasks: Vec<Task> = state.tasks.iter().map(|e| e.value().clone()).collect();
+    let entries = state
+        .ledger
+        .all_entries()
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    let orphans = find_orphans(&tasks, &entries);
+    if orphans.is_empty() {
+        return Ok(());
+    }
+
+    let policy = state.config.recovery.policy;
+    let interactive = io::stdin().is_terminal();
+    println!(
+        "{} task(s) were left in flight by an interrupted run:",
+        orphans.len()
+    );
+    for orphan in &orphans {
+        println!("  {}", describe(orphan, state));
+        let action = if interactive { ask(policy)? } else { policy };
+        recover(orphan, action, state).await?;
+        println!(
+            "    {}",
+            match action {
+                RecoveryAction::Resume => "Resuming on the next /execute",
+                RecoveryAction::Retry => "Retrying from scratch on the next /execute",
+                RecoveryAction::MarkFailed => "Marked failed",
+            }
+        );
+    }
+    if !interactive {
+        println!("Applied recovery.policy = {policy}.");
+    }
+    settle_ancestors(&orphans, state).await
+}
+
+/// One line on an orphan: where it stopped, on which agent, and how far it got.
+fn describe(orphan: &Orphan, state: &AppState) -> String {
+    let mut facts = vec![format!(
+        "{:?} since {}",
+        orphan.state,
+        orphan.since.format("%Y-%m-%d %H:%M UTC")
+    )];
+    if let Some(agent_id) = orphan.agent_id {
+        let name = state
+            .agents
+            .get(&agent_id)
+            .map(|a| a.name.clone())
+            .unwrap_or_else(|| agent_id.to_string());
+        facts.push(format!("on {name}"));
+    }
+    if orphan.attempts > 0 {
+        facts.push(format!("{} attempt(s)", orphan.attempts));
+    }
+    if let Some((progress, status)) = &orphan.last_checkpoint {
+        facts.push(format!("last at {:.0}%: {status}", progress * 100.0));
+    }
+    if let Some(session_id) = &orphan.session_id {
+        facts.push(format!("session {session_id}"));
+    }
+    format!(
+        "{} ({}) — {}",
+        orphan.name,
+        orphan.task_id,
+        facts.join(", ")
+    )
+}
+
+/// Ask what to do with an orphan; an empty answer (or end of input) takes `default`.
+fn ask(default: RecoveryAction) -> Result<RecoveryAction> {
+    loop {
+        print!("    resume, retry, or mark_failed? [{default}] ");
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 || input.trim().is_empty() {
+            return Ok(default);
+        }
+        match input.parse() {
+            Ok(action) => return Ok(action),
+            Err(e) => println!("    {e}"),
+        }
+    }
+}
+
+/// Apply a recovery action to an orphan. Resumed and retried tasks go back to `Pending`:
+/// a resumed one keeps its agent and, when the interrupted session is known, continues it;
+/// a retried one is released from its agent, contract, and session.
+async fn recover(orphan: &Orphan, action: RecoveryAction, state: &AppState) -> Result<()> {
+    let task_id = orphan.task_id;
+    let reason = format!("recovered from an interrupted run: {action}");
+    let details = serde_json::json!({ "recovery": action });
+    match action {
+        RecoveryAction::Resume => {
+            state
+                .set_state(task_id, TaskState::Pending, COORDINATOR_ID, &reason)
+                .await?;
+            let session_id = orphan.session_id.clone();
+            state
+                .update_task(task_id, COORDINATOR_ID, details, |task| {
+                    task.blocked_by = None;
+                    if session_id.is_some() {
+                        task.session_id = session_id;
+                        task.feedback
+                            .get_or_insert_with(|| RESUME_FEEDBACK.to_string());
+                    }
+                })
+                .await
+        }
+        RecoveryAction::Retry => {
+            state
+                .set_state(task_id, TaskState::Pending, COORDINATOR_ID, &reason)
+                .await?;
+            state.assign(task_id, None, COORDINATOR_ID).await?;
+            state
+                .update_task(task_id, COORDINATOR_ID, details, |task| {
+                    task.blocked_by = None;
+                    task.contract_id = None;
+                    task.session_id = None;
+                })
+                .await
+        }
+        RecoveryAction::MarkFailed => {
+            state
+                .set_state(task_id, TaskState::Failed, COORDINATOR_ID, &reason)
+                .await?;
+            state
+                .update_task(task_id, COORDINATOR_ID, details, |task| {
+                    task.failure_reason = Some(INTERRUPTED_REASON.to_string());
+                })
+                .await
+        }
+    }
+}
+
+/// Re-derive the state of every ancestor of the recovered tasks still in flight, deepest
+/// first. A parent whose subtasks have all completed goes back to `Pending`, as its results
+/// have yet to be aggregated.
+async fn settle_ancestors(orphans: &[Orphan], state: &AppState) -> Result<()> {
+    let mut ancestors: Vec<(usize, Uuid)> = Vec::new();
+    let mut seen = HashSet::new();
+    for orphan in orphans {
+        let mut chain = Vec::new();
+        let mut next = state.tasks.get(&orphan.task_id).and_then(|t| t.parent_id);
+        while let Some(id) = next {
+            chain.push(id);
+            next = state.tasks.get(&id).and_then(|t| t.parent_id);
+        }
+        let depth = chain.len();
+        for (i, id) in chain.into_iter().enumerate() {
+            if seen.insert(id) {
+                ancestors.push((depth - i, id));
+            }
+        }
+    }
+    ancestors.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+
+    for (_, id) in ancestors {
+        let Some(parent) = state.tasks.get(&id).map(|t| t.value().clone()) else {
+            continue;
+        };
+        if matches!(
+            parent.state,
+            TaskState::Pending | TaskState::Completed | TaskState::Failed | TaskState::Disputed
+        ) {
+            continue;
+        }
+        let children: Vec<TaskState> = parent
+            .subtask_ids
+            .iter()
+            .filter_map(|id| state.tasks.get(id).map(|t| t.state))
+            .collect();
+        let derived = match TaskState::rollup(children) {
+            TaskState::Completed => TaskState::Pending,
+            derived => derived,
+        };
+        state
+            .set_state(
+                id,
+                derived,
+                COORDINATOR_ID,
+                "derived from its subtasks after recovery",
+            )
+            .await?;
+    }
+    Ok(())
+}
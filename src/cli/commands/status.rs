@@ -2,7 +2,7 @@ use anyhow::Result;
 
 use crate::cli::state::AppState;
 use crate::reputation::ReputationEngine;
-use crate::types::TaskState;
+use crate::types::{ResourceUsage, TaskState};
 
 /// Handle the `status` command: show a dashboard of task/agent state.
 pub async fn handle(state: &AppState) -> Result<()> {
@@ -48,7 +48,11 @@ pub async fn handle(state: &AppState) -> Result<()> {
             if !first {
                 print!(", ");
             }
-            print!("{} reputation: {:.3} [{:?}]", a.name, composite, trust);
+            let spent = state.agent_usage(a.id).cost_usd;
+            print!(
+                "{} reputation: {:.3} [{:?}] spent ${:.4}/${:.2}",
+                a.name, composite, trust, spent, a.permissions.max_cost_budget
+            );
             first = false;
         }
         println!(")");
@@ -73,11 +77,31 @@ pub async fn handle(state: &AppState) -> Result<()> {
         if integrity { "OK" } else { "CORRUPTED" }
     );
 
+    // Usage rollups.
+    let total_usage: ResourceUsage = state.tasks.iter().map(|e| e.value().usage).sum();
+    println!("Usage (session): {}", state.session_usage());
+    println!("Usage (all time): {}", total_usage);
+
+    let mut trees: Vec<_> = state
+        .tasks
+        .iter()
+        .filter(|e| e.value().parent_id.is_none())
+        .map(|e| (e.value().name.clone(), state.tree_usage(*e.key())))
+        .filter(|(_, usage)| !usage.is_empty())
+        .collect();
+    if !trees.is_empty() {
+        trees.sort_by(|a, b| b.1.cost_usd.total_cmp(&a.1.cost_usd));
+        println!("\nUsage by task tree:");
+        for (name, usage) in trees.iter().take(10) {
+            println!("  {}  {}", name, usage);
+        }
+    }
+
     // Show recent tasks.
     if total > 0 {
         println!("\nRecent tasks:");
         let mut tasks: Vec<_> = state.tasks.iter().map(|e| e.value().clone()).collect();
-        tasks.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        tasks.sort_by_key(|t| std::cmp::Reverse(t.updated_at));
         for t in tasks.iter().take(10) {
             println!(
                 "  {} {:?}  {}{}",
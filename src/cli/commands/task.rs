@@ -1,8 +1,12 @@
-use crate::decomposition::{
-    HybridStrategy, ParallelStrategy, SequentialStrategy, traits::DecompositionStrategy,
-};
+use crate::assignment::COORDINATOR_ID;
+use crate::coordination::{CoordinationTrigger, Coordinator, ExternalTrigger};
+use crate::decomposition::builtin_strategies;
+use crate::ledger::LedgerEntryKind;
 use crate::types::{Task, TaskCharacteristics, TaskEvent};
-use crate::verification::{TaskResult, Verifier, verifiers::DirectInspectionVerifier};
+use crate::verification::{
+    CompiledSchema, TaskResult, Verifier,
+    verifiers::{DirectInspectionVerifier, SchemaVerifier},
+};
 use anyhow::{Result, bail};
 use chrono::Utc;
 
@@ -40,7 +44,7 @@ pub async fn handle(action: TaskAction, state: &AppState) -> Result<()> {
             let id = task.id;
             println!("Created task: {} ({})", name, id);
             print_task(&task);
-            state.tasks.insert(id, task);
+            state.create_task(task, COORDINATOR_ID).await?;
         }
 
         TaskAction::List => {
@@ -61,13 +65,13 @@ pub async fn handle(action: TaskAction, state: &AppState) -> Result<()> {
 
         TaskAction::Transition { id, event } => {
             let event = parse_event(&event)?;
-            let mut entry = state
+            let prev = state
                 .tasks
-                .get_mut(&id)
+                .get(&id)
+                .map(|t| t.state)
                 .ok_or_else(|| anyhow::anyhow!("Task not found: {id}"))?;
-            let prev = entry.state;
-            entry.apply_event(event)?;
-            println!("{:?} -> {:?}", prev, entry.state);
+            let new = state.transition(id, event, COORDINATOR_ID).await?;
+            println!("{:?} -> {:?}", prev, new);
         }
 
         TaskAction::Decompose {
@@ -80,12 +84,14 @@ pub async fn handle(action: TaskAction, state: &AppState) -> Result<()> {
                 .ok_or_else(|| anyhow::anyhow!("Task not found: {id}"))?
                 .clone();
 
-            let strategy: Box<dyn DecompositionStrategy> = match strategy_name.as_str() {
-                "sequential" => Box::new(SequentialStrategy::default()),
-                "parallel" => Box::new(ParallelStrategy::default()),
-                "hybrid" => Box::new(HybridStrategy::default()),
-                other => bail!("Unknown strategy: {other} (use sequential, parallel, hybrid)"),
-            };
+            let strategy = builtin_strategies()
+                .into_iter()
+                .find(|s| s.name() == strategy_name)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unknown strategy: {strategy_name} (use sequential, parallel, hybrid)"
+                    )
+                })?;
 
             let proposal = strategy.decompose(&task).await?;
 
@@ -100,9 +106,26 @@ pub async fn handle(action: TaskAction, state: &AppState) -> Result<()> {
                     "  [{}] {} (complexity={:.2})",
                     i, sub.name, sub.characteristics.complexity
                 );
-                state.tasks.insert(sub.id, sub.clone());
             }
             println!("{} dependencies", proposal.dependencies.len());
+            let previous = subtask_fields(&task);
+            let mut parent = task;
+            proposal.apply_to(&mut parent);
+            let new = subtask_fields(&parent);
+            state.tasks.insert(id, parent);
+            state
+                .record_mutation(
+                    LedgerEntryKind::TaskUpdated,
+                    COORDINATOR_ID,
+                    id,
+                    previous,
+                    new,
+                    serde_json::json!({ "strategy": strategy_name }),
+                )
+                .await?;
+            for sub in proposal.subtasks {
+                state.create_task(sub, COORDINATOR_ID).await?;
+            }
         }
 
         TaskAction::Assign { id, agent } => {
@@ -110,11 +133,13 @@ pub async fn handle(action: TaskAction, state: &AppState) -> Result<()> {
             if !state.agents.contains_key(&agent) {
                 bail!("Agent not found: {agent}");
             }
-            let mut entry = state
-                .tasks
-                .get_mut(&id)
-                .ok_or_else(|| anyhow::anyhow!("Task not found: {id}"))?;
-            entry.assigned_agent_id = Some(agent);
+            state.assign(id, Some(agent), COORDINATOR_ID).await?;
+            // The task is negotiated afresh with its new assignee.
+            state
+                .update_task(id, COORDINATOR_ID, serde_json::json!({}), |task| {
+                    task.contract_id = None;
+                })
+                .await?;
             println!("Assigned task {} to agent {}", id, agent);
         }
 
@@ -136,16 +161,91 @@ pub async fn handle(action: TaskAction, state: &AppState) -> Result<()> {
                 output: task.metadata.clone(),
                 completed_at: Utc::now(),
                 resource_consumed: 0.0,
+                usage: Default::default(),
+                artifacts: task.artifacts.clone(),
+                session_id: task.session_id.clone(),
             };
 
-            let verifier = DirectInspectionVerifier::new(vec!["result".to_string()]);
+            let verifier: Box<dyn Verifier> = if task.output_schema.is_some() {
+                Box::new(SchemaVerifier)
+            } else {
+                Box::new(DirectInspectionVerifier::new(vec!["result".to_string()]))
+            };
             let outcome = verifier.verify(&task, &result).await.map_err(|e| anyhow::anyhow!("{e}"))?;
             println!("Verification result: {:?}", outcome);
         }
+
+        TaskAction::Schema { id, schema } => {
+            if let Some(schema) = &schema {
+                CompiledSchema::compile(schema)?;
+            }
+            let previous = {
+                let mut entry = state
+                    .tasks
+                    .get_mut(&id)
+                    .ok_or_else(|| anyhow::anyhow!("Task not found: {id}"))?;
+                std::mem::replace(&mut entry.output_schema, schema.clone())
+            };
+            match &schema {
+                Some(_) => println!("Set output schema on task {}", id),
+                None => println!("Cleared output schema on task {}", id),
+            }
+            state
+                .record_mutation(
+                    LedgerEntryKind::TaskUpdated,
+                    COORDINATOR_ID,
+                    id,
+                    serde_json::json!({ "output_schema": previous }),
+                    serde_json::json!({ "output_schema": schema }),
+                    serde_json::json!({}),
+                )
+                .await?;
+        }
+
+        TaskAction::Priority { id, priority } => {
+            if !state.tasks.contains_key(&id) {
+                bail!("Task not found: {id}");
+            }
+            let trigger = CoordinationTrigger::External(ExternalTrigger::PriorityChanged {
+                task_id: id,
+                new_priority: priority,
+            });
+            let plan = Coordinator::handle_trigger(&trigger);
+            println!("Coordinator: {}", plan.justification);
+            let outcomes = state.response_executor().execute(&plan).await?;
+            for outcome in &outcomes {
+                println!("  {}", outcome.summary);
+            }
+        }
+
+        TaskAction::Deadline { id, deadline } => {
+            state
+                .update_task(id, COORDINATOR_ID, serde_json::json!({}), |task| {
+                    task.deadline = deadline;
+                    task.updated_at = Utc::now();
+                })
+                .await?;
+            match deadline {
+                Some(at) => println!(
+                    "Task {} is due {}",
+                    id,
+                    at.format("%Y-%m-%d %H:%M:%S UTC")
+                ),
+                None => println!("Cleared the deadline of task {}", id),
+            }
+        }
     }
     Ok(())
 }
 
+/// The fields of a task that decomposing it changes.
+fn subtask_fields(task: &Task) -> serde_json::Value {
+    serde_json::json!({
+        "subtask_ids": task.subtask_ids,
+        "metadata": task.metadata,
+    })
+}
+
 fn parse_event(s: &str) -> Result<TaskEvent> {
     match s {
         "StartDecomposition" => Ok(TaskEvent::StartDecomposition),
@@ -162,11 +262,12 @@ fn parse_event(s: &str) -> Result<TaskEvent> {
         "DisputeResolved" => Ok(TaskEvent::DisputeResolved),
         "TaskFailed" => Ok(TaskEvent::TaskFailed),
         "Retry" => Ok(TaskEvent::Retry),
+        "FollowUp" => Ok(TaskEvent::FollowUp),
         other => bail!(
             "Unknown event: {other}\nValid events: StartDecomposition, DecompositionComplete, \
              SkipDecomposition, StartNegotiation, NegotiationComplete, ContractSigned, \
              StartExecution, ExecutionComplete, VerificationPassed, VerificationFailed, \
-             DisputeRaised, DisputeResolved, TaskFailed, Retry"
+             DisputeRaised, DisputeResolved, TaskFailed, Retry, FollowUp"
         ),
     }
 }
@@ -175,6 +276,10 @@ pub fn print_task(t: &Task) {
     println!("  ID:          {}", t.id);
     println!("  Name:        {}", t.name);
     println!("  State:       {:?}", t.state);
+    println!("  Priority:    {:.2}", t.priority);
+    if let Some(deadline) = t.deadline {
+        println!("  Deadline:    {}", deadline.format("%Y-%m-%d %H:%M:%S UTC"));
+    }
     println!("  Complexity:  {:.2}", t.characteristics.complexity);
     println!("  Criticality: {:.2}", t.characteristics.criticality);
     println!("  Verifiab.:   {:.2}", t.characteristics.verifiability);
@@ -185,4 +290,22 @@ pub fn print_task(t: &Task) {
     if let Some(agent) = t.assigned_agent_id {
         println!("  Assigned to: {agent}");
     }
+    if let Some(contract) = t.contract_id {
+        println!("  Contract:    {contract}");
+    }
+    if let Some(workspace) = &t.workspace {
+        println!("  Workspace:   {workspace}");
+    }
+    for artifact in &t.artifacts {
+        println!("  Artifact:    {artifact}");
+    }
+    if let Some(session_id) = &t.session_id {
+        println!("  Session:     {session_id}");
+    }
+    if let Some(feedback) = &t.feedback {
+        println!("  Feedback:    {feedback}");
+    }
+    if let Some(schema) = &t.output_schema {
+        println!("  Output schema: {schema}");
+    }
 }
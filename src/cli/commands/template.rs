@@ -0,0 +1,55 @@
+use anyhow::Result;
+
+use crate::cli::TemplateAction;
+use crate::cli::state::AppState;
+use crate::executor::TemplateStore;
+
+/// Handle template subcommands.
+pub async fn handle(action: TemplateAction, state: &AppState) -> Result<()> {
+    let store = TemplateStore::new(state.state_dir.as_deref());
+    match action {
+        TemplateAction::List => {
+            let selected = &state.config.templates;
+            for (name, from_file) in store.list() {
+                let source = if from_file { "file" } else { "built-in" };
+                let mut uses = Vec::new();
+                if name == selected.task {
+                    uses.push("task");
+                }
+                if name == selected.plan {
+                    uses.push("plan");
+                }
+                if name == selected.router {
+                    uses.push("router");
+                }
+                if name == selected.aggregate {
+                    uses.push("aggregate");
+                }
+                if uses.is_empty() {
+                    println!("  {name} ({source})");
+                } else {
+                    println!("  {name} ({source}, default {})", uses.join("/"));
+                }
+            }
+            if let Some(dir) = store.dir() {
+                println!("Templates directory: {}", dir.display());
+            }
+        }
+
+        TemplateAction::Show { name } => {
+            print!("{}", store.source(&name)?);
+            println!();
+        }
+
+        TemplateAction::Init => {
+            let written = store.init()?;
+            if written.is_empty() {
+                println!("All built-in templates already exist.");
+            }
+            for path in written {
+                println!("Wrote {}", path.display());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use tokio::sync::mpsc;
+
+/// How long the reader waits for input before checking whether anyone still listens.
+#[cfg(unix)]
+const POLL_INTERVAL_MS: i32 = 100;
+
+/// Lines typed at the terminal while a long command (such as `/execute`) runs.
+///
+/// Lines are read whole, and only while the receiver is alive: once it is dropped, stdin is
+/// left to the prompt again within `POLL_INTERVAL_MS`. Nothing is read when stdin is not a
+/// terminal, since piped input holds the commands that come after this one, or on platforms
+/// other than Unix.
+pub fn lines() -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel(8);
+    #[cfg(unix)]
+    {
+        use std::io::IsTerminal;
+        if std::io::stdin().is_terminal() {
+            tokio::task::spawn_blocking(move || read_lines(tx));
+        }
+    }
+    #[cfg(not(unix))]
+    drop(tx);
+    rx
+}
+
+/// Forward lines from stdin until `tx` is closed, stdin ends, or reading fails.
+#[cfg(unix)]
+fn read_lines(tx: mpsc::Sender<String>) {
+    let interrupted = || std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted;
+    let mut buf = [0u8; 4096];
+    let mut pending = Vec::new();
+    while !tx.is_closed() {
+        let mut fd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: polls one pollfd we own, for at most POLL_INTERVAL_MS.
+        let ready = unsafe { libc::poll(&mut fd, 1, POLL_INTERVAL_MS) };
+        if ready < 0 && !interrupted() {
+            return;
+        }
+        if ready <= 0 {
+            continue;
+        }
+        // SAFETY: reads at most `buf.len()` bytes into `buf`. A terminal in canonical mode
+        // returns one line at a time, so nothing meant for the next prompt is taken.
+        let read = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+        if read < 0 && interrupted() {
+            continue;
+        }
+        if read <= 0 {
+            return;
+        }
+        pending.extend_from_slice(&buf[..read as usize]);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if tx.blocking_send(line).is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod console;
 pub mod state;
 
 /// Task management actions (used by slash command dispatch).
@@ -25,6 +26,19 @@ pub enum TaskAction {
     Assign { id: uuid::Uuid, agent: uuid::Uuid },
     /// Verify a completed task
     Verify { id: uuid::Uuid },
+    /// Set (or clear, with `None`) the JSON Schema a task's output must match
+    Schema {
+        id: uuid::Uuid,
+        schema: Option<serde_json::Value>,
+    },
+    /// Change a task's priority (raised to the coordinator as `PriorityChanged`; can also be
+    /// entered while `/execute` runs)
+    Priority { id: uuid::Uuid, priority: f64 },
+    /// Set (or clear, with `None`) a task's deadline
+    Deadline {
+        id: uuid::Uuid,
+        deadline: Option<chrono::DateTime<chrono::Utc>>,
+    },
 }
 
 /// Agent management actions.
@@ -49,3 +63,13 @@ pub enum ConfigAction {
     /// Show current configuration
     Show,
 }
+
+/// Prompt template actions.
+pub enum TemplateAction {
+    /// List built-in and custom templates
+    List,
+    /// Print a template's source
+    Show { name: String },
+    /// Copy the built-in templates into the state dir for editing
+    Init,
+}
@@ -1,13 +1,20 @@
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
 use dashmap::DashMap;
 use uuid::Uuid;
 
-use crate::ledger::{InMemoryLedger, Ledger, LedgerEntry};
-use crate::persistence::{FileStore, PersistedState};
+use crate::assignment::CapabilityMatcher;
+use crate::config::PanopticonConfig;
+use crate::coordination::ResponseExecutor;
+use crate::ledger::{
+    InMemoryLedger, Ledger, LedgerEntry, LedgerEntryKind, changed_fields, mutation_payload,
+};
+use crate::persistence::{FileStore, JournalRecord, PersistedState};
 use crate::reputation::ReputationEngine;
-use crate::types::{Agent, Task};
+use crate::types::{Agent, DelegationContract, ResourceUsage, Task, TaskEvent, TaskState};
 
 /// Shared application state for CLI commands.
 #[allow(dead_code)]
@@ -16,6 +23,19 @@ pub struct AppState {
     pub reputation_engine: Arc<ReputationEngine>,
     pub tasks: DashMap<Uuid, Task>,
     pub agents: DashMap<Uuid, Agent>,
+    /// Delegation contracts awarded to agents, by contract id.
+    pub contracts: DashMap<Uuid, DelegationContract>,
+    pub config: PanopticonConfig,
+    /// State directory backing this state (`None` for purely in-memory state).
+    pub state_dir: Option<PathBuf>,
+    /// Usage recorded since this state was created (i.e. this session).
+    session_usage: Mutex<ResourceUsage>,
+    /// Command line being run, recorded as the trigger of every change it makes.
+    command: Mutex<Option<String>>,
+    /// Held while the state is written out, so concurrent saves do not interleave.
+    save_lock: tokio::sync::Mutex<()>,
+    /// Number of ledger entries already on disk, in the snapshot or its journal.
+    saved_entries: Mutex<usize>,
 }
 
 impl Default for AppState {
@@ -34,12 +54,26 @@ impl AppState {
             reputation_engine,
             tasks: DashMap::new(),
             agents: DashMap::new(),
+            contracts: DashMap::new(),
+            config: PanopticonConfig::default(),
+            state_dir: None,
+            session_usage: Mutex::new(ResourceUsage::default()),
+            command: Mutex::new(None),
+            save_lock: tokio::sync::Mutex::new(()),
+            saved_entries: Mutex::new(0),
         }
     }
 
-    /// Load state from a `FileStore`, populating ledger, reputation, tasks, and agents.
+    /// Load state from a `FileStore`, populating ledger, reputation, tasks, agents, and
+    /// contracts.
+    /// The config is loaded from the same state directory.
     pub async fn load_from(store: &FileStore) -> Result<Self> {
         let persisted = store.load()?;
+        let state_dir = store.path().parent().map(|p| p.to_path_buf());
+        let config = match &state_dir {
+            Some(dir) => PanopticonConfig::load(dir).unwrap_or_default(),
+            None => PanopticonConfig::default(),
+        };
 
         let ledger = Arc::new(InMemoryLedger::new());
 
@@ -48,6 +82,7 @@ impl AppState {
             ledger.append(entry.clone()).await.map_err(|e| anyhow::anyhow!("{e}"))?;
         }
 
+        let saved_entries = persisted.ledger_entries.len();
         let reputation_engine = Arc::new(ReputationEngine::new(ledger.clone()));
 
         // Restore reputation scores.
@@ -63,16 +98,383 @@ impl AppState {
             agents.insert(id, agent);
         }
 
+        let contracts = DashMap::new();
+        for (id, contract) in persisted.contracts {
+            contracts.insert(id, contract);
+        }
+
         Ok(Self {
             ledger,
             reputation_engine,
             tasks,
             agents,
+            contracts,
+            config,
+            state_dir,
+            session_usage: Mutex::new(ResourceUsage::default()),
+            command: Mutex::new(None),
+            save_lock: tokio::sync::Mutex::new(()),
+            saved_entries: Mutex::new(saved_entries),
         })
     }
 
+    /// Append an entry to the ledger, chained onto the current head.
+    pub async fn record(
+        &self,
+        kind: LedgerEntryKind,
+        actor_id: Uuid,
+        subject_id: Uuid,
+        payload: serde_json::Value,
+    ) -> Result<()> {
+        self.ledger
+            .append_chained(kind, actor_id, subject_id, payload)
+            .await
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("{e}"))
+    }
+
+    /// Start running a command: the changes made from now on are recorded as triggered by
+    /// `line`.
+    pub fn begin_command(&self, line: &str) {
+        *self.command.lock().unwrap() = Some(line.to_string());
+    }
+
+    /// The command line being run, if any.
+    pub fn command(&self) -> Option<String> {
+        self.command.lock().unwrap().clone()
+    }
+
+    /// Record a change to a domain object, triggered by the running command (see
+    /// `mutation_payload`).
+    pub async fn record_mutation(
+        &self,
+        kind: LedgerEntryKind,
+        actor_id: Uuid,
+        subject_id: Uuid,
+        previous: serde_json::Value,
+        new: serde_json::Value,
+        details: serde_json::Value,
+    ) -> Result<()> {
+        let command = self.command();
+        let payload = mutation_payload(previous, new, command.as_deref(), details);
+        self.record(kind, actor_id, subject_id, payload).await
+    }
+
+    /// Add a task to the store (`TaskCreated`, with the whole task as its new state).
+    pub async fn create_task(&self, task: Task, actor_id: Uuid) -> Result<()> {
+        let id = task.id;
+        let snapshot = serde_json::to_value(&task)?;
+        self.tasks.insert(id, task);
+        self.record_mutation(
+            LedgerEntryKind::TaskCreated,
+            actor_id,
+            id,
+            serde_json::Value::Null,
+            snapshot,
+            serde_json::json!({}),
+        )
+        .await
+    }
+
+    /// Delete a task from the store (`TaskRemoved`, with the whole task as its previous
+    /// state). Removing a task that does not exist does nothing.
+    pub async fn remove_task(&self, task_id: Uuid, actor_id: Uuid) -> Result<()> {
+        let Some((_, task)) = self.tasks.remove(&task_id) else {
+            return Ok(());
+        };
+        self.record_mutation(
+            LedgerEntryKind::TaskRemoved,
+            actor_id,
+            task_id,
+            serde_json::to_value(&task)?,
+            serde_json::Value::Null,
+            serde_json::json!({}),
+        )
+        .await
+    }
+
+    /// Add an agent to the store (`AgentRegistered`, with the whole agent as its new state).
+    pub async fn register_agent(&self, agent: Agent, actor_id: Uuid) -> Result<()> {
+        let id = agent.id;
+        let snapshot = serde_json::to_value(&agent)?;
+        self.agents.insert(id, agent);
+        self.record_mutation(
+            LedgerEntryKind::AgentRegistered,
+            actor_id,
+            id,
+            serde_json::Value::Null,
+            snapshot,
+            serde_json::json!({}),
+        )
+        .await
+    }
+
+    /// Replace an agent with an updated copy (`AgentUpdated`, with its capabilities,
+    /// permitted actions, and capacity before and after). Nothing is recorded when none of
+    /// them changed.
+    pub async fn update_agent(&self, agent: Agent, actor_id: Uuid) -> Result<()> {
+        let settings = |agent: &Agent| {
+            let capabilities: Vec<(&str, f64)> = agent
+                .capabilities
+                .capabilities
+                .iter()
+                .map(|c| (c.name.as_str(), c.proficiency))
+                .collect();
+            serde_json::json!({
+                "capabilities": capabilities,
+                "allowed_actions": agent.permissions.allowed_actions,
+                "max_concurrent_tasks": agent.max_concurrent_tasks,
+            })
+        };
+        let (id, new) = (agent.id, settings(&agent));
+        let previous = self.agents.insert(id, agent).map(|old| settings(&old));
+        if previous.as_ref() == Some(&new) {
+            return Ok(());
+        }
+        self.record_mutation(
+            LedgerEntryKind::AgentUpdated,
+            actor_id,
+            id,
+            previous.unwrap_or_default(),
+            new,
+            serde_json::json!({}),
+        )
+        .await
+    }
+
+    /// Move a task through its state machine (`TaskStateChanged`), returning its new state.
+    pub async fn transition(
+        &self,
+        task_id: Uuid,
+        event: TaskEvent,
+        actor_id: Uuid,
+    ) -> Result<TaskState> {
+        let (name, previous, new) = {
+            let mut task = self
+                .tasks
+                .get_mut(&task_id)
+                .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?;
+            let previous = task.state;
+            task.apply_event(event)?;
+            (task.name.clone(), previous, task.state)
+        };
+        self.record_mutation(
+            LedgerEntryKind::TaskStateChanged,
+            actor_id,
+            task_id,
+            serde_json::json!(previous),
+            serde_json::json!(new),
+            serde_json::json!({ "task_name": name, "event": event }),
+        )
+        .await?;
+        self.persist().await?;
+        Ok(new)
+    }
+
+    /// Set a task's state outside its state machine, such as a parent's state derived from
+    /// its subtasks (`TaskStateChanged`, with `reason` in place of an event). Nothing is
+    /// recorded when the state does not change.
+    pub async fn set_state(
+        &self,
+        task_id: Uuid,
+        state: TaskState,
+        actor_id: Uuid,
+        reason: &str,
+    ) -> Result<()> {
+        let (name, previous) = {
+            let mut task = self
+                .tasks
+                .get_mut(&task_id)
+                .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?;
+            let previous = task.state;
+            if previous == state {
+                return Ok(());
+            }
+            task.state = state;
+            task.updated_at = chrono::Utc::now();
+            (task.name.clone(), previous)
+        };
+        self.record_mutation(
+            LedgerEntryKind::TaskStateChanged,
+            actor_id,
+            task_id,
+            serde_json::json!(previous),
+            serde_json::json!(state),
+            serde_json::json!({ "task_name": name, "reason": reason }),
+        )
+        .await?;
+        self.persist().await
+    }
+
+    /// Change fields of a task other than its state and assignee (`TaskUpdated`, with the
+    /// changed fields before and after). Nothing is recorded when nothing changed.
+    pub async fn update_task(
+        &self,
+        task_id: Uuid,
+        actor_id: Uuid,
+        details: serde_json::Value,
+        update: impl FnOnce(&mut Task),
+    ) -> Result<()> {
+        let (previous, new) = {
+            let mut task = self
+                .tasks
+                .get_mut(&task_id)
+                .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?;
+            let previous = serde_json::to_value(&*task)?;
+            update(&mut task);
+            (previous, serde_json::to_value(&*task)?)
+        };
+        let Some((previous, new)) = changed_fields(&previous, &new, &["updated_at"]) else {
+            return Ok(());
+        };
+        self.record_mutation(
+            LedgerEntryKind::TaskUpdated,
+            actor_id,
+            task_id,
+            previous,
+            new,
+            details,
+        )
+        .await
+    }
+
+    /// Give a task to an agent, or take it away with `None` (`TaskAssigned`, with the
+    /// previous and new assignee). Nothing is recorded when the assignee does not change.
+    pub async fn assign(
+        &self,
+        task_id: Uuid,
+        agent_id: Option<Uuid>,
+        actor_id: Uuid,
+    ) -> Result<()> {
+        let (name, previous) = {
+            let mut task = self
+                .tasks
+                .get_mut(&task_id)
+                .ok_or_else(|| anyhow::anyhow!("Task not found: {task_id}"))?;
+            let previous = task.assigned_agent_id;
+            if previous == agent_id {
+                return Ok(());
+            }
+            task.assigned_agent_id = agent_id;
+            task.updated_at = chrono::Utc::now();
+            (task.name.clone(), previous)
+        };
+        self.record_mutation(
+            LedgerEntryKind::TaskAssigned,
+            actor_id,
+            task_id,
+            serde_json::json!(previous),
+            serde_json::json!(agent_id),
+            serde_json::json!({ "task_name": name }),
+        )
+        .await
+    }
+
+    /// Record usage from one execution against the task and the session totals.
+    pub fn record_usage(&self, task_id: Uuid, usage: ResourceUsage) {
+        if let Some(mut task) = self.tasks.get_mut(&task_id) {
+            task.usage += usage;
+        }
+        *self.session_usage.lock().unwrap() += usage;
+    }
+
+    /// Usage recorded during this session.
+    pub fn session_usage(&self) -> ResourceUsage {
+        *self.session_usage.lock().unwrap()
+    }
+
+    /// Usage of a task plus all of its descendants.
+    pub fn tree_usage(&self, root: Uuid) -> ResourceUsage {
+        let mut total = ResourceUsage::default();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if let Some(task) = self.tasks.get(&id) {
+                total += task.usage;
+                stack.extend(task.subtask_ids.iter().copied());
+            }
+        }
+        total
+    }
+
+    /// Usage of all tasks assigned to an agent.
+    pub fn agent_usage(&self, agent_id: Uuid) -> ResourceUsage {
+        self.tasks
+            .iter()
+            .filter(|t| t.assigned_agent_id == Some(agent_id))
+            .map(|t| t.usage)
+            .sum()
+    }
+
+    /// Usage of each leaf task the agent completed, the record its bids are based on.
+    pub fn track_record(&self, agent_id: Uuid) -> Vec<ResourceUsage> {
+        self.tasks
+            .iter()
+            .filter(|t| t.assigned_agent_id == Some(agent_id) && t.subtask_ids.is_empty())
+            .filter(|t| t.state == TaskState::Completed)
+            .map(|t| t.usage)
+            .collect()
+    }
+
+    /// Executor for coordinator response plans over these stores, redelegating under the
+    /// configured reputation threshold and redecomposing with the configured strategy.
+    pub fn response_executor(&self) -> ResponseExecutor<'_> {
+        ResponseExecutor::new(&self.tasks, &self.agents, &self.contracts, self.ledger.clone())
+            .with_matcher(CapabilityMatcher::new(self.config.min_reputation_threshold))
+            .with_default_strategy(&self.config.decomposition_strategy)
+            .with_command(self.command())
+    }
+
+    /// Save what changed since the last save to the state directory backing this state, if
+    /// any: the tasks, agents, and contracts named by the new ledger entries, and the entries
+    /// themselves, appended to the journal rather than rewriting the snapshot. Task state
+    /// changes are saved as they happen, so a run that is killed leaves its tasks' progress
+    /// behind for recovery. Reputation scores are saved with the next snapshot.
+    pub async fn persist(&self) -> Result<()> {
+        let Some(dir) = &self.state_dir else {
+            return Ok(());
+        };
+        let _saving = self.save_lock.lock().await;
+        let saved = *self.saved_entries.lock().unwrap();
+        let entries = self
+            .ledger
+            .entries_since(saved)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut records = Vec::new();
+        let mut subjects = HashSet::new();
+        for id in entries.iter().map(|e| e.subject_id) {
+            if !subjects.insert(id) {
+                continue;
+            }
+            if let Some(task) = self.tasks.get(&id) {
+                records.push(JournalRecord::Task(Box::new(task.clone())));
+            } else if let Some(agent) = self.agents.get(&id) {
+                records.push(JournalRecord::Agent(agent.clone()));
+            } else if let Some(contract) = self.contracts.get(&id) {
+                records.push(JournalRecord::Contract(contract.clone()));
+            } else if entries
+                .iter()
+                .any(|e| e.subject_id == id && e.kind == LedgerEntryKind::TaskRemoved)
+            {
+                records.push(JournalRecord::TaskRemoved(id));
+            }
+        }
+        let count = entries.len();
+        records.extend(entries.into_iter().map(JournalRecord::Ledger));
+
+        let store = FileStore::new(dir);
+        tokio::task::spawn_blocking(move || store.append(&records)).await??;
+        *self.saved_entries.lock().unwrap() = saved + count;
+        Ok(())
+    }
+
     /// Dump current state into a `PersistedState` and save via `FileStore`.
     pub async fn save_to(&self, store: &FileStore) -> Result<()> {
+        let _saving = self.save_lock.lock().await;
         let ledger_entries: Vec<LedgerEntry> = self
             .ledger
             .all_entries()
@@ -91,14 +493,185 @@ impl AppState {
             agents.insert(*entry.key(), entry.value().clone());
         }
 
+        let mut contracts = std::collections::HashMap::new();
+        for entry in self.contracts.iter() {
+            contracts.insert(*entry.key(), entry.value().clone());
+        }
+
         let persisted = PersistedState {
             tasks,
             agents,
             ledger_entries,
             reputation_scores,
+            contracts,
         };
 
-        store.save(&persisted)?;
+        let count = persisted.ledger_entries.len();
+        let store = store.clone();
+        tokio::task::spawn_blocking(move || store.save(&persisted)).await??;
+        *self.saved_entries.lock().unwrap() = count;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(tokens: u64, cost: f64) -> ResourceUsage {
+        ResourceUsage {
+            output_tokens: tokens,
+            cost_usd: cost,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_usage_rollups() {
+        let state = AppState::new();
+        let agent_id = Uuid::new_v4();
+
+        let mut parent = Task::new("parent", "p");
+        let mut child = Task::new("child", "c");
+        child.parent_id = Some(parent.id);
+        child.assigned_agent_id = Some(agent_id);
+        parent.subtask_ids.push(child.id);
+        let other = Task::new("other", "o");
+        let (parent_id, child_id, other_id) = (parent.id, child.id, other.id);
+        state.tasks.insert(parent.id, parent);
+        state.tasks.insert(child.id, child);
+        state.tasks.insert(other.id, other);
+
+        state.record_usage(child_id, usage(100, 0.5));
+        state.record_usage(child_id, usage(50, 0.25));
+        state.record_usage(other_id, usage(10, 0.1));
+
+        assert_eq!(state.tree_usage(parent_id).output_tokens, 150);
+        assert_eq!(state.agent_usage(agent_id).output_tokens, 150);
+        assert_eq!(state.session_usage().output_tokens, 160);
+        assert!((state.session_usage().cost_usd - 0.85).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_mutations_are_recorded() {
+        let state = AppState::new();
+        let (actor, agent_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let task = Task::new("t", "d");
+        let task_id = task.id;
+
+        state.begin_command("/task create t d");
+        state.create_task(task, actor).await.unwrap();
+        state.begin_command("/execute");
+        state.assign(task_id, Some(agent_id), actor).await.unwrap();
+        state.assign(task_id, Some(agent_id), actor).await.unwrap();
+        state
+            .transition(task_id, TaskEvent::SkipDecomposition, actor)
+            .await
+            .unwrap();
+
+        let entries = state.ledger.all_entries().await.unwrap();
+        let kinds: Vec<_> = entries.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LedgerEntryKind::TaskCreated,
+                LedgerEntryKind::TaskAssigned,
+                LedgerEntryKind::TaskStateChanged,
+            ]
+        );
+        assert!(entries.iter().all(|e| e.actor_id == actor && e.subject_id == task_id));
+        assert_eq!(entries[0].payload["command"], "/task create t d");
+        assert!(entries[0].payload["previous"].is_null());
+        assert_eq!(entries[0].payload["new"]["name"], "t");
+        assert_eq!(entries[1].payload["new"], serde_json::json!(agent_id));
+        assert_eq!(entries[2].payload["previous"], "Pending");
+        assert_eq!(entries[2].payload["new"], "AwaitingAssignment");
+        assert_eq!(entries[2].payload["command"], "/execute");
+    }
+
+    #[tokio::test]
+    async fn test_transitions_are_journaled() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+        let actor = Uuid::new_v4();
+        let state = AppState::load_from(&store).await.unwrap();
+        let task = Task::new("t", "d");
+        let task_id = task.id;
+        state.create_task(task, actor).await.unwrap();
+        state.save_to(&store).await.unwrap();
+        let snapshot = std::fs::read(store.path()).unwrap();
+
+        // A transition appends to the journal and leaves the snapshot alone.
+        state
+            .transition(task_id, TaskEvent::SkipDecomposition, actor)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(store.path()).unwrap(), snapshot);
+        let journal = std::fs::read_to_string(dir.path().join("state.journal")).unwrap();
+        assert_eq!(journal.lines().count(), 2);
+
+        // Nothing new, nothing written.
+        state.persist().await.unwrap();
+        let again = std::fs::read_to_string(dir.path().join("state.journal")).unwrap();
+        assert_eq!(again, journal);
+
+        let loaded = AppState::load_from(&store).await.unwrap();
+        assert_eq!(
+            loaded.tasks.get(&task_id).unwrap().state,
+            TaskState::AwaitingAssignment
+        );
+        assert_eq!(loaded.ledger.all_entries().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_concurrent_writers_keep_the_chain_intact() {
+        use crate::monitoring::{Checkpoint, WatchConfig, watch_execution};
+
+        let state = Arc::new(AppState::new());
+        let (task_id, agent_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let (checkpoint_tx, checkpoint_rx) = tokio::sync::mpsc::channel(16);
+        let mut events = watch_execution(
+            agent_id,
+            &crate::types::MonitoringTerms {
+                checkpoint_interval_secs: 60,
+                max_latency_ms: 0,
+                min_quality_score: 0.0,
+                max_resource_budget: 0.0,
+            },
+            &WatchConfig::default(),
+            true,
+            checkpoint_rx,
+            |_: &Checkpoint| {},
+            state.ledger.clone(),
+        );
+        let drain = tokio::spawn(async move { while events.recv().await.is_some() {} });
+
+        let mut writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    for i in 0..200 {
+                        let payload = serde_json::json!({ "writer": writer, "i": i });
+                        state
+                            .record(LedgerEntryKind::TaskUpdated, agent_id, task_id, payload)
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        writers.push(tokio::spawn(async move {
+            for _ in 0..200 {
+                let checkpoint = Checkpoint::new(task_id, agent_id);
+                checkpoint_tx.send(checkpoint).await.unwrap();
+            }
+        }));
+        for writer in writers {
+            writer.await.unwrap();
+        }
+        drain.await.unwrap();
+
+        assert_eq!(state.ledger.all_entries().await.unwrap().len(), 1000);
+        assert!(state.ledger.verify_integrity().await.unwrap());
+    }
+}
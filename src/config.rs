@@ -2,6 +2,14 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
+use crate::aggregation::AggregationConfig;
+use crate::assignment::MarketConfig;
+use crate::executor::{RetryPolicy, UpstreamLimits, WorkspaceConfig, standard_actions};
+use crate::monitoring::WatchConfig;
+use crate::scheduler::{DeadlineConfig, FailureConfig, RecoveryConfig};
+use crate::types::Task;
+use crate::util::glob_match;
+
 /// Top-level configuration for panopticon.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PanopticonConfig {
@@ -21,6 +29,11 @@ pub struct PanopticonConfig {
     #[serde(default)]
     pub allowed_tools: Vec<String>,
 
+    /// Actions granted to agents without their own `allowed_actions`
+    /// ("read", "write", "execute", "network", or any other action name).
+    #[serde(default = "standard_actions")]
+    pub allowed_actions: Vec<String>,
+
     /// Minimum reputation threshold for agent assignment.
     #[serde(default = "default_min_reputation")]
     pub min_reputation_threshold: f64,
@@ -40,6 +53,308 @@ pub struct PanopticonConfig {
     /// Maximum number of conversation messages retained in the REPL session.
     #[serde(default = "default_max_context_messages")]
     pub max_context_messages: u32,
+
+    /// Executor backend for plan/execute/routing: "claude", "command", "scripted", "http".
+    #[serde(default = "default_executor")]
+    pub executor: String,
+
+    /// JSON fixtures file for the scripted executor (relative paths resolve against the state dir).
+    #[serde(default)]
+    pub scripted_fixtures: Option<String>,
+
+    /// Program and arguments for the command executor.
+    #[serde(default)]
+    pub command: Vec<String>,
+
+    /// Endpoint settings for the HTTP executor.
+    #[serde(default)]
+    pub http: HttpConfig,
+
+    /// How failed executions are retried.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+
+    /// Size limits on dependency outputs passed to dependent tasks.
+    #[serde(default)]
+    pub upstream: UpstreamLimits,
+
+    /// How many times an output that violates its task's schema is sent back for repair.
+    #[serde(default = "default_schema_repair_rounds")]
+    pub schema_repair_rounds: u32,
+
+    /// Which prompt templates are used for tasks, planning, and routing.
+    #[serde(default)]
+    pub templates: TemplateConfig,
+
+    /// Maximum number of subtasks executed at once (each agent is further limited by its
+    /// `max_concurrent_tasks`).
+    #[serde(default = "default_max_parallel_tasks")]
+    pub max_parallel_tasks: u32,
+
+    /// What a task tree does when one of its subtasks fails.
+    #[serde(default)]
+    pub failure: FailureConfig,
+
+    /// How a parent's output is combined from its subtasks' outputs.
+    #[serde(default)]
+    pub aggregation: AggregationConfig,
+
+    /// How tasks are auctioned to agents and what the awarded contracts say.
+    #[serde(default)]
+    pub market: MarketConfig,
+
+    /// How executions are monitored against their contracts.
+    #[serde(default)]
+    pub monitoring: WatchConfig,
+
+    /// When unfinished tasks are reported at risk of missing their deadlines.
+    #[serde(default)]
+    pub deadlines: DeadlineConfig,
+
+    /// What happens on startup to tasks left in flight by an interrupted run.
+    #[serde(default)]
+    pub recovery: RecoveryConfig,
+
+    /// Which workspace files are captured as artifacts.
+    #[serde(default)]
+    pub workspace: WorkspaceConfig,
+
+    /// Configured agents, each bound to its own executor backend.
+    #[serde(default)]
+    pub agents: Vec<AgentConfig>,
+}
+
+/// An agent bound to an executor backend. Unset fields fall back to the top-level settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentConfig {
+    /// Agent name; matches `Agent.name` in the state.
+    pub name: String,
+
+    /// Executor backend: "claude", "command", "scripted", "http".
+    #[serde(default)]
+    pub executor: Option<String>,
+
+    /// Model passed to the backend.
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Permission mode for Claude CLI.
+    #[serde(default)]
+    pub permission_mode: Option<String>,
+
+    /// Allowed tools for Claude CLI (empty = all).
+    #[serde(default)]
+    pub allowed_tools: Option<Vec<String>>,
+
+    /// Actions the agent is permitted; mapped onto executor controls when it runs a task.
+    #[serde(default)]
+    pub allowed_actions: Option<Vec<String>>,
+
+    /// Max turns for Claude agent execution.
+    #[serde(default)]
+    pub max_turns: Option<u32>,
+
+    /// Working directory for executions.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+
+    /// Program and arguments for the command executor.
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+
+    /// JSON fixtures file for the scripted executor.
+    #[serde(default)]
+    pub scripted_fixtures: Option<String>,
+
+    /// Endpoint settings for the HTTP executor.
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
+
+    /// Retry policy for this agent's executions.
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+
+    /// Advertised capabilities: name -> proficiency (0.0 - 1.0).
+    #[serde(default)]
+    pub capabilities: std::collections::BTreeMap<String, f64>,
+
+    /// Maximum number of tasks the agent runs at once.
+    #[serde(default)]
+    pub max_concurrent_tasks: Option<u32>,
+}
+
+impl AgentConfig {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            executor: None,
+            model: None,
+            permission_mode: None,
+            allowed_tools: None,
+            allowed_actions: None,
+            max_turns: None,
+            working_dir: None,
+            command: None,
+            scripted_fixtures: None,
+            http: None,
+            retry: None,
+            capabilities: Default::default(),
+            max_concurrent_tasks: None,
+        }
+    }
+
+    /// The top-level config with this agent's backend settings applied on top.
+    pub fn backend_config(&self, base: &PanopticonConfig) -> PanopticonConfig {
+        let mut config = base.clone();
+        if let Some(executor) = &self.executor {
+            config.executor = executor.clone();
+        }
+        if let Some(mode) = &self.permission_mode {
+            config.permission_mode = mode.clone();
+        }
+        if let Some(tools) = &self.allowed_tools {
+            config.allowed_tools = tools.clone();
+        }
+        if let Some(actions) = &self.allowed_actions {
+            config.allowed_actions = actions.clone();
+        }
+        if let Some(turns) = self.max_turns {
+            config.max_turns = turns;
+        }
+        if let Some(command) = &self.command {
+            config.command = command.clone();
+        }
+        if let Some(fixtures) = &self.scripted_fixtures {
+            config.scripted_fixtures = Some(fixtures.clone());
+        }
+        if let Some(http) = &self.http {
+            config.http = http.clone();
+        }
+        if let Some(retry) = &self.retry {
+            config.retry = retry.clone();
+        }
+        config.agents.clear();
+        config
+    }
+}
+
+/// Selection of named prompt templates (see `TemplateStore`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateConfig {
+    /// Template for task prompts when no more specific rule matches.
+    #[serde(default = "default_task_template")]
+    pub task: String,
+
+    /// System prompt template for `/plan`.
+    #[serde(default = "default_plan_template")]
+    pub plan: String,
+
+    /// System prompt template for the natural-language router.
+    #[serde(default = "default_router_template")]
+    pub router: String,
+
+    /// Task template for agents combining the results of a parent's subtasks.
+    #[serde(default = "default_aggregate_template")]
+    pub aggregate: String,
+
+    /// Agent name -> task template.
+    #[serde(default)]
+    pub by_agent: std::collections::BTreeMap<String, String>,
+
+    /// Task name (exact or glob) -> task template.
+    #[serde(default)]
+    pub by_task: std::collections::BTreeMap<String, String>,
+
+    /// Required capability -> task template.
+    #[serde(default)]
+    pub by_capability: std::collections::BTreeMap<String, String>,
+}
+
+impl TemplateConfig {
+    /// The task template for `task` run by `agent_name`: by agent, then by task name
+    /// (exact names before globs), then by the first required capability with a template.
+    pub fn select(&self, task: &Task, agent_name: &str) -> &str {
+        if let Some(name) = self.by_agent.get(agent_name) {
+            return name;
+        }
+        if let Some(name) = self.by_task.get(&task.name).or_else(|| {
+            self.by_task
+                .iter()
+                .find(|(pattern, _)| glob_match(pattern, &task.name))
+                .map(|(_, name)| name)
+        }) {
+            return name;
+        }
+        task.required_capabilities
+            .iter()
+            .find_map(|cap| self.by_capability.get(cap))
+            .unwrap_or(&self.task)
+    }
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        Self {
+            task: default_task_template(),
+            plan: default_plan_template(),
+            router: default_router_template(),
+            aggregate: default_aggregate_template(),
+            by_agent: Default::default(),
+            by_task: Default::default(),
+            by_capability: Default::default(),
+        }
+    }
+}
+
+/// Settings for the `http` executor backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Base URL of the endpoint, without the API path.
+    #[serde(default = "default_http_base_url")]
+    pub base_url: String,
+
+    /// Wire format: "anthropic" (Messages API) or "openai" (chat completions).
+    #[serde(default = "default_http_api")]
+    pub api: String,
+
+    /// Model sent to the endpoint (defaults to the model requested by the command).
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Environment variable holding the API key (unset = no authentication).
+    #[serde(default)]
+    pub auth_env: Option<String>,
+
+    /// Extra headers sent with every request.
+    #[serde(default)]
+    pub headers: std::collections::BTreeMap<String, String>,
+
+    /// Maximum tokens to generate per request.
+    #[serde(default = "default_http_max_tokens")]
+    pub max_tokens: u32,
+
+    /// Seconds allowed to establish a connection.
+    #[serde(default = "default_http_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+
+    /// Seconds allowed for a whole request, response included.
+    #[serde(default = "default_http_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            base_url: default_http_base_url(),
+            api: default_http_api(),
+            model: None,
+            auth_env: None,
+            headers: Default::default(),
+            max_tokens: default_http_max_tokens(),
+            connect_timeout_secs: default_http_connect_timeout_secs(),
+            request_timeout_secs: default_http_request_timeout_secs(),
+        }
+    }
 }
 
 fn default_state_dir() -> String {
@@ -74,6 +389,54 @@ fn default_max_context_messages() -> u32 {
     20
 }
 
+fn default_executor() -> String {
+    "claude".to_string()
+}
+
+fn default_http_base_url() -> String {
+    "https://api.anthropic.com".to_string()
+}
+
+fn default_http_api() -> String {
+    "anthropic".to_string()
+}
+
+fn default_http_max_tokens() -> u32 {
+    4096
+}
+
+fn default_http_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_http_request_timeout_secs() -> u64 {
+    600
+}
+
+fn default_task_template() -> String {
+    "task".to_string()
+}
+
+fn default_schema_repair_rounds() -> u32 {
+    2
+}
+
+fn default_max_parallel_tasks() -> u32 {
+    4
+}
+
+fn default_plan_template() -> String {
+    "plan".to_string()
+}
+
+fn default_router_template() -> String {
+    "router".to_string()
+}
+
+fn default_aggregate_template() -> String {
+    "aggregate".to_string()
+}
+
 impl Default for PanopticonConfig {
     fn default() -> Self {
         Self {
@@ -81,16 +444,43 @@ impl Default for PanopticonConfig {
             default_model: default_model(),
             permission_mode: default_permission_mode(),
             allowed_tools: Vec::new(),
+            allowed_actions: standard_actions(),
             min_reputation_threshold: default_min_reputation(),
             decomposition_strategy: default_decomposition_strategy(),
             max_turns: default_max_turns(),
             router_model: default_router_model(),
             max_context_messages: default_max_context_messages(),
+            executor: default_executor(),
+            scripted_fixtures: None,
+            command: Vec::new(),
+            http: HttpConfig::default(),
+            retry: RetryPolicy::default(),
+            upstream: UpstreamLimits::default(),
+            schema_repair_rounds: default_schema_repair_rounds(),
+            templates: TemplateConfig::default(),
+            max_parallel_tasks: default_max_parallel_tasks(),
+            failure: FailureConfig::default(),
+            aggregation: AggregationConfig::default(),
+            market: MarketConfig::default(),
+            monitoring: WatchConfig::default(),
+            deadlines: DeadlineConfig::default(),
+            recovery: RecoveryConfig::default(),
+            workspace: WorkspaceConfig::default(),
+            agents: Vec::new(),
         }
     }
 }
 
 impl PanopticonConfig {
+    /// Backend settings for the named agent: its `[[agents]]` entry applied on top of this
+    /// config, or this config itself for agents that are not configured.
+    pub fn backend_for(&self, agent_name: &str) -> PanopticonConfig {
+        match self.agents.iter().find(|a| a.name == agent_name) {
+            Some(agent) => agent.backend_config(self),
+            None => self.clone(),
+        }
+    }
+
     /// Config file path within the state directory.
     pub fn config_path(state_dir: &Path) -> PathBuf {
         state_dir.join("config.toml")
@@ -122,6 +512,8 @@ impl PanopticonConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::aggregation::{AggregationMode, MergeFunction};
+    use crate::scheduler::FailurePolicy;
     use tempfile::tempdir;
 
     #[test]
@@ -129,6 +521,8 @@ mod tests {
         let config = PanopticonConfig::default();
         assert_eq!(config.default_model, "sonnet");
         assert_eq!(config.max_turns, 10);
+        assert_eq!(config.max_parallel_tasks, 4);
+        assert_eq!(config.failure.policy, FailurePolicy::SkipDependents);
     }
 
     #[test]
@@ -148,4 +542,142 @@ mod tests {
         assert!(toml_str.contains("default_model"));
         assert!(toml_str.contains("sonnet"));
     }
+
+    #[test]
+    fn test_config_missing_executor_defaults_to_claude() {
+        let config: PanopticonConfig = toml::from_str("default_model = \"opus\"").unwrap();
+        assert_eq!(config.executor, "claude");
+        assert!(config.scripted_fixtures.is_none());
+    }
+
+    #[test]
+    fn test_config_http_section() {
+        let config: PanopticonConfig = toml::from_str(
+            "executor = \"http\"\n\
+             [http]\n\
+             base_url = \"http://localhost:8000\"\n\
+             api = \"openai\"\n\
+             [http.headers]\n\
+             x-team = \"ops\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.http.api, "openai");
+        assert_eq!(config.http.headers["x-team"], "ops");
+        assert!(config.http.auth_env.is_none());
+        assert_eq!(config.http.max_tokens, 4096);
+        assert_eq!(config.http.connect_timeout_secs, 10);
+        assert_eq!(config.http.request_timeout_secs, 600);
+    }
+
+    #[test]
+    fn test_config_agents() {
+        let config: PanopticonConfig = toml::from_str(
+            "permission_mode = \"acceptEdits\"\n\
+             [[agents]]\n\
+             name = \"reviewer\"\n\
+             model = \"opus\"\n\
+             allowed_tools = [\"Read\"]\n\
+             capabilities = { review = 0.9 }\n\
+             [[agents]]\n\
+             name = \"offline\"\n\
+             executor = \"scripted\"\n\
+             scripted_fixtures = \"fixtures.json\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.agents.len(), 2);
+        assert_eq!(config.agents[0].capabilities["review"], 0.9);
+
+        let reviewer = config.agents[0].backend_config(&config);
+        assert_eq!(reviewer.executor, "claude");
+        assert_eq!(reviewer.permission_mode, "acceptEdits");
+        assert_eq!(reviewer.allowed_tools, vec!["Read".to_string()]);
+
+        assert_eq!(reviewer.allowed_actions, standard_actions());
+
+        let offline = config.agents[1].backend_config(&config);
+        assert_eq!(offline.executor, "scripted");
+        assert_eq!(offline.scripted_fixtures.as_deref(), Some("fixtures.json"));
+    }
+
+    #[test]
+    fn test_config_retry_override() {
+        let config: PanopticonConfig = toml::from_str(
+            "[retry]\n\
+             max_attempts = 5\n\
+             [[agents]]\n\
+             name = \"fragile\"\n\
+             retry = { max_attempts = 1 }\n\
+             [[agents]]\n\
+             name = \"steady\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.retry.max_attempts, 5);
+        assert_eq!(config.retry.initial_backoff_ms, 1000);
+        assert_eq!(config.agents[0].backend_config(&config).retry.max_attempts, 1);
+        assert_eq!(config.agents[1].backend_config(&config).retry.max_attempts, 5);
+    }
+
+    #[test]
+    fn test_allowed_actions_override() {
+        let config: PanopticonConfig = toml::from_str(
+            "allowed_actions = [\"read\", \"write\"]\n\
+             [[agents]]\n\
+             name = \"reader\"\n\
+             allowed_actions = [\"read\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.backend_for("reader").allowed_actions, vec!["read".to_string()]);
+        assert_eq!(config.backend_for("other").allowed_actions.len(), 2);
+        assert_eq!(PanopticonConfig::default().allowed_actions.len(), 4);
+    }
+
+    #[test]
+    fn test_aggregation_section() {
+        let config: PanopticonConfig =
+            toml::from_str("[aggregation]\nmode = \"merge\"\nmerge = \"collect\"").unwrap();
+        assert_eq!(config.aggregation.mode, AggregationMode::Merge);
+        assert_eq!(config.aggregation.merge, MergeFunction::Collect);
+        assert_eq!(config.templates.aggregate, "aggregate");
+    }
+
+    #[test]
+    fn test_market_section() {
+        let config: PanopticonConfig =
+            toml::from_str("[market]\nmax_cost = 0.5\nmax_latency_ms = 60000").unwrap();
+        assert_eq!(config.market.max_cost, 0.5);
+        assert_eq!(config.market.monitoring_terms().max_latency_ms, 60_000);
+        assert_eq!(config.market.default_duration_secs, 120);
+    }
+
+    #[test]
+    fn test_template_selection() {
+        let config: PanopticonConfig = toml::from_str(
+            "[templates]\n\
+             by_agent = { reviewer = \"review\" }\n\
+             by_task = { \"audit*\" = \"audit\" }\n\
+             by_capability = { code = \"coding\" }\n",
+        )
+        .unwrap();
+        let templates = &config.templates;
+        let coding = Task::new("implement", "d").with_capabilities(vec!["code".to_string()]);
+        let audit = Task::new("audit site", "d").with_capabilities(vec!["code".to_string()]);
+
+        assert_eq!(templates.select(&coding, "reviewer"), "review");
+        assert_eq!(templates.select(&audit, "worker"), "audit");
+        assert_eq!(templates.select(&coding, "worker"), "coding");
+        assert_eq!(templates.select(&Task::new("other", "d"), "worker"), "task");
+        assert_eq!(templates.plan, "plan");
+    }
+
+    #[test]
+    fn test_config_with_agents_roundtrip() {
+        let dir = tempdir().unwrap();
+        let mut config = PanopticonConfig::default();
+        let mut agent = AgentConfig::new("a");
+        agent.capabilities.insert("code".to_string(), 0.7);
+        config.agents.push(agent);
+        config.save(dir.path()).unwrap();
+        let loaded = PanopticonConfig::load(dir.path()).unwrap();
+        assert_eq!(loaded.agents[0].name, "a");
+    }
 }
@@ -3,6 +3,9 @@ use tokio::sync::{mpsc, watch};
 use super::response::{ResponseAction, ResponsePlan};
 use super::trigger::{CoordinationTrigger, ExternalTrigger, InternalTrigger};
 
+/// Priority given to a task whose deadline is at risk.
+pub const AT_RISK_PRIORITY: f64 = 1.0;
+
 /// The coordinator receives triggers and maps them to response plans.
 pub struct Coordinator {
     trigger_rx: mpsc::Receiver<CoordinationTrigger>,
@@ -160,36 +163,41 @@ impl Coordinator {
                     reason: format!("Agent {} unresponsive", agent_id),
                 })
             }
-        }
-    }
-}
 
-/// Execute a response plan by logging each action.
-/// In a real system this would dispatch to actual subsystems.
-pub fn execute_response(plan: &ResponsePlan) {
-    tracing::info!("Executing response plan: {}", plan.justification);
-    for (i, action) in plan.actions.iter().enumerate() {
-        match action {
-            ResponseAction::AdjustParameters {
+            InternalTrigger::DeadlineAtRisk {
                 task_id,
-                adjustments,
+                deadline,
+                missed: true,
             } => {
-                tracing::info!("[{i}] AdjustParameters: task={task_id} adjustments={adjustments}");
+                let justification = format!(
+                    "Task {} missed its deadline ({}); escalating",
+                    task_id,
+                    deadline.format("%Y-%m-%d %H:%M UTC")
+                );
+                ResponsePlan::new(justification).with_action(ResponseAction::Escalate {
+                    task_id: Some(*task_id),
+                    reason: format!("Deadline missed: {}", deadline.to_rfc3339()),
+                })
             }
-            ResponseAction::Redelegate {
-                task_id,
-                from_agent_id,
+
+            InternalTrigger::DeadlineAtRisk {
+                task_id, deadline, ..
             } => {
-                tracing::info!("[{i}] Redelegate: task={task_id} from={from_agent_id}");
-            }
-            ResponseAction::Redecompose { task_id } => {
-                tracing::info!("[{i}] Redecompose: task={task_id}");
-            }
-            ResponseAction::Escalate { task_id, reason } => {
-                tracing::info!("[{i}] Escalate: task={task_id:?} reason={reason}");
-            }
-            ResponseAction::Terminate { task_id, reason } => {
-                tracing::info!("[{i}] Terminate: task={task_id} reason={reason}");
+                let justification = format!(
+                    "Task {} is due {}; raising its priority and escalating",
+                    task_id,
+                    deadline.format("%Y-%m-%d %H:%M UTC")
+                );
+                let mut plan = ResponsePlan::new(justification);
+                plan.add_action(ResponseAction::AdjustParameters {
+                    task_id: *task_id,
+                    adjustments: serde_json::json!({ "priority": AT_RISK_PRIORITY }),
+                });
+                plan.add_action(ResponseAction::Escalate {
+                    task_id: Some(*task_id),
+                    reason: format!("Deadline at risk: due {}", deadline.to_rfc3339()),
+                });
+                plan
             }
         }
     }
@@ -198,6 +206,7 @@ pub fn execute_response(plan: &ResponsePlan) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::monitoring::{Comparison, MonitoringEvent, SloDefinition, SloViolation};
     use uuid::Uuid;
 
     #[test]
@@ -225,6 +234,40 @@ mod tests {
         assert!(matches!(plan.actions[0], ResponseAction::Terminate { .. }));
     }
 
+    #[test]
+    fn test_trigger_from_monitoring_event() {
+        let (task_id, agent_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let violation = |metric: &str| MonitoringEvent::SloViolation {
+            task_id,
+            agent_id,
+            violation: SloViolation {
+                definition: SloDefinition {
+                    metric_name: metric.into(),
+                    threshold: 100.0,
+                    comparison: Comparison::LessThan,
+                    window_secs: 30,
+                },
+                actual_value: 150.0,
+                detected_at: chrono::Utc::now(),
+            },
+        };
+
+        let budget = CoordinationTrigger::from_monitoring(&violation("resource_consumed")).unwrap();
+        let plan = Coordinator::handle_trigger(&budget);
+        assert!(matches!(plan.actions[0], ResponseAction::Terminate { .. }));
+
+        let slow = CoordinationTrigger::from_monitoring(&violation("elapsed_ms")).unwrap();
+        let plan = Coordinator::handle_trigger(&slow);
+        assert!(matches!(plan.actions[0], ResponseAction::Redelegate { .. }));
+
+        let checkpoint = MonitoringEvent::CheckpointReceived {
+            task_id,
+            agent_id,
+            progress_pct: 0.5,
+        };
+        assert!(CoordinationTrigger::from_monitoring(&checkpoint).is_none());
+    }
+
     #[test]
     fn test_trigger_verification_failed() {
         let task_id = Uuid::new_v4();
@@ -251,6 +294,31 @@ mod tests {
         assert!(matches!(plan.actions[0], ResponseAction::Escalate { .. }));
     }
 
+    #[test]
+    fn test_trigger_deadline_at_risk() {
+        let task_id = Uuid::new_v4();
+        let at_risk = |missed| {
+            Coordinator::handle_trigger(&CoordinationTrigger::Internal(
+                InternalTrigger::DeadlineAtRisk {
+                    task_id,
+                    deadline: chrono::Utc::now(),
+                    missed,
+                },
+            ))
+        };
+        let plan = at_risk(false);
+        assert_eq!(plan.actions.len(), 2);
+        assert!(matches!(
+            &plan.actions[0],
+            ResponseAction::AdjustParameters { adjustments, .. } if adjustments["priority"] == 1.0
+        ));
+        assert!(matches!(plan.actions[1], ResponseAction::Escalate { .. }));
+
+        let plan = at_risk(true);
+        assert_eq!(plan.actions.len(), 1);
+        assert!(matches!(plan.actions[0], ResponseAction::Escalate { .. }));
+    }
+
     #[test]
     fn test_trigger_performance_degraded() {
         let task_id = Uuid::new_v4();
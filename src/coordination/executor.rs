@@ -0,0 +1,1166 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::response::{ResponseAction, ResponsePlan};
+use crate::assignment::{COORDINATOR_ID, CapabilityMatcher};
+use crate::decomposition::{DecompositionStrategy, builtin_strategies};
+use crate::ledger::{Ledger, LedgerEntryKind, changed_fields, mutation_payload};
+use crate::types::{Agent, DelegationContract, PanopticonError, Task, TaskState};
+
+/// Start of the failure reason of a task failed by a `Terminate` action.
+pub const TERMINATED_PREFIX: &str = "terminated: ";
+
+/// What carrying out a response action changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionOutcome {
+    pub action: ResponseAction,
+    /// Human-readable account of the change.
+    pub summary: String,
+    /// Tasks the action changed, created, or removed.
+    pub task_ids: Vec<Uuid>,
+}
+
+/// Changes staged by a plan; nothing reaches the stores until every action has succeeded.
+#[derive(Default)]
+struct Staged {
+    tasks: HashMap<Uuid, Task>,
+    removed: HashSet<Uuid>,
+    agents: HashMap<Uuid, Agent>,
+    contracts: HashMap<Uuid, DelegationContract>,
+    /// Every task, agent, and contract read from the stores, as it was read. The plan is
+    /// abandoned if one it changes is no longer so when it is committed.
+    read: HashMap<Uuid, serde_json::Value>,
+}
+
+/// Carries out coordinator response plans against the task, agent, and contract stores.
+///
+/// A plan is applied as a whole: every action is staged, and if one fails, or something the
+/// plan changes was changed by someone else in the meantime, nothing is changed. The outcome
+/// of each action (applied, failed, rolled back, or not attempted) is recorded in the ledger
+/// as `ResponseExecuted`, and every task, agent, and contract a plan changes is recorded with
+/// its state before and after.
+pub struct ResponseExecutor<'a> {
+    tasks: &'a DashMap<Uuid, Task>,
+    agents: &'a DashMap<Uuid, Agent>,
+    contracts: &'a DashMap<Uuid, DelegationContract>,
+    ledger: Arc<dyn Ledger>,
+    matcher: CapabilityMatcher,
+    strategies: Vec<Box<dyn DecompositionStrategy>>,
+    default_strategy: String,
+    command: Option<String>,
+    /// Agents a task may be redelegated to; any agent when `None`.
+    eligible: Option<HashSet<Uuid>>,
+}
+
+impl<'a> ResponseExecutor<'a> {
+    pub fn new(
+        tasks: &'a DashMap<Uuid, Task>,
+        agents: &'a DashMap<Uuid, Agent>,
+        contracts: &'a DashMap<Uuid, DelegationContract>,
+        ledger: Arc<dyn Ledger>,
+    ) -> Self {
+        Self {
+            tasks,
+            agents,
+            contracts,
+            ledger,
+            matcher: CapabilityMatcher::new(0.0),
+            strategies: builtin_strategies(),
+            default_strategy: "hybrid".to_string(),
+            command: None,
+            eligible: None,
+        }
+    }
+
+    /// Matcher that picks the agent a task is redelegated to.
+    pub fn with_matcher(mut self, matcher: CapabilityMatcher) -> Self {
+        self.matcher = matcher;
+        self
+    }
+
+    /// Strategies available for redecomposition (the built-in ones by default).
+    pub fn with_strategies(mut self, strategies: Vec<Box<dyn DecompositionStrategy>>) -> Self {
+        self.strategies = strategies;
+        self
+    }
+
+    /// Strategy used for tasks that do not name one in `metadata.decomposition_strategy`.
+    pub fn with_default_strategy(mut self, name: impl Into<String>) -> Self {
+        self.default_strategy = name.into();
+        self
+    }
+
+    /// Command that triggered the plans, recorded with the changes they make.
+    pub fn with_command(mut self, command: Option<String>) -> Self {
+        self.command = command;
+        self
+    }
+
+    /// Only redelegate tasks to these agents, such as those bound to an executor backend:
+    /// a task moved to any other agent could not run.
+    pub fn with_eligible_agents(mut self, agent_ids: impl IntoIterator<Item = Uuid>) -> Self {
+        self.eligible = Some(agent_ids.into_iter().collect());
+        self
+    }
+
+    /// Carry out every action of the plan, in order, or none of them.
+    pub async fn execute(
+        &self,
+        plan: &ResponsePlan,
+    ) -> Result<Vec<ActionOutcome>, PanopticonError> {
+        let mut staged = Staged::default();
+        let mut outcomes = Vec::new();
+        for (i, action) in plan.actions.iter().enumerate() {
+            match self.apply(action, &mut staged).await {
+                Ok((summary, task_ids)) => outcomes.push(ActionOutcome {
+                    action: action.clone(),
+                    summary,
+                    task_ids,
+                }),
+                Err(e) => {
+                    for (j, action) in plan.actions.iter().enumerate() {
+                        let (status, detail) = match j.cmp(&i) {
+                            std::cmp::Ordering::Less => ("rolled_back", serde_json::Value::Null),
+                            std::cmp::Ordering::Equal => {
+                                ("failed", serde_json::json!(e.to_string()))
+                            }
+                            std::cmp::Ordering::Greater => {
+                                ("not_attempted", serde_json::Value::Null)
+                            }
+                        };
+                        self.record(plan, action, status, detail).await?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        // Staging may await (a redecomposition), so the stores may have moved on; checking
+        // and committing do not, so nothing can change in between.
+        if let Err(e) = self.check_unchanged(&staged) {
+            for action in &plan.actions {
+                self.record(plan, action, "conflict", serde_json::json!(e.to_string()))
+                    .await?;
+            }
+            return Err(e);
+        }
+        self.commit(plan, staged).await?;
+        for outcome in &outcomes {
+            let detail = serde_json::json!({
+                "summary": outcome.summary,
+                "task_ids": outcome.task_ids,
+            });
+            self.record(plan, &outcome.action, "applied", detail)
+                .await?;
+        }
+        Ok(outcomes)
+    }
+
+    /// Stage one action, returning a summary and the tasks it touched.
+    async fn apply(
+        &self,
+        action: &ResponseAction,
+        staged: &mut Staged,
+    ) -> Result<(String, Vec<Uuid>), PanopticonError> {
+        match action {
+            ResponseAction::AdjustParameters {
+                task_id,
+                adjustments,
+            } => self.adjust(*task_id, adjustments, staged),
+            ResponseAction::Redelegate {
+                task_id,
+                from_agent_id,
+            } => self.redelegate(*task_id, *from_agent_id, staged),
+            ResponseAction::Redecompose { task_id } => self.redecompose(*task_id, staged).await,
+            ResponseAction::Escalate { task_id, reason } => {
+                let task_ids = match task_id {
+                    Some(id) => vec![self.task(staged, *id)?.id],
+                    None => Vec::new(),
+                };
+                Ok((format!("escalated to the operator: {reason}"), task_ids))
+            }
+            ResponseAction::Terminate { task_id, reason } => {
+                self.terminate(*task_id, reason, staged)
+            }
+        }
+    }
+
+    /// Set a task's priority, deadline, required capabilities, or characteristics, or the
+    /// monitoring terms of its contract.
+    fn adjust(
+        &self,
+        task_id: Uuid,
+        adjustments: &serde_json::Value,
+        staged: &mut Staged,
+    ) -> Result<(String, Vec<Uuid>), PanopticonError> {
+        let mut task = self.task(staged, task_id)?;
+        let adjustments = adjustments.as_object().ok_or_else(|| {
+            PanopticonError::Internal("adjustments must be an object".to_string())
+        })?;
+        let invalid = |key: &str, expected: &str| {
+            PanopticonError::Internal(format!("adjustment '{key}' must be {expected}"))
+        };
+        let fraction = |key: &str, value: &serde_json::Value| {
+            value
+                .as_f64()
+                .filter(|v| (0.0..=1.0).contains(v))
+                .ok_or_else(|| invalid(key, "a number in [0, 1]"))
+        };
+
+        let mut characteristics = serde_json::to_value(&task.characteristics)
+            .map_err(|e| PanopticonError::Serialization(e.to_string()))?;
+        let mut monitoring = None;
+        for (key, value) in adjustments {
+            match key.as_str() {
+                "priority" => task.priority = fraction(key, value)?,
+                "deadline" => {
+                    task.deadline = match value {
+                        serde_json::Value::Null => None,
+                        _ => Some(
+                            value
+                                .as_str()
+                                .and_then(|s| s.parse::<DateTime<Utc>>().ok())
+                                .ok_or_else(|| invalid(key, "an RFC 3339 timestamp or null"))?,
+                        ),
+                    }
+                }
+                "required_capabilities" => {
+                    task.required_capabilities = serde_json::from_value(value.clone())
+                        .map_err(|_| invalid(key, "a list of capability names"))?
+                }
+                "checkpoint_interval_secs"
+                | "max_latency_ms"
+                | "min_quality_score"
+                | "max_resource_budget" => {
+                    let contract_id = task.contract_id.ok_or_else(|| {
+                        PanopticonError::Internal(format!(
+                            "'{}' has no contract to adjust '{key}' on",
+                            task.name
+                        ))
+                    })?;
+                    let contract = match monitoring.take() {
+                        Some(contract) => contract,
+                        None => self.contract(staged, contract_id)?,
+                    };
+                    let mut terms = serde_json::to_value(&contract.monitoring)
+                        .map_err(|e| PanopticonError::Serialization(e.to_string()))?;
+                    terms[key] = value.clone();
+                    let terms = serde_json::from_value(terms)
+                        .map_err(|_| invalid(key, "a non-negative number"))?;
+                    monitoring = Some(DelegationContract {
+                        monitoring: terms,
+                        ..contract
+                    });
+                }
+                _ if characteristics.get(key).is_some() => {
+                    characteristics[key] = serde_json::json!(fraction(key, value)?)
+                }
+                _ => {
+                    return Err(PanopticonError::Internal(format!(
+                        "unknown adjustment '{key}'"
+                    )));
+                }
+            }
+        }
+        task.characteristics = serde_json::from_value(characteristics)
+            .map_err(|e| PanopticonError::Serialization(e.to_string()))?;
+        task.updated_at = Utc::now();
+
+        let keys: Vec<&str> = adjustments.keys().map(|k| k.as_str()).collect();
+        let summary = format!("adjusted {} on '{}'", keys.join(", "), task.name);
+        if let Some(contract) = monitoring {
+            staged.contracts.insert(contract.id, contract);
+        }
+        staged.tasks.insert(task_id, task);
+        Ok((summary, vec![task_id]))
+    }
+
+    /// Hand a task from its agent to the best other capable agent. The task goes back to
+    /// `Pending` without a contract, to be negotiated with its new assignee when it next runs.
+    fn redelegate(
+        &self,
+        task_id: Uuid,
+        from_agent_id: Uuid,
+        staged: &mut Staged,
+    ) -> Result<(String, Vec<Uuid>), PanopticonError> {
+        let mut task = self.task(staged, task_id)?;
+        if task.assigned_agent_id != Some(from_agent_id) {
+            return Err(PanopticonError::AssignmentError(format!(
+                "'{}' is not assigned to agent {from_agent_id}",
+                task.name
+            )));
+        }
+        if task.state == TaskState::Completed {
+            return Err(PanopticonError::AssignmentError(format!(
+                "'{}' is already completed",
+                task.name
+            )));
+        }
+
+        let others: Vec<Agent> = self
+            .agent_ids(staged)
+            .into_iter()
+            .filter(|id| *id != from_agent_id)
+            .filter(|id| {
+                self.eligible
+                    .as_ref()
+                    .is_none_or(|eligible| eligible.contains(id))
+            })
+            .map(|id| self.agent(staged, id))
+            .collect::<Result<_, _>>()?;
+        let to = self
+            .matcher
+            .filter_agents(&task, &others)
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                PanopticonError::AssignmentError(format!("no other agent can take '{}'", task.name))
+            })?;
+
+        if let Ok(mut from) = self.agent(staged, from_agent_id) {
+            from.active_task_ids.retain(|id| *id != task_id);
+            staged.agents.insert(from.id, from);
+        }
+        task.assigned_agent_id = Some(to.id);
+        task.contract_id = None;
+        // Sessions belong to the backend that created them.
+        task.session_id = None;
+        reopen(&mut task);
+        let summary = format!("moved '{}' to {}", task.name, to.name);
+        staged.tasks.insert(task_id, task);
+        Ok((summary, vec![task_id]))
+    }
+
+    /// Replace a task's subtasks with a fresh decomposition by its strategy (from
+    /// `metadata.decomposition_strategy`, else the default). Refused while the task or any
+    /// of its subtasks is underway.
+    async fn redecompose(
+        &self,
+        task_id: Uuid,
+        staged: &mut Staged,
+    ) -> Result<(String, Vec<Uuid>), PanopticonError> {
+        let mut task = self.task(staged, task_id)?;
+        let descendants = self.descendants(staged, &task)?;
+        if let Some(busy) = std::iter::once(&task)
+            .chain(&descendants)
+            .find(|t| is_underway(t.state))
+        {
+            return Err(PanopticonError::DecompositionError(format!(
+                "'{}' is {:?}",
+                busy.name, busy.state
+            )));
+        }
+
+        let name = task
+            .metadata
+            .get("decomposition_strategy")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.default_strategy)
+            .to_string();
+        let strategy = self
+            .strategies
+            .iter()
+            .find(|s| s.name() == name)
+            .ok_or_else(|| {
+                PanopticonError::DecompositionError(format!("unknown strategy: {name}"))
+            })?;
+        let proposal = strategy.decompose(&task).await?;
+        if !proposal.is_acyclic() {
+            return Err(PanopticonError::DecompositionError(format!(
+                "{name} produced a cyclic plan for '{}'",
+                task.name
+            )));
+        }
+
+        for old in &descendants {
+            staged.tasks.remove(&old.id);
+            staged.removed.insert(old.id);
+        }
+        // A leaf's metadata is its output; a parent keeps its settings but not its results.
+        if task.subtask_ids.is_empty() || !task.metadata.is_object() {
+            task.metadata = serde_json::json!({});
+        } else if let Some(metadata) = task.metadata.as_object_mut() {
+            metadata.remove("output");
+            metadata.remove("run_summary");
+        }
+        proposal.apply_to(&mut task);
+        task.contract_id = None;
+        task.session_id = None;
+        task.failure_reason = None;
+        reopen(&mut task);
+
+        let mut task_ids = vec![task_id];
+        for subtask in &proposal.subtasks {
+            task_ids.push(subtask.id);
+            staged.tasks.insert(subtask.id, subtask.clone());
+        }
+        let summary = format!(
+            "redecomposed '{}' into {} subtasks ({name}), replacing {}",
+            task.name,
+            proposal.subtasks.len(),
+            descendants.len()
+        );
+        staged.tasks.insert(task_id, task);
+        task_ids.extend(descendants.iter().map(|t| t.id));
+        Ok((summary, task_ids))
+    }
+
+    /// Fail a task and all of its unfinished subtasks, with `reason` as the failure reason.
+    fn terminate(
+        &self,
+        task_id: Uuid,
+        reason: &str,
+        staged: &mut Staged,
+    ) -> Result<(String, Vec<Uuid>), PanopticonError> {
+        let task = self.task(staged, task_id)?;
+        let name = task.name.clone();
+        let mut descendants = self.descendants(staged, &task)?;
+        descendants.insert(0, task);
+
+        let mut task_ids = Vec::new();
+        for mut task in descendants {
+            if matches!(task.state, TaskState::Completed | TaskState::Failed) {
+                continue;
+            }
+            // Termination is imposed on the task, not driven by its agent, so it bypasses
+            // the state machine.
+            task.state = TaskState::Failed;
+            task.failure_reason = Some(format!("{TERMINATED_PREFIX}{reason}"));
+            task.blocked_by = None;
+            task.updated_at = Utc::now();
+            task_ids.push(task.id);
+            staged.tasks.insert(task.id, task);
+        }
+        Ok((
+            format!("terminated {} task(s) of '{name}'", task_ids.len()),
+            task_ids,
+        ))
+    }
+
+    fn task(&self, staged: &mut Staged, id: Uuid) -> Result<Task, PanopticonError> {
+        if staged.removed.contains(&id) {
+            return Err(PanopticonError::TaskNotFound(id));
+        }
+        match staged.tasks.get(&id) {
+            Some(task) => Ok(task.clone()),
+            None => {
+                let task = self
+                    .tasks
+                    .get(&id)
+                    .map(|t| t.value().clone())
+                    .ok_or(PanopticonError::TaskNotFound(id))?;
+                staged.note_read(id, &task)?;
+                Ok(task)
+            }
+        }
+    }
+
+    /// Every subtask below `task`, breadth first.
+    fn descendants(&self, staged: &mut Staged, task: &Task) -> Result<Vec<Task>, PanopticonError> {
+        let mut found = Vec::new();
+        let mut queue: std::collections::VecDeque<Uuid> =
+            task.subtask_ids.iter().copied().collect();
+        while let Some(id) = queue.pop_front() {
+            let subtask = self.task(staged, id)?;
+            queue.extend(subtask.subtask_ids.iter().copied());
+            found.push(subtask);
+        }
+        Ok(found)
+    }
+
+    fn agent(&self, staged: &mut Staged, id: Uuid) -> Result<Agent, PanopticonError> {
+        match staged.agents.get(&id) {
+            Some(agent) => Ok(agent.clone()),
+            None => {
+                let agent = self
+                    .agents
+                    .get(&id)
+                    .map(|a| a.value().clone())
+                    .ok_or(PanopticonError::AgentNotFound(id))?;
+                staged.note_read(id, &agent)?;
+                Ok(agent)
+            }
+        }
+    }
+
+    fn agent_ids(&self, staged: &Staged) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = self.agents.iter().map(|a| *a.key()).collect();
+        ids.extend(
+            staged
+                .agents
+                .keys()
+                .filter(|id| !self.agents.contains_key(id)),
+        );
+        ids
+    }
+
+    fn contract(
+        &self,
+        staged: &mut Staged,
+        id: Uuid,
+    ) -> Result<DelegationContract, PanopticonError> {
+        match staged.contracts.get(&id) {
+            Some(contract) => Ok(contract.clone()),
+            None => {
+                let contract = self
+                    .contracts
+                    .get(&id)
+                    .map(|c| c.value().clone())
+                    .ok_or(PanopticonError::ContractNotFound(id))?;
+                staged.note_read(id, &contract)?;
+                Ok(contract)
+            }
+        }
+    }
+
+    /// Fail with `Conflict` when a task, agent, or contract the plan changes or removes is
+    /// no longer as the plan read it: changed, say, by an execution that went on while a
+    /// redecomposition was awaited.
+    fn check_unchanged(&self, staged: &Staged) -> Result<(), PanopticonError> {
+        let changed = staged
+            .tasks
+            .keys()
+            .chain(&staged.removed)
+            .chain(staged.agents.keys())
+            .chain(staged.contracts.keys());
+        for id in changed {
+            // Created by the plan.
+            let Some(read) = staged.read.get(id) else {
+                continue;
+            };
+            let current = if let Some(task) = self.tasks.get(id) {
+                Some(snapshot(task.value())?)
+            } else if let Some(agent) = self.agents.get(id) {
+                Some(snapshot(agent.value())?)
+            } else if let Some(contract) = self.contracts.get(id) {
+                Some(snapshot(contract.value())?)
+            } else {
+                None
+            };
+            if current.as_ref() != Some(read) {
+                let name = read["name"]
+                    .as_str()
+                    .map_or_else(|| id.to_string(), |name| format!("'{name}'"));
+                return Err(PanopticonError::Conflict(format!(
+                    "{name} changed while the plan was being applied"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write staged changes to the stores, then record each change in the ledger.
+    async fn commit(&self, plan: &ResponsePlan, staged: Staged) -> Result<(), PanopticonError> {
+        let mut changes = Vec::new();
+        for id in &staged.removed {
+            if let Some((_, task)) = self.tasks.remove(id) {
+                changes.push((
+                    LedgerEntryKind::TaskRemoved,
+                    *id,
+                    snapshot(&task)?,
+                    serde_json::Value::Null,
+                ));
+            }
+        }
+        for (id, task) in staged.tasks {
+            let new = snapshot(&task)?;
+            let previous = self.tasks.insert(id, task);
+            let Some(previous) = previous else {
+                changes.push((
+                    LedgerEntryKind::TaskCreated,
+                    id,
+                    serde_json::Value::Null,
+                    new,
+                ));
+                continue;
+            };
+            let previous = snapshot(&previous)?;
+            for (kind, field) in [
+                (LedgerEntryKind::TaskStateChanged, "state"),
+                (LedgerEntryKind::TaskAssigned, "assigned_agent_id"),
+            ] {
+                if previous[field] != new[field] {
+                    changes.push((kind, id, previous[field].clone(), new[field].clone()));
+                }
+            }
+            let ignored = ["state", "assigned_agent_id", "updated_at"];
+            if let Some((before, after)) = changed_fields(&previous, &new, &ignored) {
+                changes.push((LedgerEntryKind::TaskUpdated, id, before, after));
+            }
+        }
+        for (id, agent) in staged.agents {
+            let new = snapshot(&agent)?;
+            let previous = self
+                .agents
+                .insert(id, agent)
+                .map(|a| snapshot(&a))
+                .transpose()?;
+            changes.extend(Self::updated(
+                LedgerEntryKind::AgentUpdated,
+                id,
+                previous,
+                new,
+            ));
+        }
+        for (id, contract) in staged.contracts {
+            let new = snapshot(&contract)?;
+            let previous = self
+                .contracts
+                .insert(id, contract)
+                .map(|c| snapshot(&c))
+                .transpose()?;
+            changes.extend(Self::updated(
+                LedgerEntryKind::ContractUpdated,
+                id,
+                previous,
+                new,
+            ));
+        }
+
+        for (kind, subject_id, previous, new) in changes {
+            let details = serde_json::json!({ "justification": plan.justification });
+            let payload = mutation_payload(previous, new, self.command.as_deref(), details);
+            self.append(kind, subject_id, payload).await?;
+        }
+        Ok(())
+    }
+
+    /// A change to an agent or contract, or its creation when there was none before.
+    fn updated(
+        kind: LedgerEntryKind,
+        id: Uuid,
+        previous: Option<serde_json::Value>,
+        new: serde_json::Value,
+    ) -> Option<(LedgerEntryKind, Uuid, serde_json::Value, serde_json::Value)> {
+        match previous {
+            Some(previous) => changed_fields(&previous, &new, &["updated_at"])
+                .map(|(before, after)| (kind, id, before, after)),
+            None if kind == LedgerEntryKind::ContractUpdated => Some((
+                LedgerEntryKind::ContractCreated,
+                id,
+                serde_json::Value::Null,
+                new,
+            )),
+            None => Some((
+                LedgerEntryKind::AgentRegistered,
+                id,
+                serde_json::Value::Null,
+                new,
+            )),
+        }
+    }
+
+    async fn record(
+        &self,
+        plan: &ResponsePlan,
+        action: &ResponseAction,
+        status: &str,
+        detail: serde_json::Value,
+    ) -> Result<(), PanopticonError> {
+        self.append(
+            LedgerEntryKind::ResponseExecuted,
+            action.task_id().unwrap_or_else(Uuid::nil),
+            serde_json::json!({
+                "justification": plan.justification,
+                "action": action,
+                "status": status,
+                "detail": detail,
+            }),
+        )
+        .await
+    }
+
+    async fn append(
+        &self,
+        kind: LedgerEntryKind,
+        subject_id: Uuid,
+        payload: serde_json::Value,
+    ) -> Result<(), PanopticonError> {
+        self.ledger
+            .append_chained(kind, COORDINATOR_ID, subject_id, payload)
+            .await
+            .map(|_| ())
+    }
+}
+
+impl Staged {
+    /// Remember an object as it was first read from the stores.
+    fn note_read<T: Serialize>(&mut self, id: Uuid, value: &T) -> Result<(), PanopticonError> {
+        if let std::collections::hash_map::Entry::Vacant(entry) = self.read.entry(id) {
+            entry.insert(snapshot(value)?);
+        }
+        Ok(())
+    }
+}
+
+fn snapshot<T: Serialize>(value: &T) -> Result<serde_json::Value, PanopticonError> {
+    serde_json::to_value(value).map_err(|e| PanopticonError::Serialization(e.to_string()))
+}
+
+/// Whether a task is being worked on (decomposed, negotiated, executed, verified, or
+/// disputed), as `TaskState::rollup` counts it.
+fn is_underway(state: TaskState) -> bool {
+    !matches!(
+        state,
+        TaskState::Pending | TaskState::Completed | TaskState::Failed
+    )
+}
+
+/// Put a task back to `Pending` so it runs again. Like termination, this is imposed from
+/// outside and bypasses the state machine.
+fn reopen(task: &mut Task) {
+    task.state = TaskState::Pending;
+    task.blocked_by = None;
+    task.updated_at = Utc::now();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decomposition::{DecompositionProposal, ParallelStrategy};
+    use crate::ledger::InMemoryLedger;
+    use crate::types::{Capability, DisputeResolutionTerms, MonitoringTerms, PaymentTerms};
+
+    /// The parallel strategy, after a pause during which the stores may change.
+    struct SlowStrategy;
+
+    #[async_trait::async_trait]
+    impl DecompositionStrategy for SlowStrategy {
+        async fn decompose(&self, task: &Task) -> Result<DecompositionProposal, PanopticonError> {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            ParallelStrategy::default().decompose(task).await
+        }
+
+        fn name(&self) -> &str {
+            "slow"
+        }
+    }
+
+    struct Stores {
+        tasks: DashMap<Uuid, Task>,
+        agents: DashMap<Uuid, Agent>,
+        contracts: DashMap<Uuid, DelegationContract>,
+        ledger: Arc<InMemoryLedger>,
+    }
+
+    impl Stores {
+        fn new() -> Self {
+            Self {
+                tasks: DashMap::new(),
+                agents: DashMap::new(),
+                contracts: DashMap::new(),
+                ledger: Arc::new(InMemoryLedger::new()),
+            }
+        }
+
+        fn executor(&self) -> ResponseExecutor<'_> {
+            ResponseExecutor::new(
+                &self.tasks,
+                &self.agents,
+                &self.contracts,
+                self.ledger.clone(),
+            )
+        }
+
+        fn agent(&self, name: &str) -> Uuid {
+            let mut agent = Agent::new(name);
+            agent.capabilities.capabilities.push(Capability {
+                name: "code".to_string(),
+                proficiency: 0.8,
+                certified: false,
+                last_verified: None,
+            });
+            let id = agent.id;
+            self.agents.insert(id, agent);
+            id
+        }
+
+        fn task(&self, task: Task) -> Uuid {
+            let id = task.id;
+            self.tasks.insert(id, task);
+            id
+        }
+
+        fn contract(&self, task_id: Uuid) -> Uuid {
+            let contract = DelegationContract {
+                id: Uuid::new_v4(),
+                task_id,
+                delegator_id: COORDINATOR_ID,
+                delegatee_id: Uuid::new_v4(),
+                payment: PaymentTerms {
+                    total_amount: 1.0,
+                    escrow_amount: 0.0,
+                    milestone_payments: Vec::new(),
+                    penalty_rate: 0.0,
+                },
+                monitoring: MonitoringTerms {
+                    checkpoint_interval_secs: 60,
+                    max_latency_ms: 60_000,
+                    min_quality_score: 0.5,
+                    max_resource_budget: 1000.0,
+                },
+                dispute_resolution: DisputeResolutionTerms {
+                    dispute_bond: 0.0,
+                    resolution_timeout_secs: 60,
+                    panel_size: 1,
+                    escalation_enabled: false,
+                },
+                permitted_actions: Vec::new(),
+                max_delegation_depth: 0,
+                created_at: Utc::now(),
+                expires_at: None,
+                signed_by_delegator: true,
+                signed_by_delegatee: true,
+            };
+            let id = contract.id;
+            self.contracts.insert(id, contract);
+            id
+        }
+
+        async fn statuses(&self) -> Vec<String> {
+            self.ledger
+                .query_by_kind(LedgerEntryKind::ResponseExecuted)
+                .await
+                .unwrap()
+                .iter()
+                .map(|e| e.payload["status"].as_str().unwrap().to_string())
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redelegate_moves_task_to_other_agent() {
+        let stores = Stores::new();
+        let from = stores.agent("from");
+        let to = stores.agent("to");
+        let mut task = Task::new("t", "d").with_capabilities(vec!["code".into()]);
+        task.assigned_agent_id = Some(from);
+        task.contract_id = Some(Uuid::new_v4());
+        task.state = TaskState::InProgress;
+        let task_id = stores.task(task);
+
+        let plan = ResponsePlan::new("degraded").with_action(ResponseAction::Redelegate {
+            task_id,
+            from_agent_id: from,
+        });
+        let outcomes = stores.executor().execute(&plan).await.unwrap();
+        assert_eq!(outcomes.len(), 1);
+
+        let task = stores.tasks.get(&task_id).unwrap().clone();
+        assert_eq!(task.assigned_agent_id, Some(to));
+        assert_eq!(task.contract_id, None);
+        assert_eq!(task.state, TaskState::Pending);
+        assert_eq!(stores.statuses().await, vec!["applied"]);
+
+        let assigned = stores
+            .ledger
+            .query_by_kind(LedgerEntryKind::TaskAssigned)
+            .await
+            .unwrap();
+        assert_eq!(assigned.len(), 1);
+        assert_eq!(assigned[0].payload["previous"], serde_json::json!(from));
+        assert_eq!(assigned[0].payload["new"], serde_json::json!(to));
+        assert_eq!(assigned[0].payload["justification"], "degraded");
+        let changed = stores
+            .ledger
+            .query_by_kind(LedgerEntryKind::TaskStateChanged)
+            .await
+            .unwrap();
+        assert_eq!(changed[0].payload["new"], "Pending");
+    }
+
+    #[tokio::test]
+    async fn test_redelegate_only_to_eligible_agents() {
+        let stores = Stores::new();
+        let from = stores.agent("from");
+        // As able as the bound agent, but with no backend to run on.
+        stores.agent("unbound");
+        let bound = stores.agent("bound");
+        let mut task = Task::new("t", "d").with_capabilities(vec!["code".into()]);
+        task.assigned_agent_id = Some(from);
+        task.state = TaskState::InProgress;
+        let task_id = stores.task(task);
+        let plan = ResponsePlan::new("degraded").with_action(ResponseAction::Redelegate {
+            task_id,
+            from_agent_id: from,
+        });
+
+        let err = stores
+            .executor()
+            .with_eligible_agents([from])
+            .execute(&plan)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no other agent"), "{err}");
+        assert_eq!(
+            stores.tasks.get(&task_id).unwrap().assigned_agent_id,
+            Some(from)
+        );
+
+        stores
+            .executor()
+            .with_eligible_agents([from, bound])
+            .execute(&plan)
+            .await
+            .unwrap();
+        assert_eq!(
+            stores.tasks.get(&task_id).unwrap().assigned_agent_id,
+            Some(bound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_action_rolls_back_plan() {
+        let stores = Stores::new();
+        let agent = stores.agent("only");
+        let mut task = Task::new("t", "d");
+        task.state = TaskState::InProgress;
+        task.assigned_agent_id = Some(agent);
+        let task_id = stores.task(task);
+
+        let plan = ResponsePlan::new("mixed")
+            .with_action(ResponseAction::AdjustParameters {
+                task_id,
+                adjustments: serde_json::json!({"priority": 0.9}),
+            })
+            .with_action(ResponseAction::Redelegate {
+                task_id,
+                from_agent_id: agent,
+            })
+            .with_action(ResponseAction::Terminate {
+                task_id,
+                reason: "never reached".into(),
+            });
+        let err = stores.executor().execute(&plan).await.unwrap_err();
+        assert!(err.to_string().contains("no other agent"));
+
+        let task = stores.tasks.get(&task_id).unwrap().clone();
+        assert_eq!(task.priority, 0.5);
+        assert_eq!(task.state, TaskState::InProgress);
+        assert_eq!(
+            stores.statuses().await,
+            vec!["rolled_back", "failed", "not_attempted"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_terminate_and_redecompose_subtree() {
+        let stores = Stores::new();
+        let mut parent = Task::new("p", "d");
+        let mut done = Task::new("done", "d");
+        done.state = TaskState::Completed;
+        done.parent_id = Some(parent.id);
+        let mut pending = Task::new("pending", "d");
+        pending.parent_id = Some(parent.id);
+        parent.subtask_ids = vec![done.id, pending.id];
+        let (parent_id, done_id, pending_id) = (parent.id, done.id, pending.id);
+        stores.task(parent);
+        stores.task(done);
+        stores.task(pending);
+
+        let plan = ResponsePlan::new("over budget").with_action(ResponseAction::Terminate {
+            task_id: parent_id,
+            reason: "budget".into(),
+        });
+        stores.executor().execute(&plan).await.unwrap();
+        assert_eq!(
+            stores.tasks.get(&pending_id).unwrap().state,
+            TaskState::Failed
+        );
+        assert_eq!(
+            stores.tasks.get(&done_id).unwrap().state,
+            TaskState::Completed
+        );
+        assert_eq!(
+            stores.tasks.get(&parent_id).unwrap().state,
+            TaskState::Failed
+        );
+
+        let plan = ResponsePlan::new("retry")
+            .with_action(ResponseAction::Redecompose { task_id: parent_id });
+        let executor = stores.executor().with_default_strategy("parallel");
+        let outcomes = executor.execute(&plan).await.unwrap();
+        assert!(outcomes[0].summary.contains("(parallel)"));
+
+        let parent = stores.tasks.get(&parent_id).unwrap().clone();
+        assert_eq!(parent.state, TaskState::Pending);
+        assert!(parent.subtask_ids.len() >= 2);
+        assert!(!parent.subtask_ids.contains(&done_id));
+        assert!(!stores.tasks.contains_key(&pending_id));
+        assert!(
+            parent
+                .subtask_ids
+                .iter()
+                .all(|id| stores.tasks.contains_key(id))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_adjust_parameters() {
+        let stores = Stores::new();
+        let task_id = stores.task(Task::new("t", "d"));
+
+        let plan = ResponsePlan::new("priority").with_action(ResponseAction::AdjustParameters {
+            task_id,
+            adjustments: serde_json::json!({
+                "priority": 0.9,
+                "time_sensitivity": 0.8,
+                "deadline": "2030-01-01T00:00:00Z",
+            }),
+        });
+        stores.executor().execute(&plan).await.unwrap();
+        let task = stores.tasks.get(&task_id).unwrap().clone();
+        assert_eq!(task.priority, 0.9);
+        assert_eq!(task.characteristics.time_sensitivity, 0.8);
+        assert!(task.deadline.is_some());
+
+        for adjustments in [
+            serde_json::json!({"priority": 2.0}),
+            serde_json::json!({"colour": "red"}),
+            serde_json::json!({"max_latency_ms": 1000}),
+        ] {
+            let plan = ResponsePlan::new("bad").with_action(ResponseAction::AdjustParameters {
+                task_id,
+                adjustments,
+            });
+            assert!(stores.executor().execute(&plan).await.is_err());
+        }
+        assert_eq!(stores.tasks.get(&task_id).unwrap().priority, 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_adjust_monitoring_terms() {
+        let stores = Stores::new();
+        let mut task = Task::new("t", "d");
+        let contract_id = stores.contract(task.id);
+        task.contract_id = Some(contract_id);
+        let task_id = stores.task(task);
+
+        let plan = ResponsePlan::new("slow").with_action(ResponseAction::AdjustParameters {
+            task_id,
+            adjustments: serde_json::json!({
+                "max_latency_ms": 5000,
+                "checkpoint_interval_secs": 10,
+            }),
+        });
+        stores.executor().execute(&plan).await.unwrap();
+        let monitoring = stores
+            .contracts
+            .get(&contract_id)
+            .unwrap()
+            .monitoring
+            .clone();
+        assert_eq!(monitoring.max_latency_ms, 5000);
+        assert_eq!(monitoring.checkpoint_interval_secs, 10);
+        assert_eq!(monitoring.max_resource_budget, 1000.0);
+
+        let updated = stores
+            .ledger
+            .query_by_kind(LedgerEntryKind::ContractUpdated)
+            .await
+            .unwrap();
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].subject_id, contract_id);
+        assert_eq!(
+            updated[0].payload["previous"]["monitoring"]["max_latency_ms"],
+            60_000
+        );
+        assert_eq!(
+            updated[0].payload["new"]["monitoring"]["max_latency_ms"],
+            5000
+        );
+
+        let plan = ResponsePlan::new("bad").with_action(ResponseAction::AdjustParameters {
+            task_id,
+            adjustments: serde_json::json!({"max_latency_ms": -1}),
+        });
+        assert!(stores.executor().execute(&plan).await.is_err());
+        let monitoring = stores
+            .contracts
+            .get(&contract_id)
+            .unwrap()
+            .monitoring
+            .clone();
+        assert_eq!(monitoring.max_latency_ms, 5000);
+    }
+
+    #[tokio::test]
+    async fn test_escalate_changes_nothing() {
+        let stores = Stores::new();
+        let task_id = stores.task(Task::new("t", "d"));
+        let before = snapshot(stores.tasks.get(&task_id).unwrap().value()).unwrap();
+
+        let plan = ResponsePlan::new("unresponsive")
+            .with_action(ResponseAction::Escalate {
+                task_id: Some(task_id),
+                reason: "no checkpoints".into(),
+            })
+            .with_action(ResponseAction::Escalate {
+                task_id: None,
+                reason: "fleet degraded".into(),
+            });
+        let outcomes = stores.executor().execute(&plan).await.unwrap();
+        assert_eq!(outcomes[0].task_ids, vec![task_id]);
+        assert!(outcomes[0].summary.contains("no checkpoints"));
+        assert!(outcomes[1].task_ids.is_empty());
+        assert_eq!(
+            snapshot(stores.tasks.get(&task_id).unwrap().value()).unwrap(),
+            before
+        );
+        // Only the outcomes are recorded.
+        assert_eq!(stores.statuses().await, vec!["applied", "applied"]);
+        assert_eq!(stores.ledger.all_entries().await.unwrap().len(), 2);
+
+        let plan = ResponsePlan::new("unknown").with_action(ResponseAction::Escalate {
+            task_id: Some(Uuid::new_v4()),
+            reason: "lost".into(),
+        });
+        let err = stores.executor().execute(&plan).await.unwrap_err();
+        assert!(matches!(err, PanopticonError::TaskNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_change_during_redecomposition_aborts_plan() {
+        let stores = Stores::new();
+        let mut parent = Task::new("p", "d");
+        let mut pending = Task::new("pending", "d");
+        pending.parent_id = Some(parent.id);
+        parent.subtask_ids = vec![pending.id];
+        let (parent_id, pending_id) = (parent.id, pending.id);
+        stores.task(parent);
+        stores.task(pending);
+
+        let executor = stores
+            .executor()
+            .with_strategies(vec![Box::new(SlowStrategy)])
+            .with_default_strategy("slow");
+        let plan = ResponsePlan::new("retry")
+            .with_action(ResponseAction::Redecompose { task_id: parent_id });
+        // The subtask starts while the new decomposition is being worked out.
+        let meanwhile = async {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            stores.tasks.get_mut(&pending_id).unwrap().state = TaskState::InProgress;
+        };
+        let (result, ()) = tokio::join!(executor.execute(&plan), meanwhile);
+        let err = result.unwrap_err();
+        assert!(matches!(err, PanopticonError::Conflict(_)), "{err}");
+        assert!(err.to_string().contains("'pending'"));
+
+        // Nothing was committed: the running subtask is kept under its parent.
+        assert_eq!(
+            stores.tasks.get(&pending_id).unwrap().state,
+            TaskState::InProgress
+        );
+        assert_eq!(
+            stores.tasks.get(&parent_id).unwrap().subtask_ids,
+            vec![pending_id]
+        );
+        assert_eq!(stores.tasks.len(), 2);
+        assert_eq!(stores.statuses().await, vec!["conflict"]);
+    }
+}
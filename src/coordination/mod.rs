@@ -1,7 +1,9 @@
 pub mod coordinator;
+pub mod executor;
 pub mod response;
 pub mod trigger;
 
 pub use coordinator::*;
+pub use executor::*;
 pub use response::*;
 pub use trigger::*;
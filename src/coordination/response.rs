@@ -22,6 +22,19 @@ pub enum ResponseAction {
     Terminate { task_id: Uuid, reason: String },
 }
 
+impl ResponseAction {
+    /// The task the action concerns, if any.
+    pub fn task_id(&self) -> Option<Uuid> {
+        match self {
+            Self::AdjustParameters { task_id, .. }
+            | Self::Redelegate { task_id, .. }
+            | Self::Redecompose { task_id }
+            | Self::Terminate { task_id, .. } => Some(*task_id),
+            Self::Escalate { task_id, .. } => *task_id,
+        }
+    }
+}
+
 /// An ordered plan of response actions with a justification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponsePlan {
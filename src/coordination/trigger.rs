@@ -1,6 +1,9 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::monitoring::MonitoringEvent;
+
 /// External triggers originating from outside the system.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExternalTrigger {
@@ -31,6 +34,12 @@ pub enum InternalTrigger {
     AgentUnresponsive {
         agent_id: Uuid,
     },
+    /// An unfinished task is close to its deadline, or (`missed`) past it.
+    DeadlineAtRisk {
+        task_id: Uuid,
+        deadline: DateTime<Utc>,
+        missed: bool,
+    },
 }
 
 /// A coordination trigger that can be either external or internal.
@@ -39,3 +48,42 @@ pub enum CoordinationTrigger {
     External(ExternalTrigger),
     Internal(InternalTrigger),
 }
+
+impl CoordinationTrigger {
+    /// The trigger a monitoring event raises, if any: an exceeded resource budget, another
+    /// SLO violation (as degraded performance), or an unresponsive agent.
+    pub fn from_monitoring(event: &MonitoringEvent) -> Option<Self> {
+        let trigger = match event {
+            MonitoringEvent::SloViolation {
+                task_id,
+                violation,
+                ..
+            } if violation.definition.metric_name == "resource_consumed" => {
+                InternalTrigger::BudgetExceeded {
+                    task_id: *task_id,
+                    consumed: violation.actual_value,
+                    limit: violation.definition.threshold,
+                }
+            }
+            MonitoringEvent::SloViolation {
+                task_id,
+                agent_id,
+                violation,
+            } => InternalTrigger::PerformanceDegraded {
+                task_id: *task_id,
+                agent_id: *agent_id,
+                metric: violation.definition.metric_name.clone(),
+                value: violation.actual_value,
+            },
+            MonitoringEvent::AgentUnresponsive { agent_id, .. } => {
+                InternalTrigger::AgentUnresponsive {
+                    agent_id: *agent_id,
+                }
+            }
+            MonitoringEvent::CheckpointReceived { .. } | MonitoringEvent::TaskTimeout { .. } => {
+                return None;
+            }
+        };
+        Some(Self::Internal(trigger))
+    }
+}
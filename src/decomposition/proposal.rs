@@ -51,6 +51,28 @@ impl DecompositionProposal {
         self.dependencies.push(SubtaskDependency { from, to });
     }
 
+    /// Dependencies as `(from, to)` positions in `subtasks`, the form stored in a parent
+    /// task's `metadata.dependencies`. Edges naming unknown subtasks are dropped.
+    pub fn dependency_indices(&self) -> Vec<(usize, usize)> {
+        let position = |id: Uuid| self.subtasks.iter().position(|t| t.id == id);
+        self.dependencies
+            .iter()
+            .filter_map(|d| Some((position(d.from)?, position(d.to)?)))
+            .collect()
+    }
+
+    /// Link the subtasks to `parent` and record the dependencies and execution order in its
+    /// metadata, where the executor's scheduler reads them.
+    pub fn apply_to(&self, parent: &mut Task) {
+        parent.subtask_ids = self.subtasks.iter().map(|t| t.id).collect();
+        if !parent.metadata.is_object() {
+            parent.metadata = serde_json::json!({});
+        }
+        parent.metadata["dependencies"] = serde_json::json!(self.dependency_indices());
+        parent.metadata["execution_order"] = serde_json::json!(self.execution_order);
+        parent.metadata["parallelism_factor"] = serde_json::json!(self.parallelism_factor);
+    }
+
     /// Check if the DAG has cycles (simple DFS).
     pub fn is_acyclic(&self) -> bool {
         use std::collections::{HashMap, HashSet};
@@ -172,6 +172,15 @@ impl DecompositionStrategy for HybridStrategy {
     }
 }
 
+/// One instance of each built-in strategy, named `sequential`, `parallel`, and `hybrid`.
+pub fn builtin_strategies() -> Vec<Box<dyn DecompositionStrategy>> {
+    vec![
+        Box::new(SequentialStrategy::default()),
+        Box::new(ParallelStrategy::default()),
+        Box::new(HybridStrategy::default()),
+    ]
+}
+
 fn compute_subtask_count(chars: &TaskCharacteristics, max: usize) -> usize {
     let score = chars.complexity * 0.4 + chars.uncertainty * 0.3 + chars.interdependency * 0.3;
     ((score * max as f64).ceil() as usize).max(2).min(max)
@@ -215,6 +224,19 @@ mod tests {
         assert_eq!(proposal.dependencies.len(), proposal.subtasks.len() - 1);
     }
 
+    #[tokio::test]
+    async fn test_apply_to_records_dag_on_parent() {
+        let mut parent = Task::new("test task", "test description");
+        let proposal = SequentialStrategy::default().decompose(&parent).await.unwrap();
+        proposal.apply_to(&mut parent);
+
+        assert_eq!(parent.subtask_ids.len(), proposal.subtasks.len());
+        assert_eq!(parent.metadata["execution_order"], "Sequential");
+        let deps = crate::scheduler::dependencies_from_metadata(&parent.metadata);
+        assert_eq!(deps, proposal.dependency_indices());
+        assert_eq!(deps[0], (0, 1));
+    }
+
     #[tokio::test]
     async fn test_parallel_decomposition() {
         let strategy = ParallelStrategy::default();
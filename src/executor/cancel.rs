@@ -0,0 +1,156 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::watch;
+
+use crate::types::{MonitoringTerms, Task};
+
+/// Cooperative cancellation signal shared between a caller and the executions it starts.
+///
+/// Clones share the same signal; once cancelled, a token stays cancelled.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    tx: Arc<watch::Sender<bool>>,
+    /// Token this one was derived from; cancelling it cancels this one too.
+    parent: Option<Arc<CancellationToken>>,
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        let (tx, _rx) = watch::channel(false);
+        Self {
+            tx: Arc::new(tx),
+            parent: None,
+        }
+    }
+
+    /// A token that is cancelled along with this one, but can also be cancelled on its own
+    /// without affecting this one.
+    pub fn child(&self) -> Self {
+        Self {
+            parent: Some(Arc::new(self.clone())),
+            ..Self::new()
+        }
+    }
+
+    /// Signal cancellation to every holder of this token.
+    pub fn cancel(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.tx.borrow() || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+
+    /// Wait until the token (or one it was derived from) is cancelled.
+    pub async fn cancelled(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender lives as long as `self`, so this only returns once cancelled.
+        let own = rx.wait_for(|cancelled| *cancelled);
+        match &self.parent {
+            Some(parent) => {
+                tokio::select! {
+                    _ = own => {}
+                    _ = Box::pin(parent.cancelled()) => {}
+                }
+            }
+            None => {
+                let _ = own.await;
+            }
+        }
+    }
+}
+
+/// Time budget for executing a task: the tighter of the task's deadline and the
+/// contract's `max_latency_ms`. `None` means unbounded.
+///
+/// A deadline that has already passed sets no limit — the task is still attempted rather
+/// than timing out before it starts; callers warn about it via [`deadline_passed`].
+pub fn execution_timeout(task: &Task, monitoring: Option<&MonitoringTerms>) -> Option<Duration> {
+    let from_deadline = task
+        .deadline
+        .and_then(|d| (d - Utc::now()).to_std().ok())
+        .filter(|remaining| !remaining.is_zero());
+    let from_latency = monitoring
+        .filter(|m| m.max_latency_ms > 0)
+        .map(|m| Duration::from_millis(m.max_latency_ms));
+
+    match (from_deadline, from_latency) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Whether the task's deadline has already passed.
+pub fn deadline_passed(task: &Task) -> bool {
+    task.deadline.is_some_and(|d| d <= Utc::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_wakes_waiters() {
+        let token = CancellationToken::new();
+        let waiter = token.clone();
+        let handle = tokio::spawn(async move { waiter.cancelled().await });
+        assert!(!token.is_cancelled());
+        token.cancel();
+        handle.await.unwrap();
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_child_follows_parent_only() {
+        let parent = CancellationToken::new();
+        let child = parent.child();
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(!parent.is_cancelled());
+
+        let child = parent.child();
+        let waiter = child.clone();
+        let handle = tokio::spawn(async move { waiter.cancelled().await });
+        parent.cancel();
+        handle.await.unwrap();
+        assert!(child.is_cancelled());
+    }
+
+    #[test]
+    fn test_execution_timeout() {
+        let mut task = Task::new("t", "d");
+        assert!(execution_timeout(&task, None).is_none());
+
+        let terms = MonitoringTerms {
+            checkpoint_interval_secs: 30,
+            max_latency_ms: 5_000,
+            min_quality_score: 0.5,
+            max_resource_budget: 100.0,
+        };
+        assert_eq!(
+            execution_timeout(&task, Some(&terms)),
+            Some(Duration::from_secs(5))
+        );
+
+        // A nearer deadline wins over the contract latency.
+        task.deadline = Some(Utc::now() + chrono::Duration::seconds(1));
+        assert!(execution_timeout(&task, Some(&terms)).unwrap() <= Duration::from_secs(1));
+
+        // A passed deadline sets no limit instead of a zero one; the latency still applies.
+        task.deadline = Some(Utc::now() - chrono::Duration::seconds(10));
+        assert!(deadline_passed(&task));
+        assert_eq!(execution_timeout(&task, None), None);
+        assert_eq!(
+            execution_timeout(&task, Some(&terms)),
+            Some(Duration::from_secs(5))
+        );
+    }
+}
@@ -1,12 +1,19 @@
 use async_trait::async_trait;
 use chrono::Utc;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use uuid::Uuid;
 
-use super::{AgentExecutor, ExecutionContext};
-use crate::types::error::PanopticonError;
-use crate::types::Task;
+use super::process::{ProcessTreeGuard, isolate};
+use super::stream::StreamParser;
+use super::template::{BUILTIN_TASK_TEMPLATE, PromptTemplate, task_variables};
+use super::{AgentExecutor, ExecutionContext, ExecutionPolicy};
+use crate::types::error::{ExecutorErrorKind, PanopticonError};
+use crate::types::{ResourceUsage, Task};
 use crate::verification::TaskResult;
 
+/// Permission modes of the `claude` CLI, from the most to the least restrictive.
+const PERMISSION_MODES: [&str; 4] = ["plan", "default", "acceptEdits", "bypassPermissions"];
+
 /// Executor that delegates tasks to the `claude` CLI as a subprocess.
 #[derive(Debug, Clone)]
 pub struct ClaudeExecutor {
@@ -37,8 +44,13 @@ impl ClaudeExecutor {
         self
     }
 
-    /// Build the prompt from a task.
-    fn build_prompt(task: &Task, context: &ExecutionContext) -> String {
+    /// Build the prompt from a task. When continuing a session, the prompt is just the
+    /// feedback turn: the session already holds the task.
+    fn build_prompt(task: &Task, context: &ExecutionContext) -> Result<String, PanopticonError> {
+        if let (Some(_), Some(feedback)) = (&context.session_id, &context.feedback) {
+            return Ok(resume_prompt(feedback, context));
+        }
+
         let mut prompt = String::new();
 
         if let Some(sys) = &context.system_prompt {
@@ -46,54 +58,173 @@ impl ClaudeExecutor {
             prompt.push_str("\n\n");
         }
 
-        prompt.push_str(&format!("# Task: {}\n\n", task.name));
-        prompt.push_str(&format!("{}\n\n", task.description));
-
-        if !task.required_capabilities.is_empty() {
-            prompt.push_str(&format!(
-                "Required capabilities: {}\n\n",
-                task.required_capabilities.join(", ")
-            ));
-        }
+        prompt.push_str(&task_prompt(task, context)?);
+        Ok(prompt)
+    }
 
-        // Only add generic JSON instructions when no system prompt provides its own format.
-        if context.system_prompt.is_none() {
-            prompt.push_str(
-                "Respond with a JSON object containing your result. \
-                 The object should have at minimum a \"result\" key with your output \
-                 and a \"summary\" key with a brief summary.",
-            );
+    /// The permission mode to run under: the configured one, lowered so that `policy` holds.
+    /// `bypassPermissions` skips every check, including `--disallowedTools` and the writable
+    /// directories, so it is kept only for a policy that withholds nothing and confines no
+    /// writes. Otherwise edits are accepted where writing is permitted, and anything outside
+    /// the allowed tools is denied.
+    fn permission_mode(&self, policy: Option<&ExecutionPolicy>) -> &str {
+        let Some(policy) = policy else {
+            return &self.permission_mode;
+        };
+        let ceiling = if !policy.is_restricted() && policy.writable_paths.is_empty() {
+            "bypassPermissions"
+        } else if policy.permits("write") {
+            "acceptEdits"
+        } else {
+            "default"
+        };
+        let rank = |mode: &str| PERMISSION_MODES.iter().position(|m| *m == mode);
+        match (rank(&self.permission_mode), rank(ceiling)) {
+            (Some(configured), Some(limit)) if configured <= limit => &self.permission_mode,
+            _ => ceiling,
         }
-
-        prompt
     }
 
     /// Build the command arguments for `claude` CLI.
-    fn build_args(&self, prompt: &str) -> Vec<String> {
+    /// Streaming runs use `stream-json`, which the CLI only emits with `--verbose`.
+    /// The context's policy, when present, replaces the configured tool allowlist, denies
+    /// the tools of actions the agent is not permitted, and caps the permission mode.
+    fn build_args(&self, prompt: &str, streaming: bool, context: &ExecutionContext) -> Vec<String> {
         let mut args = vec![
             "--model".to_string(),
             self.model.clone(),
             "--permission-mode".to_string(),
-            self.permission_mode.clone(),
+            self.permission_mode(context.policy.as_ref()).to_string(),
             "--output-format".to_string(),
-            "json".to_string(),
         ];
+        if streaming {
+            args.push("stream-json".to_string());
+            args.push("--verbose".to_string());
+        } else {
+            args.push("json".to_string());
+        }
 
         if let Some(turns) = self.max_turns {
             args.push("--max-turns".to_string());
             args.push(turns.to_string());
         }
 
-        for tool in &self.allowed_tools {
+        let allowed_tools = match &context.policy {
+            Some(policy) => &policy.allowed_tools,
+            None => &self.allowed_tools,
+        };
+        for tool in allowed_tools {
             args.push("--allowedTools".to_string());
             args.push(tool.clone());
         }
+        if let Some(policy) = &context.policy {
+            for tool in &policy.disallowed_tools {
+                args.push("--disallowedTools".to_string());
+                args.push(tool.clone());
+            }
+            for dir in policy
+                .writable_paths
+                .iter()
+                .filter(|d| context.working_dir.as_ref() != Some(*d))
+            {
+                args.push("--add-dir".to_string());
+                args.push(dir.clone());
+            }
+        }
+
+        let resume = context
+            .session_id
+            .as_deref()
+            .filter(|_| context.feedback.is_some());
+        if let Some(session_id) = resume {
+            args.push("--resume".to_string());
+            args.push(session_id.to_string());
+        }
 
         args.push("-p".to_string());
         args.push(prompt.to_string());
 
         args
     }
+
+    /// Run the CLI in `stream-json` mode, forwarding progress as checkpoints.
+    /// Returns the final result text, the reported usage, and the session id.
+    async fn run_streaming(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+        args: &[String],
+    ) -> Result<(String, ResourceUsage, Option<String>), PanopticonError> {
+        let mut cmd = tokio::process::Command::new("claude");
+        cmd.args(args)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::inherit());
+
+        if let Some(dir) = &context.working_dir {
+            cmd.current_dir(dir);
+        }
+
+        isolate(&mut cmd);
+        let mut child = cmd.spawn().map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Spawn,
+                format!("Failed to spawn claude CLI: {e}"),
+            )
+        })?;
+        let mut guard = ProcessTreeGuard::new(&child);
+        let stdout = child.stdout.take().ok_or_else(|| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Transport,
+                "claude CLI stdout unavailable",
+            )
+        })?;
+
+        let mut parser = StreamParser::new(
+            task.id,
+            context.agent_id.unwrap_or_default(),
+            self.max_turns,
+        );
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines.next_line().await.map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Transport,
+                format!("Failed to read claude output: {e}"),
+            )
+        })? {
+            if let Some(checkpoint) = parser.feed(&line) {
+                context.report(checkpoint).await;
+            }
+        }
+
+        let status = child.wait().await.map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Transport,
+                format!("Failed to wait for claude CLI: {e}"),
+            )
+        })?;
+        guard.disarm();
+        if !status.success() {
+            return Err(PanopticonError::executor(
+                ExecutorErrorKind::NonZeroExit,
+                format!("claude CLI exited with {status}"),
+            ));
+        }
+
+        let result = parser.result().ok_or_else(|| {
+            PanopticonError::executor(
+                ExecutorErrorKind::InvalidResponse,
+                "claude stream ended without a result event",
+            )
+        })?;
+        if parser.is_error() {
+            return Err(result_error(parser.subtype(), result));
+        }
+        Ok((
+            result.to_string(),
+            parser.usage(),
+            parser.session_id().map(str::to_string),
+        ))
+    }
 }
 
 #[async_trait]
@@ -103,9 +234,29 @@ impl AgentExecutor for ClaudeExecutor {
         task: &Task,
         context: &ExecutionContext,
     ) -> Result<TaskResult, PanopticonError> {
-        let prompt = Self::build_prompt(task, context);
-        let args = self.build_args(&prompt);
+        let prompt = Self::build_prompt(task, context)?;
+        let start = std::time::Instant::now();
+
+        if context.wants_checkpoints() {
+            let args = self.build_args(&prompt, true, context);
+            let (result_text, mut usage, session_id) =
+                self.run_streaming(task, context, &args).await?;
+            if usage.duration_ms == 0 {
+                usage.duration_ms = start.elapsed().as_millis() as u64;
+            }
+            return Ok(TaskResult {
+                task_id: task.id,
+                agent_id: Uuid::nil(), // Will be set by the caller
+                output: parse_agent_output(&result_text),
+                completed_at: Utc::now(),
+                resource_consumed: usage.total_tokens() as f64,
+                usage,
+                artifacts: Vec::new(),
+                session_id,
+            });
+        }
 
+        let args = self.build_args(&prompt, false, context);
         let mut cmd = tokio::process::Command::new("claude");
         cmd.args(&args)
             .stdout(std::process::Stdio::piped())
@@ -115,16 +266,27 @@ impl AgentExecutor for ClaudeExecutor {
             cmd.current_dir(dir);
         }
 
-        let output = cmd
-            .output()
-            .await
-            .map_err(|e| PanopticonError::Internal(format!("Failed to spawn claude CLI: {e}")))?;
+        isolate(&mut cmd);
+        let child = cmd.spawn().map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Spawn,
+                format!("Failed to spawn claude CLI: {e}"),
+            )
+        })?;
+        let mut guard = ProcessTreeGuard::new(&child);
+        let output = child.wait_with_output().await.map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Transport,
+                format!("Failed to wait for claude CLI: {e}"),
+            )
+        })?;
+        guard.disarm();
 
         if !output.status.success() {
-            return Err(PanopticonError::Internal(format!(
-                "claude CLI exited with {}",
-                output.status,
-            )));
+            return Err(PanopticonError::executor(
+                ExecutorErrorKind::NonZeroExit,
+                format!("claude CLI exited with {}", output.status),
+            ));
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
@@ -132,9 +294,10 @@ impl AgentExecutor for ClaudeExecutor {
         // Parse the JSON output from claude CLI.
         // The --output-format json wraps the response in a JSON object with a "result" field.
         let parsed: serde_json::Value = serde_json::from_str(&stdout).map_err(|e| {
-            PanopticonError::Serialization(format!(
-                "Failed to parse claude output: {e}\nRaw output: {stdout}"
-            ))
+            PanopticonError::executor(
+                ExecutorErrorKind::InvalidResponse,
+                format!("Failed to parse claude output: {e}\nRaw output: {stdout}"),
+            )
         })?;
 
         // Extract the actual result text from the claude JSON envelope.
@@ -142,20 +305,30 @@ impl AgentExecutor for ClaudeExecutor {
             .get("result")
             .and_then(|v| v.as_str())
             .unwrap_or_else(|| stdout.as_ref());
+        if parsed.get("is_error").and_then(|e| e.as_bool()) == Some(true) {
+            let subtype = parsed.get("subtype").and_then(|s| s.as_str());
+            return Err(result_error(subtype, result_text));
+        }
 
-        // Strip markdown code fences (```json ... ``` or ``` ... ```) if present.
-        let cleaned = strip_code_fences(result_text);
+        let output_value = parse_agent_output(result_text);
 
-        // Try to parse the result text as JSON; if it fails, wrap it as a string value.
-        let output_value: serde_json::Value = serde_json::from_str(&cleaned)
-            .unwrap_or_else(|_| serde_json::json!({ "result": result_text, "summary": "" }));
+        let mut usage = ResourceUsage::from_claude_envelope(&parsed);
+        if usage.duration_ms == 0 {
+            usage.duration_ms = start.elapsed().as_millis() as u64;
+        }
 
         Ok(TaskResult {
             task_id: task.id,
             agent_id: Uuid::nil(), // Will be set by the caller
             output: output_value,
             completed_at: Utc::now(),
-            resource_consumed: 0.0,
+            resource_consumed: usage.total_tokens() as f64,
+            usage,
+            artifacts: Vec::new(),
+            session_id: parsed
+                .get("session_id")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
         })
     }
 
@@ -174,11 +347,85 @@ impl AgentExecutor for ClaudeExecutor {
     fn name(&self) -> &str {
         "ClaudeExecutor"
     }
+
+    /// Tools of withheld actions are denied and the permission mode lowered to match.
+    fn enforces_policy(&self) -> bool {
+        true
+    }
+
+    /// Runs given a checkpoint channel stream every event of the session.
+    fn reports_checkpoints(&self) -> bool {
+        true
+    }
+}
+
+/// The error for a result the CLI reported with `is_error`, classified by its `subtype`. A run
+/// that stopped at a limit (`error_max_turns`) or failed on its own
+/// (`error_during_execution`) is the agent's failure, which retrying does not fix. An API
+/// error (`success`, with the error as the result text) is classified by its HTTP status like
+/// the http executor's responses, or as a transport failure when it has none.
+fn result_error(subtype: Option<&str>, result: &str) -> PanopticonError {
+    let message = format!(
+        "claude run failed ({}): {result}",
+        subtype.unwrap_or("no subtype")
+    );
+    let api_error = result
+        .find("API Error")
+        .filter(|_| matches!(subtype, None | Some("success")));
+    let kind = match api_error {
+        Some(at) => {
+            let status = result[at..]
+                .split(|c: char| !c.is_ascii_digit())
+                .find(|s| s.len() == 3)
+                .and_then(|s| s.parse::<u16>().ok());
+            match status {
+                Some(status) => super::http::classify_status(status),
+                None => ExecutorErrorKind::Transport,
+            }
+        }
+        None => ExecutorErrorKind::AgentFailed,
+    };
+    PanopticonError::executor(kind, message)
+}
+
+/// Build the task portion of a prompt (everything except the system prompt) from the
+/// context's template, or the built-in `task` template.
+pub(crate) fn task_prompt(
+    task: &Task,
+    context: &ExecutionContext,
+) -> Result<String, PanopticonError> {
+    let template = match &context.prompt_template {
+        Some(source) => PromptTemplate::parse(source)?,
+        None => PromptTemplate::parse(BUILTIN_TASK_TEMPLATE)?,
+    };
+    Ok(template.render(&task_variables(task, context)))
+}
+
+/// The next turn of a continued session: the feedback, plus a reminder of the answer format.
+fn resume_prompt(feedback: &str, context: &ExecutionContext) -> String {
+    if context.system_prompt.is_some() {
+        return feedback.to_string();
+    }
+    format!(
+        "{feedback}\n\nRespond with the complete, updated JSON object: at minimum a \"result\" \
+         key with your output and a \"summary\" key with a brief summary."
+    )
+}
+
+/// Interpret an agent's text answer as JSON.
+/// Code fences are stripped; text that is not JSON is wrapped as `{"result": text}`.
+pub(crate) fn parse_agent_output(text: &str) -> serde_json::Value {
+    // Strip markdown code fences (```json ... ``` or ``` ... ```) if present.
+    let cleaned = strip_code_fences(text);
+
+    // Try to parse the result text as JSON; if it fails, wrap it as a string value.
+    serde_json::from_str(&cleaned)
+        .unwrap_or_else(|_| serde_json::json!({ "result": text, "summary": "" }))
 }
 
 /// Strip markdown code fences from a string.
 /// Handles ```json\n...\n```, ```\n...\n```, and bare JSON.
-fn strip_code_fences(s: &str) -> String {
+pub(crate) fn strip_code_fences(s: &str) -> String {
     let trimmed = s.trim();
     if let Some(rest) = trimmed.strip_prefix("```") {
         // Skip optional language tag on the first line.
@@ -204,7 +451,7 @@ mod tests {
     fn test_build_prompt_default() {
         let task = Task::new("Test task", "Do something useful");
         let ctx = ExecutionContext::default();
-        let prompt = ClaudeExecutor::build_prompt(&task, &ctx);
+        let prompt = ClaudeExecutor::build_prompt(&task, &ctx).unwrap();
         assert!(prompt.contains("Test task"));
         assert!(prompt.contains("Do something useful"));
         assert!(prompt.contains("JSON object"));
@@ -217,21 +464,132 @@ mod tests {
             system_prompt: Some("Custom instructions here".to_string()),
             ..Default::default()
         };
-        let prompt = ClaudeExecutor::build_prompt(&task, &ctx);
+        let prompt = ClaudeExecutor::build_prompt(&task, &ctx).unwrap();
         assert!(prompt.contains("Custom instructions here"));
         assert!(!prompt.contains("JSON object"));
     }
 
+    #[test]
+    fn test_build_prompt_includes_upstream_results() {
+        let mut audit = Task::new("audit", "Audit the site");
+        audit.metadata = serde_json::json!({"result": "3 broken links"});
+        let ctx = ExecutionContext::default()
+            .with_upstream(vec![crate::executor::UpstreamOutput::from_task(&audit)]);
+        let prompt =
+            ClaudeExecutor::build_prompt(&Task::new("report", "Write it up"), &ctx).unwrap();
+        assert!(prompt.contains("prerequisite tasks"));
+        assert!(prompt.contains("3 broken links"));
+        assert!(prompt.find("3 broken links") < prompt.find("JSON object"));
+    }
+
     #[test]
     fn test_build_args() {
         let executor = ClaudeExecutor::default().with_model("opus");
-        let args = executor.build_args("hello");
+        let args = executor.build_args("hello", false, &ExecutionContext::default());
         assert!(args.contains(&"opus".to_string()));
         assert!(args.contains(&"--output-format".to_string()));
         assert!(args.contains(&"json".to_string()));
         assert!(args.contains(&"-p".to_string()));
     }
 
+    #[test]
+    fn test_build_args_streaming() {
+        let args =
+            ClaudeExecutor::default().build_args("hello", true, &ExecutionContext::default());
+        assert!(args.contains(&"stream-json".to_string()));
+        assert!(args.contains(&"--verbose".to_string()));
+        assert!(!args.contains(&"json".to_string()));
+    }
+
+    #[test]
+    fn test_build_args_applies_policy() {
+        let executor = ClaudeExecutor {
+            allowed_tools: vec!["Bash".to_string()],
+            ..Default::default()
+        };
+        let mut ctx = ExecutionContext::default().with_policy(crate::executor::ExecutionPolicy {
+            allowed_actions: vec!["read".to_string()],
+            allowed_tools: vec!["Read".to_string()],
+            disallowed_tools: vec!["Bash".to_string()],
+            writable_paths: vec!["/ws".to_string(), "/shared".to_string()],
+            network: false,
+        });
+        ctx.working_dir = Some("/ws".to_string());
+        let args = executor.build_args("hello", false, &ctx);
+
+        let flag_values = |flag: &str| -> Vec<&str> {
+            args.windows(2)
+                .filter(|w| w[0] == flag)
+                .map(|w| w[1].as_str())
+                .collect()
+        };
+        assert_eq!(flag_values("--allowedTools"), vec!["Read"]);
+        assert_eq!(flag_values("--disallowedTools"), vec!["Bash"]);
+        assert_eq!(flag_values("--add-dir"), vec!["/shared"]);
+        // Neither write nor bypass: only the allowed tools run.
+        assert_eq!(flag_values("--permission-mode"), vec!["default"]);
+    }
+
+    #[test]
+    fn test_permission_mode_follows_policy() {
+        let policy = |actions: &[&str], writable: &[&str]| crate::executor::ExecutionPolicy {
+            allowed_actions: actions.iter().map(|a| a.to_string()).collect(),
+            writable_paths: writable.iter().map(|w| w.to_string()).collect(),
+            ..Default::default()
+        };
+        let all = ["read", "write", "execute", "network"];
+        let bypass = ClaudeExecutor::default();
+        assert_eq!(bypass.permission_mode(None), "bypassPermissions");
+        assert_eq!(
+            bypass.permission_mode(Some(&policy(&all, &[]))),
+            "bypassPermissions"
+        );
+        // Confined writes or a withheld action rule out bypassing the checks.
+        assert_eq!(
+            bypass.permission_mode(Some(&policy(&all, &["/ws"]))),
+            "acceptEdits"
+        );
+        assert_eq!(
+            bypass.permission_mode(Some(&policy(&["read", "write"], &["/ws"]))),
+            "acceptEdits"
+        );
+        assert_eq!(
+            bypass.permission_mode(Some(&policy(&["read"], &[]))),
+            "default"
+        );
+
+        // A stricter configured mode is kept.
+        let plan = ClaudeExecutor {
+            permission_mode: "plan".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(plan.permission_mode(Some(&policy(&all, &[]))), "plan");
+    }
+
+    #[test]
+    fn test_resumed_session_sends_only_feedback() {
+        let task = Task::new("Test task", "Do something useful");
+        let ctx = ExecutionContext::default()
+            .with_session("abc-123")
+            .with_feedback("Missing expected keys: result");
+        let prompt = ClaudeExecutor::build_prompt(&task, &ctx).unwrap();
+        assert!(prompt.starts_with("Missing expected keys: result"));
+        assert!(!prompt.contains("Do something useful"));
+
+        let args = ClaudeExecutor::default().build_args(&prompt, false, &ctx);
+        let resume = args.iter().position(|a| a == "--resume").unwrap();
+        assert_eq!(args[resume + 1], "abc-123");
+    }
+
+    #[test]
+    fn test_feedback_without_session_goes_into_full_prompt() {
+        let task = Task::new("Test task", "Do something useful");
+        let ctx = ExecutionContext::default().with_feedback("Add a summary");
+        let prompt = ClaudeExecutor::build_prompt(&task, &ctx).unwrap();
+        assert!(prompt.contains("Do something useful"));
+        assert!(prompt.contains("## Feedback on your previous attempt\n\nAdd a summary"));
+    }
+
     #[test]
     fn test_strip_code_fences_json() {
         let input = "```json\n{\"key\": \"value\"}\n```";
@@ -249,4 +607,43 @@ mod tests {
         let input = "{\"key\": \"value\"}";
         assert_eq!(strip_code_fences(input), "{\"key\": \"value\"}");
     }
+
+    #[test]
+    fn test_error_results_are_classified_by_subtype() {
+        let kind = |subtype: Option<&str>, result: &str| {
+            result_error(subtype, result).executor_kind().unwrap()
+        };
+        assert_eq!(
+            kind(Some("error_max_turns"), ""),
+            ExecutorErrorKind::AgentFailed
+        );
+        assert_eq!(
+            kind(Some("error_during_execution"), "API Error: 529 overloaded"),
+            ExecutorErrorKind::AgentFailed
+        );
+        assert_eq!(
+            kind(
+                Some("success"),
+                "API Error: 529 {\"type\":\"overloaded_error\"}"
+            ),
+            ExecutorErrorKind::BackendUnavailable
+        );
+        assert_eq!(
+            kind(Some("success"), "API Error: 429 rate limited"),
+            ExecutorErrorKind::RateLimited
+        );
+        assert_eq!(
+            kind(None, "API Error: 401 invalid x-api-key"),
+            ExecutorErrorKind::Rejected
+        );
+        assert_eq!(
+            kind(Some("success"), "API Error: Connection error."),
+            ExecutorErrorKind::Transport
+        );
+        assert_eq!(
+            kind(Some("success"), "I could not finish"),
+            ExecutorErrorKind::AgentFailed
+        );
+        assert!(!ExecutorErrorKind::AgentFailed.is_retryable());
+    }
 }
@@ -0,0 +1,400 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use super::process::{ProcessTreeGuard, isolate};
+use super::{AgentExecutor, ExecutionContext};
+use crate::types::error::{ExecutorErrorKind, PanopticonError};
+use crate::types::{ResourceUsage, Task};
+use crate::verification::TaskResult;
+
+/// Version of the stdin/stdout protocol spoken by `CommandExecutor`.
+pub const COMMAND_PROTOCOL_VERSION: u32 = 1;
+
+/// Request document written to the child's stdin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandRequest {
+    pub protocol_version: u32,
+    pub task: Task,
+    pub context: ExecutionContext,
+}
+
+/// Response document read from the child's stdout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandResponse {
+    pub protocol_version: u32,
+    pub output: serde_json::Value,
+    #[serde(default)]
+    pub resource_consumed: f64,
+    #[serde(default)]
+    pub usage: ResourceUsage,
+    /// Session the program kept for this task; sent back as `context.session_id` on retries.
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+/// Executor that runs an arbitrary program and exchanges JSON over stdin/stdout.
+///
+/// Protocol version 1:
+/// - The program is spawned once per task with the configured arguments.
+/// - A single `CommandRequest` JSON document
+///   (`{"protocol_version": 1, "task": {...}, "context": {...}}`) is written to stdin,
+///   after which stdin is closed.
+/// - The program must exit with status 0 and print a single `CommandResponse` JSON document
+///   (`{"protocol_version": 1, "output": {...}, "resource_consumed": 0.0}`) to stdout.
+///   `resource_consumed`, a `usage` object (see `ResourceUsage`), and `session_id` are optional.
+/// - Anything written to stderr is passed through to the user's terminal.
+#[derive(Debug, Clone)]
+pub struct CommandExecutor {
+    /// Program to spawn (a path or a name resolved via `PATH`).
+    pub program: String,
+    /// Arguments passed to the program.
+    pub args: Vec<String>,
+    /// Extra environment variables set for the program.
+    pub env: Vec<(String, String)>,
+}
+
+impl CommandExecutor {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            env: Vec::new(),
+        }
+    }
+
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Parse the program's stdout into a `CommandResponse`, checking the protocol version.
+    fn parse_response(stdout: &str) -> Result<CommandResponse, PanopticonError> {
+        let response: CommandResponse = serde_json::from_str(stdout.trim()).map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::InvalidResponse,
+                format!("Failed to parse command output: {e}\nRaw output: {stdout}"),
+            )
+        })?;
+
+        if response.protocol_version != COMMAND_PROTOCOL_VERSION {
+            return Err(PanopticonError::executor(
+                ExecutorErrorKind::InvalidResponse,
+                format!(
+                    "Unsupported protocol version {} (expected {})",
+                    response.protocol_version, COMMAND_PROTOCOL_VERSION
+                ),
+            ));
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl AgentExecutor for CommandExecutor {
+    async fn execute(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+    ) -> Result<TaskResult, PanopticonError> {
+        let request = CommandRequest {
+            protocol_version: COMMAND_PROTOCOL_VERSION,
+            task: task.clone(),
+            context: context.clone(),
+        };
+        let input = serde_json::to_vec(&request)
+            .map_err(|e| PanopticonError::Serialization(e.to_string()))?;
+
+        let mut cmd = tokio::process::Command::new(&self.program);
+        cmd.args(&self.args)
+            .envs(self.env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::inherit());
+
+        if let Some(dir) = &context.working_dir {
+            cmd.current_dir(dir);
+        }
+
+        isolate(&mut cmd);
+        let mut child = cmd.spawn().map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Spawn,
+                format!("Failed to spawn {}: {e}", self.program),
+            )
+        })?;
+        let mut guard = ProcessTreeGuard::new(&child);
+
+        // Write the request (closing stdin so the program sees EOF) while collecting the
+        // output: a program may answer before it has read all of a large request. One that
+        // stops reading early is judged by its exit status, not the broken pipe.
+        let stdin = child.stdin.take();
+        let write = async move {
+            let Some(mut stdin) = stdin else {
+                return Ok(());
+            };
+            match stdin.write_all(&input).await {
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            }
+        };
+        let (written, output) = tokio::join!(write, child.wait_with_output());
+        let output = output.map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Transport,
+                format!("Failed to wait for {}: {e}", self.program),
+            )
+        })?;
+        guard.disarm();
+
+        if !output.status.success() {
+            return Err(PanopticonError::executor(
+                ExecutorErrorKind::NonZeroExit,
+                format!("{} exited with {}", self.program, output.status),
+            ));
+        }
+
+        written.map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Transport,
+                format!("Failed to write to {}: {e}", self.program),
+            )
+        })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let response = Self::parse_response(&stdout)?;
+
+        Ok(TaskResult {
+            task_id: task.id,
+            agent_id: Uuid::nil(), // Will be set by the caller
+            output: response.output,
+            completed_at: Utc::now(),
+            resource_consumed: response.resource_consumed,
+            usage: response.usage,
+            artifacts: Vec::new(),
+            session_id: response.session_id,
+        })
+    }
+
+    async fn health_check(&self) -> Result<bool, PanopticonError> {
+        Ok(resolve_program(&self.program).is_some())
+    }
+
+    fn name(&self) -> &str {
+        "CommandExecutor"
+    }
+}
+
+/// Resolve a program name to an existing file, searching `PATH` for bare names.
+fn resolve_program(program: &str) -> Option<std::path::PathBuf> {
+    let path = std::path::Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> CommandExecutor {
+        CommandExecutor::new("sh").with_args(vec!["-c".to_string(), script.to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_execute_parses_response() {
+        let executor = shell(
+            "cat > /dev/null; \
+             echo '{\"protocol_version\": 1, \"output\": {\"result\": \"ok\"}, \
+             \"resource_consumed\": 2.5, \"usage\": {\"output_tokens\": 9, \"cost_usd\": 0.5}}'",
+        );
+        let task = Task::new("Test task", "Do something");
+        let result = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.task_id, task.id);
+        assert_eq!(result.output["result"], "ok");
+        assert!((result.resource_consumed - 2.5).abs() < f64::EPSILON);
+        assert_eq!(result.usage.output_tokens, 9);
+        assert!((result.usage.cost_usd - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_execute_receives_task_on_stdin() {
+        // Only answer if the request carries the protocol version and the task name.
+        let executor = shell(
+            r#"input=$(cat); \
+               case "$input" in *'"protocol_version":1'*'"name":"echo-me"'*) ;; *) exit 1 ;; esac; \
+               echo '{"protocol_version": 1, "output": {"result": "seen"}}'"#,
+        );
+        let task = Task::new("echo-me", "Do something");
+        let result = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.output["result"], "seen");
+    }
+
+    #[tokio::test]
+    async fn test_execute_nonzero_exit_fails() {
+        let executor = shell("cat > /dev/null; exit 3");
+        let task = Task::new("Test task", "Do something");
+        let err = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.executor_kind(), Some(ExecutorErrorKind::NonZeroExit));
+    }
+
+    #[tokio::test]
+    async fn test_large_request_and_response_do_not_deadlock() {
+        // Answers with more than a pipe buffer before reading a request larger than one.
+        let executor = shell(
+            "printf '{\"protocol_version\": 1, \"output\": {\"result\": \"'; \
+             head -c 200000 /dev/zero | tr '\\0' x; \
+             printf '\"}}'; \
+             cat > /dev/null",
+        );
+        let task = Task::new("Test task", "y".repeat(200_000));
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            executor.execute(&task, &ExecutionContext::default()),
+        )
+        .await
+        .expect("deadlocked")
+        .unwrap();
+        assert_eq!(result.output["result"].as_str().unwrap().len(), 200_000);
+    }
+
+    #[tokio::test]
+    async fn test_exit_before_reading_reports_exit_status() {
+        let executor = shell("exit 3");
+        let task = Task::new("Test task", "y".repeat(1_000_000));
+        let err = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.executor_kind(), Some(ExecutorErrorKind::NonZeroExit));
+    }
+
+    #[tokio::test]
+    async fn test_execute_missing_program_is_spawn_error() {
+        let executor = CommandExecutor::new("definitely-not-a-real-program");
+        let err = executor
+            .execute(&Task::new("t", "d"), &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.executor_kind(), Some(ExecutorErrorKind::Spawn));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_kills_process_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("grandchild.pid");
+        // The shell starts a background grandchild and records its pid, then hangs.
+        let executor = shell(&format!(
+            "cat > /dev/null; sleep 30 & echo $! > {}; wait",
+            pid_file.display()
+        ));
+        let task = Task::new("hang", "Never finishes");
+
+        let start = std::time::Instant::now();
+        let err = crate::executor::execute_with_limits(
+            &executor,
+            &task,
+            &ExecutionContext::default(),
+            Some(std::time::Duration::from_millis(300)),
+        )
+        .await
+        .unwrap_err();
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert!(matches!(
+            err,
+            PanopticonError::Executor {
+                kind: ExecutorErrorKind::TimedOut,
+                ..
+            }
+        ));
+
+        let pid: i32 = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        // Give the kernel a moment to deliver SIGKILL, then check the grandchild is gone.
+        let mut alive = true;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            // A killed process may linger as a zombie until reaped, which counts as gone.
+            alive = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+                .map(|stat| !stat.contains(") Z "))
+                .unwrap_or(false);
+            if !alive {
+                break;
+            }
+        }
+        assert!(!alive, "grandchild {pid} survived cancellation");
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_stops_execution() {
+        let executor = shell("cat > /dev/null; sleep 30");
+        let task = Task::new("hang", "Never finishes");
+        let ctx = ExecutionContext::default();
+        let cancel = ctx.cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            cancel.cancel();
+        });
+        let err = crate::executor::execute_with_limits(&executor, &task, &ctx, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PanopticonError::Executor {
+                kind: ExecutorErrorKind::Cancelled,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_response_rejects_wrong_version() {
+        let result = CommandExecutor::parse_response(r#"{"protocol_version": 99, "output": {}}"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_response_rejects_malformed_output() {
+        let err = CommandExecutor::parse_response("not json").unwrap_err();
+        assert_eq!(
+            err.executor_kind(),
+            Some(ExecutorErrorKind::InvalidResponse)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        assert!(CommandExecutor::new("sh").health_check().await.unwrap());
+        assert!(
+            !CommandExecutor::new("definitely-not-a-real-program")
+                .health_check()
+                .await
+                .unwrap()
+        );
+    }
+}
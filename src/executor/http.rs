@@ -0,0 +1,509 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::claude::{parse_agent_output, task_prompt};
+use super::{AgentExecutor, ExecutionContext};
+use crate::types::error::{ExecutorErrorKind, PanopticonError};
+use crate::types::{ResourceUsage, Task};
+use crate::verification::TaskResult;
+
+/// Wire format spoken by an HTTP LLM endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpApi {
+    /// Anthropic Messages API (`POST {base_url}/v1/messages`).
+    Anthropic,
+    /// OpenAI-compatible chat completions (`POST {base_url}/v1/chat/completions`).
+    OpenAi,
+}
+
+/// Time allowed to establish a connection, unless configured otherwise.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time allowed for a whole request, response body included, unless configured otherwise.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Executor that calls an LLM over HTTP instead of shelling out to a CLI.
+#[derive(Debug, Clone)]
+pub struct HttpLlmExecutor {
+    /// Base URL without the API path, e.g. `https://api.anthropic.com` or `http://localhost:8000`.
+    pub base_url: String,
+    /// Wire format of the endpoint.
+    pub api: HttpApi,
+    /// Model identifier sent with each request.
+    pub model: String,
+    /// Maximum tokens to generate per request.
+    pub max_tokens: u32,
+    /// Environment variable holding the API key (`None` = no authentication).
+    pub auth_env: Option<String>,
+    /// Extra headers sent with each request.
+    pub headers: BTreeMap<String, String>,
+    /// Time allowed to establish a connection.
+    pub connect_timeout: Duration,
+    /// Time allowed for a whole request, response body included.
+    pub request_timeout: Duration,
+    client: reqwest::Client,
+}
+
+impl HttpLlmExecutor {
+    pub fn new(base_url: impl Into<String>, api: HttpApi, model: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api,
+            model: model.into(),
+            max_tokens: 4096,
+            auth_env: None,
+            headers: BTreeMap::new(),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            client: client(DEFAULT_CONNECT_TIMEOUT, DEFAULT_REQUEST_TIMEOUT),
+        }
+    }
+
+    /// Bound the time spent connecting and the time spent on a whole request, so an endpoint
+    /// that accepts the connection but never answers fails instead of hanging the task.
+    pub fn with_timeouts(mut self, connect: Duration, request: Duration) -> Self {
+        self.connect_timeout = connect;
+        self.request_timeout = request;
+        self.client = client(connect, request);
+        self
+    }
+
+    pub fn with_auth_env(mut self, var: impl Into<String>) -> Self {
+        self.auth_env = Some(var.into());
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Full endpoint URL for the configured API.
+    fn endpoint(&self) -> String {
+        let base = self.base_url.trim_end_matches('/');
+        match self.api {
+            HttpApi::Anthropic => format!("{base}/v1/messages"),
+            HttpApi::OpenAi => format!("{base}/v1/chat/completions"),
+        }
+    }
+
+    /// A failed request as a (retryable) transport error, naming the limit it ran into.
+    fn transport_error(&self, what: &str, e: reqwest::Error) -> PanopticonError {
+        let message = if e.is_connect() && e.is_timeout() {
+            format!("{what}: no connection within {:?}", self.connect_timeout)
+        } else if e.is_timeout() {
+            format!("{what}: no response within {:?}", self.request_timeout)
+        } else {
+            format!("{what}: {e}")
+        };
+        PanopticonError::executor(ExecutorErrorKind::Transport, message)
+    }
+
+    /// Build the JSON request body for a task.
+    fn build_body(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+    ) -> Result<serde_json::Value, PanopticonError> {
+        let user = task_prompt(task, context)?;
+        Ok(match self.api {
+            HttpApi::Anthropic => {
+                let mut body = serde_json::json!({
+                    "model": self.model,
+                    "max_tokens": self.max_tokens,
+                    "messages": [{ "role": "user", "content": user }],
+                });
+                if let Some(sys) = &context.system_prompt {
+                    body["system"] = serde_json::Value::String(sys.clone());
+                }
+                body
+            }
+            HttpApi::OpenAi => {
+                let mut messages = Vec::new();
+                if let Some(sys) = &context.system_prompt {
+                    messages.push(serde_json::json!({ "role": "system", "content": sys }));
+                }
+                messages.push(serde_json::json!({ "role": "user", "content": user }));
+                serde_json::json!({
+                    "model": self.model,
+                    "max_tokens": self.max_tokens,
+                    "messages": messages,
+                })
+            }
+        })
+    }
+
+    /// Extract the answer text and token usage from a successful response body.
+    fn parse_body(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<(String, ResourceUsage), PanopticonError> {
+        let (text, usage) = match self.api {
+            HttpApi::Anthropic => {
+                let text = body
+                    .get("content")
+                    .and_then(|c| c.as_array())
+                    .map(|blocks| {
+                        blocks
+                            .iter()
+                            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                            .collect::<Vec<_>>()
+                            .join("")
+                    });
+                let usage = body.get("usage").map(|u| ResourceUsage {
+                    input_tokens: u["input_tokens"].as_u64().unwrap_or(0),
+                    output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
+                    cache_creation_input_tokens: u["cache_creation_input_tokens"]
+                        .as_u64()
+                        .unwrap_or(0),
+                    cache_read_input_tokens: u["cache_read_input_tokens"].as_u64().unwrap_or(0),
+                    ..Default::default()
+                });
+                (text, usage)
+            }
+            HttpApi::OpenAi => {
+                let text = body
+                    .pointer("/choices/0/message/content")
+                    .and_then(|c| c.as_str())
+                    .map(|s| s.to_string());
+                let usage = body.get("usage").map(|u| ResourceUsage {
+                    input_tokens: u["prompt_tokens"].as_u64().unwrap_or(0),
+                    output_tokens: u["completion_tokens"].as_u64().unwrap_or(0),
+                    ..Default::default()
+                });
+                (text, usage)
+            }
+        };
+
+        let text = text.ok_or_else(|| {
+            PanopticonError::executor(
+                ExecutorErrorKind::InvalidResponse,
+                format!("Response has no message content: {body}"),
+            )
+        })?;
+        Ok((text, usage.unwrap_or_default()))
+    }
+
+    /// Resolve the API key from the configured environment variable.
+    fn api_key(&self) -> Result<Option<String>, PanopticonError> {
+        match &self.auth_env {
+            None => Ok(None),
+            Some(var) => std::env::var(var).map(Some).map_err(|_| {
+                PanopticonError::executor(
+                    ExecutorErrorKind::Rejected,
+                    format!("Environment variable {var} is not set"),
+                )
+            }),
+        }
+    }
+}
+
+/// Classify a non-success HTTP status.
+pub(super) fn classify_status(status: u16) -> ExecutorErrorKind {
+    match status {
+        408 | 429 => ExecutorErrorKind::RateLimited,
+        500..=599 => ExecutorErrorKind::BackendUnavailable,
+        _ => ExecutorErrorKind::Rejected,
+    }
+}
+
+#[async_trait]
+impl AgentExecutor for HttpLlmExecutor {
+    async fn execute(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+    ) -> Result<TaskResult, PanopticonError> {
+        let start = std::time::Instant::now();
+        let mut request = self
+            .client
+            .post(self.endpoint())
+            .json(&self.build_body(task, context)?);
+
+        if let Some(key) = self.api_key()? {
+            request = match self.api {
+                HttpApi::Anthropic => request.header("x-api-key", key),
+                HttpApi::OpenAi => request.bearer_auth(key),
+            };
+        }
+        if self.api == HttpApi::Anthropic && !self.headers.contains_key("anthropic-version") {
+            request = request.header("anthropic-version", "2023-06-01");
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| self.transport_error("HTTP request failed", e))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| self.transport_error("Failed to read response body", e))?;
+
+        if !status.is_success() {
+            return Err(PanopticonError::executor(
+                classify_status(status.as_u16()),
+                format!("{} returned {status}: {text}", self.endpoint()),
+            ));
+        }
+
+        let body: serde_json::Value = serde_json::from_str(&text).map_err(|e| {
+            PanopticonError::executor(
+                ExecutorErrorKind::InvalidResponse,
+                format!("Failed to parse response: {e}\nRaw output: {text}"),
+            )
+        })?;
+        let (answer, mut usage) = self.parse_body(&body)?;
+        usage.duration_ms = start.elapsed().as_millis() as u64;
+        usage.num_turns = 1;
+
+        Ok(TaskResult {
+            task_id: task.id,
+            agent_id: Uuid::nil(), // Will be set by the caller
+            output: parse_agent_output(&answer),
+            completed_at: Utc::now(),
+            resource_consumed: usage.total_tokens() as f64,
+            usage,
+            artifacts: Vec::new(),
+            session_id: None,
+        })
+    }
+
+    async fn health_check(&self) -> Result<bool, PanopticonError> {
+        Ok(self.api_key().is_ok())
+    }
+
+    fn name(&self) -> &str {
+        "HttpLlmExecutor"
+    }
+
+    /// The model is offered no tools, so it can take none of the standard actions.
+    fn enforces_policy(&self) -> bool {
+        true
+    }
+}
+
+fn client(connect_timeout: Duration, request_timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(connect_timeout)
+        .timeout(request_timeout)
+        .build()
+        .expect("failed to initialise the HTTP client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve a single canned HTTP response and hand back the raw request text.
+    async fn stub_server(
+        status: u16,
+        body: &'static str,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|l| {
+                            let (name, value) = l.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 {status} Stub\r\ncontent-type: application/json\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (format!("http://{addr}"), handle)
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_roundtrip() {
+        let (url, server) = stub_server(
+            200,
+            r#"{"content": [{"type": "text", "text": "{\"result\": \"hi\", \"summary\": \"s\"}"}],
+                "usage": {"input_tokens": 10, "output_tokens": 5}}"#,
+        )
+        .await;
+        let executor = HttpLlmExecutor::new(url, HttpApi::Anthropic, "claude-test")
+            .with_header("x-team", "panopticon");
+        let ctx = ExecutionContext {
+            system_prompt: Some("Be brief".to_string()),
+            ..Default::default()
+        };
+        let task = Task::new("Greet", "Say hi");
+        let result = executor.execute(&task, &ctx).await.unwrap();
+
+        assert_eq!(result.output["result"], "hi");
+        assert!((result.resource_consumed - 15.0).abs() < f64::EPSILON);
+        assert_eq!(result.usage.input_tokens, 10);
+        assert_eq!(result.usage.num_turns, 1);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/messages"));
+        assert!(request.contains("anthropic-version"));
+        assert!(request.contains("x-team: panopticon"));
+        assert!(request.contains("\"system\":\"Be brief\""));
+        assert!(request.contains("claude-test"));
+    }
+
+    #[tokio::test]
+    async fn test_openai_roundtrip() {
+        let (url, server) = stub_server(
+            200,
+            r#"{"choices": [{"message": {"role": "assistant", "content": "plain text"}}],
+                "usage": {"prompt_tokens": 7, "completion_tokens": 3}}"#,
+        )
+        .await;
+        let executor = HttpLlmExecutor::new(url, HttpApi::OpenAi, "local-model");
+        let task = Task::new("Greet", "Say hi");
+        let result = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap();
+
+        // Non-JSON answers are wrapped like the CLI executor does.
+        assert_eq!(result.output["result"], "plain text");
+        assert!((result.resource_consumed - 10.0).abs() < f64::EPSILON);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_is_retryable() {
+        let (url, _server) = stub_server(429, r#"{"error": "slow down"}"#).await;
+        let executor = HttpLlmExecutor::new(url, HttpApi::Anthropic, "m");
+        let err = executor
+            .execute(&Task::new("t", "d"), &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PanopticonError::Executor {
+                kind: ExecutorErrorKind::RateLimited,
+                ..
+            }
+        ));
+        assert!(err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_bad_request_is_not_retryable() {
+        let (url, _server) = stub_server(400, r#"{"error": "bad"}"#).await;
+        let executor = HttpLlmExecutor::new(url, HttpApi::OpenAi, "m");
+        let err = executor
+            .execute(&Task::new("t", "d"), &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert!(!err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_is_transport_error() {
+        // Bind then drop a listener to get a port nothing listens on.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let executor = HttpLlmExecutor::new(format!("http://{addr}"), HttpApi::OpenAi, "m");
+        let err = executor
+            .execute(&Task::new("t", "d"), &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PanopticonError::Executor {
+                kind: ExecutorErrorKind::Transport,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_endpoint_times_out() {
+        // Accept the connection but never answer.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            drop(socket);
+        });
+
+        let executor = HttpLlmExecutor::new(format!("http://{addr}"), HttpApi::OpenAi, "m")
+            .with_timeouts(Duration::from_secs(1), Duration::from_millis(200));
+        let err = tokio::time::timeout(
+            Duration::from_secs(5),
+            executor.execute(&Task::new("t", "d"), &ExecutionContext::default()),
+        )
+        .await
+        .expect("the request timeout did not fire")
+        .unwrap_err();
+        assert!(err.is_retryable());
+        assert!(err.to_string().contains("no response within"), "{err}");
+        server.abort();
+    }
+
+    #[test]
+    fn test_missing_content_is_invalid_response() {
+        let executor = HttpLlmExecutor::new("http://unused", HttpApi::Anthropic, "m");
+        let err = executor
+            .parse_body(&serde_json::json!({"usage": {}}))
+            .unwrap_err();
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_classify_status() {
+        assert_eq!(classify_status(429), ExecutorErrorKind::RateLimited);
+        assert_eq!(classify_status(503), ExecutorErrorKind::BackendUnavailable);
+        assert_eq!(classify_status(401), ExecutorErrorKind::Rejected);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_requires_auth_env() {
+        let executor = HttpLlmExecutor::new("http://unused", HttpApi::Anthropic, "m")
+            .with_auth_env("PANOPTICON_TEST_DEFINITELY_UNSET_KEY");
+        assert!(!executor.health_check().await.unwrap());
+    }
+}
@@ -1,9 +1,31 @@
+pub mod cancel;
 pub mod claude;
+pub mod command;
+pub mod http;
+pub mod policy;
+mod process;
+pub mod registry;
+pub mod retry;
+pub mod schema;
+pub mod scripted;
+pub mod stream;
+pub mod template;
+pub mod upstream;
+pub mod workspace;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
 
-use crate::types::error::PanopticonError;
+use crate::config::PanopticonConfig;
+use crate::monitoring::Checkpoint;
+use crate::types::DelegationContract;
+use crate::types::error::{ExecutorErrorKind, PanopticonError};
 use crate::verification::TaskResult;
 
 /// Context passed to the executor for a task run.
@@ -13,6 +35,92 @@ pub struct ExecutionContext {
     pub working_dir: Option<String>,
     /// Extra context/instructions to prepend to the prompt.
     pub system_prompt: Option<String>,
+    /// Agent the task is executed on behalf of, stamped onto checkpoints.
+    #[serde(default)]
+    pub agent_id: Option<Uuid>,
+    /// Results of the task's completed dependencies.
+    #[serde(default)]
+    pub upstream: Vec<UpstreamOutput>,
+    /// Contract the task is executed under, if any.
+    #[serde(default)]
+    pub contract: Option<DelegationContract>,
+    /// What the agent may do during this execution; executors map it onto their own controls.
+    #[serde(default)]
+    pub policy: Option<ExecutionPolicy>,
+    /// Template for the task prompt (source text); `None` uses the built-in `task` template.
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+    /// Why the previous output was rejected, when this run is a schema repair round.
+    #[serde(default)]
+    pub repair: Option<SchemaRepair>,
+    /// Backend session to continue instead of starting a new one.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Feedback on the previous run (a verification failure or a follow-up request).
+    /// When continuing a session it is sent as the next turn.
+    #[serde(default)]
+    pub feedback: Option<String>,
+    /// Channel for live checkpoints. Executors that can stream report progress here.
+    #[serde(skip)]
+    pub checkpoint_tx: Option<mpsc::Sender<Checkpoint>>,
+    /// Cancellation signal; executions stop (and kill their process trees) once it fires.
+    #[serde(skip)]
+    pub cancel: CancellationToken,
+}
+
+impl ExecutionContext {
+    pub fn with_upstream(mut self, upstream: Vec<UpstreamOutput>) -> Self {
+        self.upstream = upstream;
+        self
+    }
+
+    pub fn with_contract(mut self, contract: DelegationContract) -> Self {
+        self.contract = Some(contract);
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn with_prompt_template(mut self, source: impl Into<String>) -> Self {
+        self.prompt_template = Some(source.into());
+        self
+    }
+
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    pub fn with_feedback(mut self, feedback: impl Into<String>) -> Self {
+        self.feedback = Some(feedback.into());
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn with_checkpoints(mut self, agent_id: Uuid, tx: mpsc::Sender<Checkpoint>) -> Self {
+        self.agent_id = Some(agent_id);
+        self.checkpoint_tx = Some(tx);
+        self
+    }
+
+    /// Whether anyone is listening for checkpoints.
+    pub fn wants_checkpoints(&self) -> bool {
+        self.checkpoint_tx.is_some()
+    }
+
+    /// Send a checkpoint to the listener, if any. A closed channel is ignored.
+    pub async fn report(&self, checkpoint: Checkpoint) {
+        if let Some(tx) = &self.checkpoint_tx {
+            let _ = tx.send(checkpoint).await;
+        }
+    }
 }
 
 /// Trait for agent executors.
@@ -30,6 +138,131 @@ pub trait AgentExecutor: Send + Sync {
 
     /// Name of this executor.
     fn name(&self) -> &str;
+
+    /// Whether the backend keeps the agent within `context.policy`. Runs whose policy
+    /// withholds an action are refused on backends that do not.
+    fn enforces_policy(&self) -> bool {
+        false
+    }
+
+    /// Whether the backend reports checkpoints on `context.checkpoint_tx` throughout a run, so
+    /// that a silent agent can be told from a stalled one. Runs on backends that do not are
+    /// checked against their SLOs but never reported unresponsive.
+    fn reports_checkpoints(&self) -> bool {
+        false
+    }
+}
+
+/// Run `executor` on `task`, giving up once `context.cancel` fires or `timeout` elapses.
+/// Abandoning the execution drops it, which kills any process tree it spawned.
+pub async fn execute_with_limits(
+    executor: &dyn AgentExecutor,
+    task: &crate::types::Task,
+    context: &ExecutionContext,
+    timeout: Option<Duration>,
+) -> Result<TaskResult, PanopticonError> {
+    let timer = async {
+        match timeout {
+            Some(limit) => tokio::time::sleep(limit).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = executor.execute(task, context) => result,
+        _ = context.cancel.cancelled() => Err(PanopticonError::executor(
+            ExecutorErrorKind::Cancelled,
+            format!("Execution of '{}' was cancelled", task.name),
+        )),
+        _ = timer => Err(PanopticonError::executor(
+            ExecutorErrorKind::TimedOut,
+            format!(
+                "Execution of '{}' timed out after {:.1}s",
+                task.name,
+                timeout.unwrap_or_default().as_secs_f64()
+            ),
+        )),
+    }
+}
+
+/// Build the executor backend selected by `config.executor` for the given model.
+/// Relative fixture paths are resolved against `state_dir` when one is given.
+pub fn from_config(
+    config: &PanopticonConfig,
+    model: &str,
+    state_dir: Option<&Path>,
+) -> Result<Arc<dyn AgentExecutor>, PanopticonError> {
+    match config.executor.as_str() {
+        "claude" => Ok(Arc::new(ClaudeExecutor {
+            model: model.to_string(),
+            permission_mode: config.permission_mode.clone(),
+            max_turns: Some(config.max_turns),
+            allowed_tools: config.allowed_tools.clone(),
+        })),
+        "command" => {
+            let (program, args) = config.command.split_first().ok_or_else(|| {
+                PanopticonError::Internal(
+                    "executor = \"command\" requires a non-empty `command` list".to_string(),
+                )
+            })?;
+            Ok(Arc::new(CommandExecutor::new(program).with_args(args.to_vec())))
+        }
+        "scripted" => {
+            let fixtures = config.scripted_fixtures.as_deref().ok_or_else(|| {
+                PanopticonError::Internal(
+                    "executor = \"scripted\" requires `scripted_fixtures`".to_string(),
+                )
+            })?;
+            let mut path = std::path::PathBuf::from(fixtures);
+            if let Some(dir) = state_dir.filter(|_| path.is_relative()) {
+                path = dir.join(path);
+            }
+            Ok(Arc::new(ScriptedExecutor::from_file(&path)?))
+        }
+        "http" => {
+            let http = &config.http;
+            let api = match http.api.as_str() {
+                "anthropic" => HttpApi::Anthropic,
+                "openai" => HttpApi::OpenAi,
+                other => {
+                    return Err(PanopticonError::Internal(format!(
+                        "Unknown http api: {other} (use anthropic, openai)"
+                    )));
+                }
+            };
+            let mut executor = HttpLlmExecutor::new(
+                &http.base_url,
+                api,
+                http.model.as_deref().unwrap_or(model),
+            )
+            .with_max_tokens(http.max_tokens)
+            .with_timeouts(
+                Duration::from_secs(http.connect_timeout_secs),
+                Duration::from_secs(http.request_timeout_secs),
+            );
+            if let Some(var) = &http.auth_env {
+                executor = executor.with_auth_env(var);
+            }
+            for (name, value) in &http.headers {
+                executor = executor.with_header(name, value);
+            }
+            Ok(Arc::new(executor))
+        }
+        other => Err(PanopticonError::Internal(format!(
+            "Unknown executor: {other} (use claude, command, scripted, http)"
+        ))),
+    }
 }
 
+pub use cancel::{CancellationToken, deadline_passed, execution_timeout};
 pub use claude::ClaudeExecutor;
+pub use command::{COMMAND_PROTOCOL_VERSION, CommandExecutor};
+pub use http::{HttpApi, HttpLlmExecutor};
+pub use policy::{ExecutionPolicy, STANDARD_ACTIONS, standard_actions};
+pub use registry::{ExecutorBinding, ExecutorRegistry};
+pub use retry::{RetryPolicy, RetryingExecutor};
+pub use schema::{SchemaRepair, ValidatingExecutor, render_repair};
+pub use scripted::{ScriptedExecutor, ScriptedFixtures, ScriptedRule};
+pub use template::{PromptTemplate, TemplateStore};
+pub use upstream::{UpstreamLimits, UpstreamOutput};
+pub use workspace::{Workspace, WorkspaceConfig, WorkspaceSnapshot};
@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+
+use super::AgentExecutor;
+use crate::types::error::PanopticonError;
+use crate::types::{Agent, DelegationContract, Task};
+
+/// Actions with executor-level controls, and the tools each one unlocks.
+/// Other action names (e.g. capability names) are kept in the policy but gate no tools.
+pub const STANDARD_ACTIONS: [(&str, &[&str]); 4] = [
+    ("read", &["Read", "Glob", "Grep", "LS"]),
+    ("write", &["Edit", "MultiEdit", "Write", "NotebookEdit"]),
+    ("execute", &["Bash"]),
+    ("network", &["WebFetch", "WebSearch"]),
+];
+
+/// The standard action names, in the order of `STANDARD_ACTIONS`.
+pub fn standard_actions() -> Vec<String> {
+    STANDARD_ACTIONS
+        .iter()
+        .map(|(action, _)| action.to_string())
+        .collect()
+}
+
+/// The standard action a tool belongs to, if any.
+fn action_for_tool(tool: &str) -> Option<&'static str> {
+    STANDARD_ACTIONS
+        .iter()
+        .find(|(_, tools)| tools.contains(&tool))
+        .map(|(action, _)| *action)
+}
+
+/// What an agent may do while executing one task: its permissions narrowed by the
+/// task's contract, mapped onto the controls executors understand.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionPolicy {
+    /// Effective actions: the agent's `allowed_actions` intersected with the contract's
+    /// `permitted_actions`, if there is a contract.
+    pub allowed_actions: Vec<String>,
+    /// Tools the executor may offer the agent.
+    pub allowed_tools: Vec<String>,
+    /// Tools of the standard actions that are not permitted.
+    pub disallowed_tools: Vec<String>,
+    /// Directories the agent may write to (empty unless `write` is permitted).
+    pub writable_paths: Vec<String>,
+    /// Whether the agent may reach the network.
+    pub network: bool,
+}
+
+impl ExecutionPolicy {
+    /// Compute the policy for `agent` running `task`.
+    ///
+    /// `configured_tools` is the tool allowlist from the agent's backend config (empty = derive
+    /// it from the actions); `workspace` is the directory the task runs in.
+    /// Fails with `PermissionDenied` when the contract belongs to another agent, when the task
+    /// requires a standard action that is not permitted, when a configured tool belongs to
+    /// an action that is not permitted, or when `execute` is permitted without `write` or
+    /// `network` (shell commands can write files and reach the network, so that policy cannot
+    /// be enforced).
+    pub fn resolve(
+        agent: &Agent,
+        task: &Task,
+        contract: Option<&DelegationContract>,
+        configured_tools: &[String],
+        workspace: Option<&str>,
+    ) -> Result<Self, PanopticonError> {
+        let mut allowed_actions = agent.permissions.allowed_actions.clone();
+        if let Some(contract) = contract {
+            if contract.delegatee_id != agent.id {
+                return Err(PanopticonError::PermissionDenied(format!(
+                    "contract {} delegates '{}' to another agent",
+                    contract.id, task.name
+                )));
+            }
+            allowed_actions.retain(|a| contract.permitted_actions.contains(a));
+        }
+        let permitted = |action: &str| allowed_actions.iter().any(|a| a == action);
+
+        if permitted("execute") {
+            let withheld: Vec<&str> = ["write", "network"]
+                .into_iter()
+                .filter(|action| !permitted(action))
+                .collect();
+            if !withheld.is_empty() {
+                return Err(PanopticonError::PermissionDenied(format!(
+                    "{} may execute commands but not {}, which shell commands can do anyway; \
+                     permit execute only together with write and network",
+                    agent.name,
+                    withheld.join(" or ")
+                )));
+            }
+        }
+
+        for required in &task.required_capabilities {
+            let standard = STANDARD_ACTIONS.iter().any(|(a, _)| a == required);
+            if standard && !permitted(required) {
+                return Err(PanopticonError::PermissionDenied(format!(
+                    "'{}' requires '{required}', which {} is not permitted",
+                    task.name, agent.name
+                )));
+            }
+        }
+
+        for tool in configured_tools {
+            if let Some(action) = action_for_tool(tool).filter(|a| !permitted(a)) {
+                return Err(PanopticonError::PermissionDenied(format!(
+                    "allowed_tools grants {tool}, but {} is not permitted to {action}",
+                    agent.name
+                )));
+            }
+        }
+
+        let (allowed, disallowed): (Vec<_>, Vec<_>) =
+            STANDARD_ACTIONS.iter().partition(|(a, _)| permitted(a));
+        let allowed_tools = if configured_tools.is_empty() {
+            allowed
+                .iter()
+                .flat_map(|(_, tools)| tools.iter().map(|t| t.to_string()))
+                .collect()
+        } else {
+            configured_tools.to_vec()
+        };
+        let disallowed_tools = disallowed
+            .iter()
+            .flat_map(|(_, tools)| tools.iter().map(|t| t.to_string()))
+            .collect();
+
+        Ok(Self {
+            writable_paths: workspace
+                .filter(|_| permitted("write"))
+                .map(|w| vec![w.to_string()])
+                .unwrap_or_default(),
+            network: permitted("network"),
+            allowed_actions,
+            allowed_tools,
+            disallowed_tools,
+        })
+    }
+
+    /// Whether the policy permits an action.
+    pub fn permits(&self, action: &str) -> bool {
+        self.allowed_actions.iter().any(|a| a == action)
+    }
+
+    /// Whether the policy withholds any of the standard actions.
+    pub fn is_restricted(&self) -> bool {
+        STANDARD_ACTIONS
+            .iter()
+            .any(|(action, _)| !self.permits(action))
+    }
+
+    /// Refuse a restricted policy on a backend that cannot enforce it, with `PermissionDenied`
+    /// naming the actions it would have to withhold.
+    pub fn enforceable_by(self, executor: &dyn AgentExecutor) -> Result<Self, PanopticonError> {
+        if !self.is_restricted() || executor.enforces_policy() {
+            return Ok(self);
+        }
+        let withheld: Vec<&str> = STANDARD_ACTIONS
+            .iter()
+            .map(|(action, _)| *action)
+            .filter(|action| !self.permits(action))
+            .collect();
+        Err(PanopticonError::PermissionDenied(format!(
+            "{} cannot withhold {} from the agent; permit every standard action or use a \
+             backend that enforces policies",
+            executor.name(),
+            withheld.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DisputeResolutionTerms, MonitoringTerms, PaymentTerms};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn agent(actions: &[&str]) -> Agent {
+        let mut agent = Agent::new("worker");
+        agent.permissions.allowed_actions = actions.iter().map(|a| a.to_string()).collect();
+        agent
+    }
+
+    fn contract(task: &Task, delegatee_id: Uuid, actions: &[&str]) -> DelegationContract {
+        DelegationContract {
+            id: Uuid::new_v4(),
+            task_id: task.id,
+            delegator_id: Uuid::new_v4(),
+            delegatee_id,
+            payment: PaymentTerms {
+                total_amount: 1.0,
+                escrow_amount: 0.0,
+                milestone_payments: Vec::new(),
+                penalty_rate: 0.0,
+            },
+            monitoring: MonitoringTerms {
+                checkpoint_interval_secs: 60,
+                max_latency_ms: 60_000,
+                min_quality_score: 0.5,
+                max_resource_budget: 1000.0,
+            },
+            dispute_resolution: DisputeResolutionTerms {
+                dispute_bond: 0.0,
+                resolution_timeout_secs: 60,
+                panel_size: 1,
+                escalation_enabled: false,
+            },
+            permitted_actions: actions.iter().map(|a| a.to_string()).collect(),
+            max_delegation_depth: 0,
+            created_at: Utc::now(),
+            expires_at: None,
+            signed_by_delegator: true,
+            signed_by_delegatee: true,
+        }
+    }
+
+    #[test]
+    fn test_actions_map_to_tools() {
+        let task = Task::new("t", "d");
+        let policy =
+            ExecutionPolicy::resolve(&agent(&["read", "write"]), &task, None, &[], Some("/ws"))
+                .unwrap();
+        assert!(policy.allowed_tools.contains(&"Read".to_string()));
+        assert!(policy.allowed_tools.contains(&"Write".to_string()));
+        assert!(policy.disallowed_tools.contains(&"Bash".to_string()));
+        assert!(policy.disallowed_tools.contains(&"WebFetch".to_string()));
+        assert_eq!(policy.writable_paths, vec!["/ws".to_string()]);
+        assert!(!policy.network);
+    }
+
+    #[test]
+    fn test_contract_narrows_actions() {
+        let worker = agent(&["read", "write", "execute", "network"]);
+        let task = Task::new("t", "d");
+        let contract = contract(&task, worker.id, &["read"]);
+
+        let policy =
+            ExecutionPolicy::resolve(&worker, &task, Some(&contract), &[], Some("/ws")).unwrap();
+        assert_eq!(policy.allowed_actions, vec!["read".to_string()]);
+        assert!(policy.writable_paths.is_empty());
+        assert!(policy.disallowed_tools.contains(&"Edit".to_string()));
+    }
+
+    #[test]
+    fn test_contract_for_other_agent_is_refused() {
+        let worker = agent(&["read"]);
+        let task = Task::new("t", "d");
+        let contract = contract(&task, Uuid::new_v4(), &["read"]);
+        assert!(ExecutionPolicy::resolve(&worker, &task, Some(&contract), &[], None).is_err());
+    }
+
+    #[test]
+    fn test_required_action_must_be_permitted() {
+        let task = Task::new("t", "d").with_capabilities(vec!["execute".to_string()]);
+        assert!(ExecutionPolicy::resolve(&agent(&["read"]), &task, None, &[], None).is_err());
+        // Non-standard capabilities gate nothing.
+        let task = Task::new("t", "d").with_capabilities(vec!["code".to_string()]);
+        assert!(ExecutionPolicy::resolve(&agent(&["read"]), &task, None, &[], None).is_ok());
+    }
+
+    #[test]
+    fn test_execute_requires_network() {
+        let task = Task::new("t", "d");
+        let err = ExecutionPolicy::resolve(
+            &agent(&["read", "write", "execute"]),
+            &task,
+            None,
+            &[],
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not network"), "{err}");
+        let open = agent(&["read", "write", "execute", "network"]);
+        assert!(ExecutionPolicy::resolve(&open, &task, None, &[], None).is_ok());
+    }
+
+    #[test]
+    fn test_execute_requires_write() {
+        let task = Task::new("t", "d");
+        let err = ExecutionPolicy::resolve(
+            &agent(&["read", "execute", "network"]),
+            &task,
+            None,
+            &[],
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not write,"), "{err}");
+
+        // A contract that withholds write from an agent allowed to execute is refused too.
+        let worker = agent(&["read", "write", "execute", "network"]);
+        let contract = contract(&task, worker.id, &["read", "execute", "network"]);
+        let err = ExecutionPolicy::resolve(&worker, &task, Some(&contract), &[], None).unwrap_err();
+        assert!(err.to_string().contains("write"), "{err}");
+    }
+
+    #[test]
+    fn test_restricted_policy_needs_an_enforcing_backend() {
+        use crate::executor::{CommandExecutor, ScriptedExecutor};
+
+        let task = Task::new("t", "d");
+        let command = CommandExecutor::new("agent");
+        let restricted =
+            ExecutionPolicy::resolve(&agent(&["read"]), &task, None, &[], None).unwrap();
+        assert!(restricted.is_restricted());
+        let err = restricted.clone().enforceable_by(&command).unwrap_err();
+        assert!(err.to_string().contains("write, execute, network"), "{err}");
+        let scripted = ScriptedExecutor::new(Default::default());
+        assert!(restricted.enforceable_by(&scripted).is_ok());
+
+        let open = agent(&["read", "write", "execute", "network"]);
+        let open = ExecutionPolicy::resolve(&open, &task, None, &[], None).unwrap();
+        assert!(!open.is_restricted());
+        assert!(open.enforceable_by(&command).is_ok());
+    }
+
+    #[test]
+    fn test_configured_tools_conflicting_with_actions_are_refused() {
+        let task = Task::new("t", "d");
+        let err = ExecutionPolicy::resolve(
+            &agent(&["read"]),
+            &task,
+            None,
+            &["Read".to_string(), "Bash".to_string()],
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Bash"));
+
+        let policy =
+            ExecutionPolicy::resolve(&agent(&["read"]), &task, None, &["Read".to_string()], None)
+                .unwrap();
+        assert_eq!(policy.allowed_tools, vec!["Read".to_string()]);
+    }
+}
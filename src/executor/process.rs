@@ -0,0 +1,40 @@
+use tokio::process::{Child, Command};
+
+/// Spawn settings shared by subprocess executors: the child gets its own process group so
+/// that everything it starts can be killed together, and is killed if its handle is dropped.
+pub(crate) fn isolate(cmd: &mut Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    cmd.kill_on_drop(true);
+}
+
+/// Kills a child's whole process tree when dropped, unless disarmed.
+///
+/// Executions are cancelled by dropping their future, so this guard is what reaps
+/// grandchildren (tool subprocesses, shells) that `kill_on_drop` alone would leave behind.
+pub(crate) struct ProcessTreeGuard {
+    pgid: Option<u32>,
+}
+
+impl ProcessTreeGuard {
+    pub(crate) fn new(child: &Child) -> Self {
+        Self { pgid: child.id() }
+    }
+
+    /// The child exited normally; leave its process group alone.
+    pub(crate) fn disarm(&mut self) {
+        self.pgid = None;
+    }
+}
+
+impl Drop for ProcessTreeGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid {
+            // SAFETY: killpg only sends a signal; a stale group id fails with ESRCH.
+            unsafe {
+                libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+}
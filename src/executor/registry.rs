@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use super::{AgentExecutor, ExecutionContext, RetryPolicy, from_config};
+use crate::config::PanopticonConfig;
+use crate::types::Agent;
+use crate::types::error::PanopticonError;
+
+/// An executor backend together with the per-agent settings it runs with.
+#[derive(Clone)]
+pub struct ExecutorBinding {
+    pub executor: Arc<dyn AgentExecutor>,
+    /// Model the backend was built for.
+    pub model: String,
+    /// Working directory for executions on this backend.
+    pub working_dir: Option<String>,
+    /// How failed executions on this backend are retried.
+    pub retry: RetryPolicy,
+}
+
+impl ExecutorBinding {
+    pub fn new(executor: Arc<dyn AgentExecutor>, model: impl Into<String>) -> Self {
+        Self {
+            executor,
+            model: model.into(),
+            working_dir: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_working_dir(mut self, dir: impl Into<String>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// A fresh execution context carrying this binding's settings.
+    pub fn context(&self) -> ExecutionContext {
+        ExecutionContext {
+            working_dir: self.working_dir.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Maps agents to the executor backends that run their tasks.
+///
+/// Agents are looked up by id first, then by name, so bindings built from config
+/// (which only knows names) apply to agents registered under that name.
+#[derive(Clone, Default)]
+pub struct ExecutorRegistry {
+    by_id: HashMap<Uuid, ExecutorBinding>,
+    by_name: HashMap<String, ExecutorBinding>,
+}
+
+impl ExecutorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build one binding per `[[agents]]` entry in the config.
+    pub fn from_config(
+        config: &PanopticonConfig,
+        default_model: &str,
+        state_dir: Option<&Path>,
+    ) -> Result<Self, PanopticonError> {
+        let mut registry = Self::new();
+        for agent in &config.agents {
+            let backend = agent.backend_config(config);
+            let model = agent.model.as_deref().unwrap_or(default_model);
+            let executor = from_config(&backend, model, state_dir)
+                .map_err(|e| PanopticonError::Internal(format!("Agent '{}': {e}", agent.name)))?;
+            let mut binding = ExecutorBinding::new(executor, model).with_retry(backend.retry);
+            if let Some(dir) = &agent.working_dir {
+                binding = binding.with_working_dir(dir);
+            }
+            registry.bind_name(&agent.name, binding);
+        }
+        Ok(registry)
+    }
+
+    pub fn bind_agent(&mut self, agent_id: Uuid, binding: ExecutorBinding) {
+        self.by_id.insert(agent_id, binding);
+    }
+
+    pub fn bind_name(&mut self, name: impl Into<String>, binding: ExecutorBinding) {
+        self.by_name.insert(name.into(), binding);
+    }
+
+    /// The binding for an agent, by id first and then by name.
+    pub fn resolve(&self, agent: &Agent) -> Option<&ExecutorBinding> {
+        self.by_id
+            .get(&agent.id)
+            .or_else(|| self.by_name.get(&agent.name))
+    }
+
+    /// Remove an agent's bindings (e.g. when its backend is unavailable).
+    pub fn unbind(&mut self, agent: &Agent) {
+        self.by_id.remove(&agent.id);
+        self.by_name.remove(&agent.name);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty() && self.by_name.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentConfig;
+    use crate::executor::ScriptedExecutor;
+
+    #[test]
+    fn test_resolve_by_id_then_name() {
+        let mut registry = ExecutorRegistry::new();
+        let agent = Agent::new("worker");
+        registry.bind_name(
+            "worker",
+            ExecutorBinding::new(Arc::new(ScriptedExecutor::default()), "by-name"),
+        );
+        assert_eq!(registry.resolve(&agent).unwrap().model, "by-name");
+
+        registry.bind_agent(
+            agent.id,
+            ExecutorBinding::new(Arc::new(ScriptedExecutor::default()), "by-id"),
+        );
+        assert_eq!(registry.resolve(&agent).unwrap().model, "by-id");
+
+        assert!(registry.resolve(&Agent::new("stranger")).is_none());
+    }
+
+    #[test]
+    fn test_from_config_binds_each_agent() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("fixtures.json"), r#"{"rules": []}"#).unwrap();
+
+        let mut config = PanopticonConfig::default();
+        let mut offline = AgentConfig::new("offline");
+        offline.executor = Some("scripted".to_string());
+        offline.scripted_fixtures = Some("fixtures.json".to_string());
+        offline.working_dir = Some("/tmp".to_string());
+        offline.retry = Some(RetryPolicy::none());
+        let mut reviewer = AgentConfig::new("reviewer");
+        reviewer.model = Some("opus".to_string());
+        config.agents = vec![offline, reviewer];
+
+        let registry = ExecutorRegistry::from_config(&config, "sonnet", Some(dir.path())).unwrap();
+
+        let offline = registry.resolve(&Agent::new("offline")).unwrap();
+        assert_eq!(offline.executor.name(), "ScriptedExecutor");
+        assert_eq!(offline.context().working_dir.as_deref(), Some("/tmp"));
+        assert_eq!(offline.model, "sonnet");
+        assert_eq!(offline.retry.max_attempts, 1);
+
+        let reviewer = registry.resolve(&Agent::new("reviewer")).unwrap();
+        assert_eq!(reviewer.executor.name(), "ClaudeExecutor");
+        assert_eq!(reviewer.model, "opus");
+        assert_eq!(reviewer.retry, RetryPolicy::default());
+    }
+
+    #[test]
+    fn test_from_config_reports_agent_name_on_error() {
+        let mut config = PanopticonConfig::default();
+        let mut broken = AgentConfig::new("broken");
+        broken.executor = Some("command".to_string());
+        config.agents = vec![broken];
+        let err = ExecutorRegistry::from_config(&config, "sonnet", None)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("broken"));
+    }
+}
@@ -0,0 +1,370 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{AgentExecutor, ExecutionContext};
+use crate::ledger::{Ledger, LedgerEntryKind};
+use crate::monitoring::Checkpoint;
+use crate::types::Task;
+use crate::types::error::{ExecutorErrorKind, PanopticonError};
+use crate::verification::TaskResult;
+
+/// How failed executions are retried: which error kinds, how often, and how long to wait.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry, in milliseconds.
+    pub initial_backoff_ms: u64,
+    /// Upper bound on the delay between attempts, in milliseconds.
+    pub max_backoff_ms: u64,
+    /// Factor the delay grows by after each retry.
+    pub multiplier: f64,
+    /// Random spread applied to each delay, as a fraction of it (0.2 = ±20%).
+    pub jitter: f64,
+    /// Error kinds worth retrying.
+    pub retryable: Vec<ExecutorErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            retryable: vec![
+                ExecutorErrorKind::Transport,
+                ExecutorErrorKind::RateLimited,
+                ExecutorErrorKind::BackendUnavailable,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retrying after the given (1-based) failed attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(63) as i32;
+        let base = (self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(exponent))
+            .min(self.max_backoff_ms as f64);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+        Duration::from_millis((base * factor).round() as u64)
+    }
+
+    /// Whether `err`, raised by the given (1-based) attempt, should be retried.
+    pub fn should_retry(&self, err: &PanopticonError, attempt: u32) -> bool {
+        attempt < self.max_attempts
+            && err
+                .executor_kind()
+                .is_some_and(|kind| self.retryable.contains(&kind))
+    }
+}
+
+/// Wraps any executor and retries its failures according to a `RetryPolicy`.
+///
+/// Every attempt is recorded in the ledger (when one is attached) as an
+/// `ExecutionAttempt` entry whose subject is the task.
+pub struct RetryingExecutor {
+    inner: Arc<dyn AgentExecutor>,
+    policy: RetryPolicy,
+    ledger: Option<Arc<dyn Ledger>>,
+}
+
+impl RetryingExecutor {
+    pub fn new(inner: Arc<dyn AgentExecutor>, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            ledger: None,
+        }
+    }
+
+    pub fn with_ledger(mut self, ledger: Arc<dyn Ledger>) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    async fn record_attempt(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+        payload: serde_json::Value,
+    ) {
+        let Some(ledger) = &self.ledger else {
+            return;
+        };
+        let actor = context.agent_id.unwrap_or_default();
+        // The audit trail is best-effort: a ledger failure must not fail the execution.
+        let _ = ledger
+            .append_chained(LedgerEntryKind::ExecutionAttempt, actor, task.id, payload)
+            .await;
+    }
+}
+
+#[async_trait]
+impl AgentExecutor for RetryingExecutor {
+    async fn execute(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+    ) -> Result<TaskResult, PanopticonError> {
+        let max_attempts = self.policy.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let result = self.inner.execute(task, context).await;
+            let err = match result {
+                Ok(result) => {
+                    self.record_attempt(
+                        task,
+                        context,
+                        serde_json::json!({
+                            "executor": self.inner.name(),
+                            "attempt": attempt,
+                            "max_attempts": max_attempts,
+                            "outcome": "succeeded",
+                        }),
+                    )
+                    .await;
+                    return Ok(result);
+                }
+                Err(err) => err,
+            };
+
+            let retrying =
+                self.policy.should_retry(&err, attempt) && !context.cancel.is_cancelled();
+            let backoff = retrying.then(|| self.policy.backoff(attempt));
+            self.record_attempt(
+                task,
+                context,
+                serde_json::json!({
+                    "executor": self.inner.name(),
+                    "attempt": attempt,
+                    "max_attempts": max_attempts,
+                    "outcome": "failed",
+                    "error_kind": err.executor_kind(),
+                    "error": err.to_string(),
+                    "retrying": retrying,
+                    "backoff_ms": backoff.map(|d| d.as_millis() as u64),
+                }),
+            )
+            .await;
+
+            let Some(backoff) = backoff else {
+                return Err(err);
+            };
+            context
+                .report(
+                    Checkpoint::new(task.id, context.agent_id.unwrap_or(Uuid::nil())).with_status(
+                        format!(
+                            "Attempt {attempt}/{max_attempts} failed ({err}); retrying in {:.1}s",
+                            backoff.as_secs_f64()
+                        ),
+                    ),
+                )
+                .await;
+
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = context.cancel.cancelled() => return Err(err),
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn health_check(&self) -> Result<bool, PanopticonError> {
+        self.inner.health_check().await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn enforces_policy(&self) -> bool {
+        self.inner.enforces_policy()
+    }
+
+    fn reports_checkpoints(&self) -> bool {
+        self.inner.reports_checkpoints()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{ScriptedExecutor, ScriptedRule};
+    use crate::ledger::InMemoryLedger;
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 5,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn flaky(kind: ExecutorErrorKind, failures: u32) -> Arc<dyn AgentExecutor> {
+        Arc::new(
+            ScriptedExecutor::default().with_rule(
+                ScriptedRule::new("*")
+                    .with_output(serde_json::json!({"result": "ok"}))
+                    .with_failure("flaky backend")
+                    .with_failure_kind(kind)
+                    .with_fail_attempts(failures),
+            ),
+        )
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 350,
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_in_range() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 1000,
+            jitter: 0.2,
+            ..RetryPolicy::default()
+        };
+        for _ in 0..50 {
+            let ms = policy.backoff(1).as_millis();
+            assert!((800..=1200).contains(&ms), "{ms}");
+        }
+    }
+
+    #[test]
+    fn test_should_retry_respects_kind_and_attempts() {
+        let policy = fast_policy(3);
+        let transient = PanopticonError::executor(ExecutorErrorKind::RateLimited, "429");
+        let fatal = PanopticonError::executor(ExecutorErrorKind::Rejected, "401");
+        assert!(policy.should_retry(&transient, 1));
+        assert!(policy.should_retry(&transient, 2));
+        assert!(!policy.should_retry(&transient, 3));
+        assert!(!policy.should_retry(&fatal, 1));
+        assert!(!policy.should_retry(&PanopticonError::Internal("x".into()), 1));
+    }
+
+    #[test]
+    fn test_policy_deserialize_partial() {
+        let policy: RetryPolicy =
+            toml::from_str("max_attempts = 5\nretryable = [\"NonZeroExit\"]").unwrap();
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.initial_backoff_ms, 1000);
+        assert_eq!(policy.retryable, vec![ExecutorErrorKind::NonZeroExit]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_and_records_attempts() {
+        let ledger = Arc::new(InMemoryLedger::new());
+        let executor = RetryingExecutor::new(
+            flaky(ExecutorErrorKind::BackendUnavailable, 2),
+            fast_policy(3),
+        )
+        .with_ledger(ledger.clone());
+        let task = Task::new("flaky", "desc");
+
+        let result = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.output["result"], "ok");
+
+        let attempts = ledger.query_by_subject(task.id).await.unwrap();
+        assert_eq!(attempts.len(), 3);
+        assert!(
+            attempts
+                .iter()
+                .all(|e| e.kind == LedgerEntryKind::ExecutionAttempt)
+        );
+        assert_eq!(attempts[0].payload["outcome"], "failed");
+        assert_eq!(attempts[0].payload["error_kind"], "BackendUnavailable");
+        assert_eq!(attempts[0].payload["retrying"], true);
+        assert_eq!(attempts[2].payload["outcome"], "succeeded");
+        assert_eq!(attempts[2].payload["attempt"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let ledger = Arc::new(InMemoryLedger::new());
+        let executor =
+            RetryingExecutor::new(flaky(ExecutorErrorKind::Transport, 10), fast_policy(2))
+                .with_ledger(ledger.clone());
+        let task = Task::new("down", "desc");
+
+        let err = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.executor_kind(), Some(ExecutorErrorKind::Transport));
+        let attempts = ledger.query_by_subject(task.id).await.unwrap();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[1].payload["retrying"], false);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_permanent_failures() {
+        let ledger = Arc::new(InMemoryLedger::new());
+        let executor = RetryingExecutor::new(flaky(ExecutorErrorKind::Rejected, 1), fast_policy(3))
+            .with_ledger(ledger.clone());
+        let task = Task::new("denied", "desc");
+
+        let err = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.executor_kind(), Some(ExecutorErrorKind::Rejected));
+        assert_eq!(ledger.query_by_subject(task.id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_stops_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 60_000,
+            max_backoff_ms: 60_000,
+            ..fast_policy(3)
+        };
+        let executor = RetryingExecutor::new(flaky(ExecutorErrorKind::RateLimited, 10), policy);
+        let cancel = crate::executor::CancellationToken::new();
+        let ctx = ExecutionContext::default().with_cancellation(cancel.clone());
+        let task = Task::new("throttled", "desc");
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancel.cancel();
+        });
+        let start = std::time::Instant::now();
+        let err = executor.execute(&task, &ctx).await.unwrap_err();
+        assert_eq!(err.executor_kind(), Some(ExecutorErrorKind::RateLimited));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::{AgentExecutor, ExecutionContext};
+use crate::monitoring::Checkpoint;
+use crate::types::Task;
+use crate::types::error::PanopticonError;
+use crate::verification::{CompiledSchema, SchemaViolation, TaskResult};
+
+/// Feedback for an agent whose previous output did not match the task's output schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaRepair {
+    /// Repair round this prompt starts (1 for the first re-prompt).
+    pub round: u32,
+    pub previous_output: Value,
+    pub violations: Vec<SchemaViolation>,
+}
+
+/// Render repair feedback as a prompt section. Empty when there is none.
+pub fn render_repair(repair: Option<&SchemaRepair>) -> String {
+    let Some(repair) = repair else {
+        return String::new();
+    };
+    let previous =
+        serde_json::to_string_pretty(&repair.previous_output).unwrap_or_else(|_| "null".into());
+    let mut section = String::from("## Your previous output was rejected\n\n");
+    section.push_str(&format!("```json\n{previous}\n```\n\n"));
+    section.push_str("It does not match the required output schema:\n");
+    for violation in &repair.violations {
+        section.push_str(&format!("- {violation}\n"));
+    }
+    section.push_str("\nRespond again with a corrected JSON object.\n\n");
+    section
+}
+
+/// Wraps any executor and enforces the task's `output_schema`: an output that does not
+/// match is sent back to the agent, together with the violations, up to `max_rounds` times.
+///
+/// The last output is returned even if it still does not match, so verification can
+/// report the violations. Tasks without a schema pass straight through.
+pub struct ValidatingExecutor {
+    inner: Arc<dyn AgentExecutor>,
+    max_rounds: u32,
+}
+
+impl ValidatingExecutor {
+    pub fn new(inner: Arc<dyn AgentExecutor>, max_rounds: u32) -> Self {
+        Self { inner, max_rounds }
+    }
+}
+
+#[async_trait]
+impl AgentExecutor for ValidatingExecutor {
+    async fn execute(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+    ) -> Result<TaskResult, PanopticonError> {
+        // A schema that cannot be compiled fails the task before the agent runs: no output
+        // could satisfy it, and every repair round would be wasted on it.
+        let schema = task
+            .output_schema
+            .as_ref()
+            .map(CompiledSchema::compile)
+            .transpose()?;
+        let mut result = self.inner.execute(task, context).await?;
+        let Some(schema) = schema else {
+            return Ok(result);
+        };
+
+        let mut round = 1;
+        loop {
+            let violations = schema.validate(&result.output);
+            if violations.is_empty() || round > self.max_rounds || context.cancel.is_cancelled() {
+                return Ok(result);
+            }
+            context
+                .report(
+                    Checkpoint::new(task.id, context.agent_id.unwrap_or(Uuid::nil())).with_status(
+                        format!(
+                            "Output violates the schema ({} problem(s)); repair round {round}/{}",
+                            violations.len(),
+                            self.max_rounds
+                        ),
+                    ),
+                )
+                .await;
+
+            let repair = SchemaRepair {
+                round,
+                previous_output: result.output.clone(),
+                violations,
+            };
+            let mut repair_context = context.clone();
+            // A backend that keeps sessions continues the same one, with the violations as the next turn.
+            if let Some(session_id) = &result.session_id {
+                repair_context.session_id = Some(session_id.clone());
+                repair_context.feedback = Some(render_repair(Some(&repair)));
+            }
+            repair_context.repair = Some(repair);
+            let mut repaired = self.inner.execute(task, &repair_context).await?;
+
+            // The task's cost covers every round, not just the last one.
+            repaired.resource_consumed += result.resource_consumed;
+            repaired.usage += result.usage;
+            result = repaired;
+            round += 1;
+        }
+    }
+
+    async fn health_check(&self) -> Result<bool, PanopticonError> {
+        self.inner.health_check().await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn enforces_policy(&self) -> bool {
+        self.inner.enforces_policy()
+    }
+
+    fn reports_checkpoints(&self) -> bool {
+        self.inner.reports_checkpoints()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Answers with a bare string until it is shown repair feedback `fix_after` times.
+    struct Stubborn {
+        fix_after: u32,
+        session_id: Option<&'static str>,
+        calls: AtomicU32,
+        resumed: AtomicU32,
+    }
+
+    #[async_trait]
+    impl AgentExecutor for Stubborn {
+        async fn execute(
+            &self,
+            task: &Task,
+            context: &ExecutionContext,
+        ) -> Result<TaskResult, PanopticonError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if context.session_id.is_some() && context.feedback.is_some() {
+                self.resumed.fetch_add(1, Ordering::SeqCst);
+            }
+            let round = context.repair.as_ref().map_or(0, |r| r.round);
+            let output = if round >= self.fix_after {
+                serde_json::json!({"result": "fixed"})
+            } else {
+                serde_json::json!("oops")
+            };
+            Ok(TaskResult {
+                task_id: task.id,
+                agent_id: Uuid::nil(),
+                output,
+                completed_at: Utc::now(),
+                resource_consumed: 1.0,
+                usage: Default::default(),
+                artifacts: Vec::new(),
+                session_id: self.session_id.map(str::to_string),
+            })
+        }
+
+        async fn health_check(&self) -> Result<bool, PanopticonError> {
+            Ok(true)
+        }
+
+        fn name(&self) -> &str {
+            "stubborn"
+        }
+    }
+
+    fn schema_task() -> Task {
+        Task::new("t", "d").with_output_schema(serde_json::json!({
+            "type": "object",
+            "required": ["result"]
+        }))
+    }
+
+    fn stubborn(fix_after: u32) -> Arc<Stubborn> {
+        Arc::new(Stubborn {
+            fix_after,
+            session_id: None,
+            calls: AtomicU32::new(0),
+            resumed: AtomicU32::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_repairs_invalid_output() {
+        let inner = stubborn(1);
+        let executor = ValidatingExecutor::new(inner.clone(), 2);
+        let result = executor
+            .execute(&schema_task(), &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.output["result"], "fixed");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(result.resource_consumed, 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_rounds() {
+        let inner = stubborn(5);
+        let executor = ValidatingExecutor::new(inner.clone(), 2);
+        let result = executor
+            .execute(&schema_task(), &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.output, serde_json::json!("oops"));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_repair_continues_session() {
+        let inner = Arc::new(Stubborn {
+            fix_after: 1,
+            session_id: Some("s1"),
+            calls: AtomicU32::new(0),
+            resumed: AtomicU32::new(0),
+        });
+        let executor = ValidatingExecutor::new(inner.clone(), 2);
+        executor
+            .execute(&schema_task(), &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(inner.resumed.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_schema_fails_before_running() {
+        let inner = stubborn(0);
+        let executor = ValidatingExecutor::new(inner.clone(), 2);
+        let task = Task::new("t", "d").with_output_schema(serde_json::json!({
+            "type": "string",
+            "pattern": "(unclosed"
+        }));
+        let err = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PanopticonError::InvalidSchema(_)), "{err}");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_without_schema_runs_once() {
+        let inner = stubborn(5);
+        let executor = ValidatingExecutor::new(inner.clone(), 2);
+        executor
+            .execute(&Task::new("t", "d"), &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_render_repair_lists_violations() {
+        let repair = SchemaRepair {
+            round: 1,
+            previous_output: serde_json::json!("oops"),
+            violations: CompiledSchema::compile(&serde_json::json!({"type": "object"}))
+                .unwrap()
+                .validate(&serde_json::json!("oops")),
+        };
+        let section = render_repair(Some(&repair));
+        assert!(section.contains("\"oops\""));
+        assert!(section.contains("- /: expected object, got string"));
+        assert!(render_repair(None).is_empty());
+    }
+}
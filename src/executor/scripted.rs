@@ -0,0 +1,438 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::claude::strip_code_fences;
+use super::{AgentExecutor, ExecutionContext};
+use crate::monitoring::Checkpoint;
+use crate::types::error::{ExecutorErrorKind, PanopticonError};
+use crate::types::{ResourceUsage, Task};
+use crate::util::glob_match;
+use crate::verification::TaskResult;
+
+/// A canned response for tasks whose name matches `pattern`.
+///
+/// Exactly one of `output`, `raw`, or `fail` is normally set; `fail` combined with
+/// `fail_attempts` fails the first attempts and then answers with `output` or `raw`.
+/// If none is set the rule answers with an empty `{"result": "", "summary": ""}` object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptedRule {
+    /// Exact task name or glob pattern (`*` matches any run, `?` a single character).
+    pub pattern: String,
+    /// JSON output returned as-is.
+    #[serde(default)]
+    pub output: Option<serde_json::Value>,
+    /// Raw agent text, parsed like real agent output. Malformed JSON fails the task.
+    #[serde(default)]
+    pub raw: Option<String>,
+    /// Fail the execution with this error message.
+    #[serde(default)]
+    pub fail: Option<String>,
+    /// Error kind reported by `fail` (defaults to `NonZeroExit`).
+    #[serde(default)]
+    pub fail_kind: Option<ExecutorErrorKind>,
+    /// Only fail the first N executions of each task, then answer normally.
+    #[serde(default)]
+    pub fail_attempts: Option<u32>,
+    /// Artificial delay before responding, in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Resource units reported as consumed.
+    #[serde(default)]
+    pub resource_consumed: f64,
+    /// Usage reported for the execution (tokens, cost, ...).
+    #[serde(default)]
+    pub usage: ResourceUsage,
+    /// Files written into the working directory: relative path -> contents.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+/// A set of scripted responses, typically loaded from a JSON fixtures file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptedFixtures {
+    pub rules: Vec<ScriptedRule>,
+}
+
+impl ScriptedFixtures {
+    /// Load fixtures from a JSON file.
+    pub fn load(path: &Path) -> Result<Self, PanopticonError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            PanopticonError::Internal(format!(
+                "Failed to read fixtures file {}: {e}",
+                path.display()
+            ))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            PanopticonError::Serialization(format!(
+                "Failed to parse fixtures file {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Find the rule for a task name. Exact matches win over globs; globs are tried in order.
+    pub fn find(&self, task_name: &str) -> Option<&ScriptedRule> {
+        self.rules
+            .iter()
+            .find(|r| r.pattern == task_name)
+            .or_else(|| {
+                self.rules
+                    .iter()
+                    .find(|r| glob_match(&r.pattern, task_name))
+            })
+    }
+}
+
+/// Executor that answers from canned fixtures instead of calling an agent.
+/// Useful for offline, deterministic runs of the whole pipeline (CI, demos, tests).
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedExecutor {
+    pub fixtures: ScriptedFixtures,
+    /// Executions seen per task id, for `fail_attempts`.
+    attempts: Arc<DashMap<Uuid, u32>>,
+}
+
+impl ScriptedExecutor {
+    pub fn new(fixtures: ScriptedFixtures) -> Self {
+        Self {
+            fixtures,
+            attempts: Arc::default(),
+        }
+    }
+
+    /// Create an executor from a JSON fixtures file.
+    pub fn from_file(path: &Path) -> Result<Self, PanopticonError> {
+        Ok(Self::new(ScriptedFixtures::load(path)?))
+    }
+
+    pub fn with_rule(mut self, rule: ScriptedRule) -> Self {
+        self.fixtures.rules.push(rule);
+        self
+    }
+}
+
+impl ScriptedRule {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            output: None,
+            raw: None,
+            fail: None,
+            fail_kind: None,
+            fail_attempts: None,
+            delay_ms: 0,
+            resource_consumed: 0.0,
+            usage: ResourceUsage::default(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_output(mut self, output: serde_json::Value) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    pub fn with_raw(mut self, raw: impl Into<String>) -> Self {
+        self.raw = Some(raw.into());
+        self
+    }
+
+    pub fn with_failure(mut self, message: impl Into<String>) -> Self {
+        self.fail = Some(message.into());
+        self
+    }
+
+    /// Fail with a specific error kind instead of `NonZeroExit`.
+    pub fn with_failure_kind(mut self, kind: ExecutorErrorKind) -> Self {
+        self.fail_kind = Some(kind);
+        self
+    }
+
+    /// Only fail the first `attempts` executions of each task.
+    pub fn with_fail_attempts(mut self, attempts: u32) -> Self {
+        self.fail_attempts = Some(attempts);
+        self
+    }
+
+    pub fn with_delay_ms(mut self, delay_ms: u64) -> Self {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: ResourceUsage) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn with_file(mut self, path: impl Into<String>, contents: impl Into<String>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+}
+
+#[async_trait]
+impl AgentExecutor for ScriptedExecutor {
+    async fn execute(
+        &self,
+        task: &Task,
+        context: &ExecutionContext,
+    ) -> Result<TaskResult, PanopticonError> {
+        let rule = self.fixtures.find(&task.name).ok_or_else(|| {
+            PanopticonError::executor(
+                ExecutorErrorKind::Rejected,
+                format!("No scripted response for task '{}'", task.name),
+            )
+        })?;
+        let attempt = {
+            let mut seen = self.attempts.entry(task.id).or_insert(0);
+            *seen += 1;
+            *seen
+        };
+
+        let agent_id = context.agent_id.unwrap_or_default();
+        context
+            .report(Checkpoint::new(task.id, agent_id).with_status("Started"))
+            .await;
+
+        if rule.delay_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(rule.delay_ms)).await;
+        }
+
+        if let Some(message) = &rule.fail
+            && rule.fail_attempts.is_none_or(|n| attempt <= n)
+        {
+            return Err(PanopticonError::executor(
+                rule.fail_kind.unwrap_or(ExecutorErrorKind::NonZeroExit),
+                message.clone(),
+            ));
+        }
+
+        let dir = Path::new(context.working_dir.as_deref().unwrap_or("."));
+        for (path, contents) in &rule.files {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| {
+                    PanopticonError::Internal(format!("Failed to create {}: {e}", parent.display()))
+                })?;
+            }
+            std::fs::write(&path, contents).map_err(|e| {
+                PanopticonError::Internal(format!("Failed to write {}: {e}", path.display()))
+            })?;
+        }
+
+        let output = match (&rule.output, &rule.raw) {
+            (Some(output), _) => output.clone(),
+            (None, Some(raw)) => serde_json::from_str(&strip_code_fences(raw)).map_err(|e| {
+                PanopticonError::executor(
+                    ExecutorErrorKind::InvalidResponse,
+                    format!("Failed to parse scripted output: {e}\nRaw output: {raw}"),
+                )
+            })?,
+            (None, None) => serde_json::json!({ "result": "", "summary": "" }),
+        };
+
+        context
+            .report(
+                Checkpoint::new(task.id, agent_id)
+                    .with_progress(1.0)
+                    .with_resource_consumed(rule.resource_consumed)
+                    .with_status("Finished"),
+            )
+            .await;
+
+        Ok(TaskResult {
+            task_id: task.id,
+            agent_id: Uuid::nil(), // Will be set by the caller
+            output,
+            completed_at: Utc::now(),
+            resource_consumed: rule.resource_consumed,
+            usage: rule.usage,
+            artifacts: Vec::new(),
+            session_id: None,
+        })
+    }
+
+    async fn health_check(&self) -> Result<bool, PanopticonError> {
+        Ok(true)
+    }
+
+    fn name(&self) -> &str {
+        "ScriptedExecutor"
+    }
+
+    /// Scripted responses take no actions.
+    fn enforces_policy(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_match_wins_over_glob() {
+        let fixtures = ScriptedFixtures {
+            rules: vec![
+                ScriptedRule::new("*").with_output(serde_json::json!({"result": "glob"})),
+                ScriptedRule::new("special").with_output(serde_json::json!({"result": "exact"})),
+            ],
+        };
+        assert_eq!(
+            fixtures.find("special").unwrap().output.as_ref().unwrap()["result"],
+            "exact"
+        );
+        assert_eq!(
+            fixtures.find("other").unwrap().output.as_ref().unwrap()["result"],
+            "glob"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_returns_canned_output() {
+        let executor = ScriptedExecutor::default()
+            .with_rule(ScriptedRule::new("*").with_output(serde_json::json!({"result": "ok"})));
+        let task = Task::new("anything", "desc");
+        let result = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.task_id, task.id);
+        assert_eq!(result.output["result"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_execute_injected_failure() {
+        let executor =
+            ScriptedExecutor::default().with_rule(ScriptedRule::new("*").with_failure("boom"));
+        let task = Task::new("anything", "desc");
+        let err = executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("boom"));
+        assert_eq!(err.executor_kind(), Some(ExecutorErrorKind::NonZeroExit));
+    }
+
+    #[tokio::test]
+    async fn test_execute_fails_first_attempts_only() {
+        let executor = ScriptedExecutor::default().with_rule(
+            ScriptedRule::new("*")
+                .with_output(serde_json::json!({"result": "ok"}))
+                .with_failure("overloaded")
+                .with_failure_kind(ExecutorErrorKind::BackendUnavailable)
+                .with_fail_attempts(2),
+        );
+        let task = Task::new("flaky", "desc");
+        let ctx = ExecutionContext::default();
+        for _ in 0..2 {
+            let err = executor.execute(&task, &ctx).await.unwrap_err();
+            assert!(err.is_retryable());
+        }
+        let result = executor.execute(&task, &ctx).await.unwrap();
+        assert_eq!(result.output["result"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_execute_malformed_raw_output() {
+        let executor = ScriptedExecutor::default()
+            .with_rule(ScriptedRule::new("broken").with_raw("{\"result\": "))
+            .with_rule(ScriptedRule::new("fenced").with_raw("```json\n{\"result\": 1}\n```"));
+
+        let broken = Task::new("broken", "desc");
+        let result = executor
+            .execute(&broken, &ExecutionContext::default())
+            .await;
+        assert_eq!(
+            result.unwrap_err().executor_kind(),
+            Some(ExecutorErrorKind::InvalidResponse)
+        );
+
+        let fenced = Task::new("fenced", "desc");
+        let result = executor
+            .execute(&fenced, &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert_eq!(result.output["result"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_execute_delay() {
+        let executor =
+            ScriptedExecutor::default().with_rule(ScriptedRule::new("*").with_delay_ms(50));
+        let task = Task::new("slow", "desc");
+        let start = std::time::Instant::now();
+        executor
+            .execute(&task, &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_execute_reports_checkpoints() {
+        let executor = ScriptedExecutor::default().with_rule(ScriptedRule::new("*"));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let agent_id = Uuid::new_v4();
+        let ctx = ExecutionContext::default().with_checkpoints(agent_id, tx);
+        let task = Task::new("observed", "desc");
+        executor.execute(&task, &ctx).await.unwrap();
+        drop(ctx);
+
+        let first = rx.recv().await.unwrap();
+        assert_eq!(first.agent_id, agent_id);
+        assert_eq!(first.progress_pct, 0.0);
+        let last = rx.recv().await.unwrap();
+        assert_eq!(last.progress_pct, 1.0);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_execute_writes_files_into_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor = ScriptedExecutor::default()
+            .with_rule(ScriptedRule::new("*").with_file("out/report.md", "# Report"));
+        let ctx = ExecutionContext {
+            working_dir: Some(dir.path().to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        executor
+            .execute(&Task::new("write", "desc"), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("out/report.md")).unwrap(),
+            "# Report"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_without_matching_rule_fails() {
+        let executor = ScriptedExecutor::default();
+        let task = Task::new("unscripted", "desc");
+        let result = executor.execute(&task, &ExecutionContext::default()).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_load_fixtures_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures.json");
+        std::fs::write(
+            &path,
+            r#"{"rules": [{"pattern": "Plan*", "output": {"subtasks": []}, "delay_ms": 10}]}"#,
+        )
+        .unwrap();
+        let executor = ScriptedExecutor::from_file(&path).unwrap();
+        let rule = executor.fixtures.find("Plan decomposition").unwrap();
+        assert_eq!(rule.delay_ms, 10);
+        assert!(rule.fail.is_none());
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::monitoring::Checkpoint;
+use crate::types::ResourceUsage;
+
+/// Incremental parser for `claude --output-format stream-json` output.
+///
+/// Each line of output is one JSON event. Events that say something about progress
+/// (session start, assistant text, tool calls, the final result) become `Checkpoint`s, which
+/// carry the session id so that an interrupted run can be resumed.
+#[derive(Debug, Clone)]
+pub struct StreamParser {
+    task_id: Uuid,
+    agent_id: Uuid,
+    max_turns: Option<u32>,
+    turns: u32,
+    /// Tokens per assistant message id; streamed messages repeat their usage per content block.
+    message_tokens: HashMap<String, u64>,
+    result: Option<String>,
+    session_id: Option<String>,
+    is_error: bool,
+    subtype: Option<String>,
+    usage: ResourceUsage,
+}
+
+impl StreamParser {
+    pub fn new(task_id: Uuid, agent_id: Uuid, max_turns: Option<u32>) -> Self {
+        Self {
+            task_id,
+            agent_id,
+            max_turns,
+            turns: 0,
+            message_tokens: HashMap::new(),
+            result: None,
+            session_id: None,
+            is_error: false,
+            subtype: None,
+            usage: ResourceUsage::default(),
+        }
+    }
+
+    /// Feed one line of output. Returns a checkpoint if the event reports progress.
+    pub fn feed(&mut self, line: &str) -> Option<Checkpoint> {
+        let event: Value = serde_json::from_str(line.trim()).ok()?;
+        if let Some(id) = event.get("session_id").and_then(|s| s.as_str()) {
+            self.session_id = Some(id.to_string());
+        }
+        match event.get("type").and_then(|t| t.as_str())? {
+            "system" if event.get("subtype").and_then(|s| s.as_str()) == Some("init") => {
+                let model = event
+                    .get("model")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("agent");
+                Some(self.checkpoint(format!("Session started ({model})"), &event))
+            }
+            "assistant" => self.handle_assistant(&event),
+            "result" => {
+                self.is_error = event
+                    .get("is_error")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false);
+                self.subtype = event
+                    .get("subtype")
+                    .and_then(|s| s.as_str())
+                    .map(|s| s.to_string());
+                if let Some(turns) = event.get("num_turns").and_then(|n| n.as_u64()) {
+                    self.turns = turns as u32;
+                }
+                self.result = event
+                    .get("result")
+                    .and_then(|r| r.as_str())
+                    .map(|s| s.to_string());
+                self.usage = ResourceUsage::from_claude_envelope(&event);
+                let status = if self.is_error { "Failed" } else { "Finished" };
+                Some(
+                    self.checkpoint(format!("{status} after {} turn(s)", self.turns), &event)
+                        .with_progress(1.0),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_assistant(&mut self, event: &Value) -> Option<Checkpoint> {
+        let message = event.get("message")?;
+        let id = message
+            .get("id")
+            .and_then(|i| i.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        // A new message id marks a new turn.
+        if !self.message_tokens.contains_key(&id) {
+            self.turns += 1;
+        }
+        if let Some(usage) = message.get("usage") {
+            let tokens = ["input_tokens", "output_tokens"]
+                .iter()
+                .filter_map(|k| usage.get(k).and_then(|v| v.as_u64()))
+                .sum();
+            self.message_tokens.insert(id, tokens);
+        } else {
+            self.message_tokens.entry(id).or_insert(0);
+        }
+
+        let blocks = message.get("content").and_then(|c| c.as_array())?;
+        let tools: Vec<&str> = blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+            .filter_map(|b| b.get("name").and_then(|n| n.as_str()))
+            .collect();
+        let status = if !tools.is_empty() {
+            format!("Turn {}: using {}", self.turns, tools.join(", "))
+        } else {
+            let text: String = blocks
+                .iter()
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let first_line = text.lines().next().unwrap_or_default();
+            let preview: String = first_line.chars().take(80).collect();
+            format!("Turn {}: {preview}", self.turns)
+        };
+
+        Some(
+            self.checkpoint(status, event)
+                .with_metadata(serde_json::json!({
+                    "event": "assistant",
+                    "turn": self.turns,
+                    "tools": tools,
+                    "session_id": self.session_id,
+                })),
+        )
+    }
+
+    fn checkpoint(&self, status: String, event: &Value) -> Checkpoint {
+        let progress = match self.max_turns {
+            // Turns only bound the run from above, so never claim completion before the result.
+            Some(max) if max > 0 => (self.turns as f64 / max as f64).min(0.95),
+            _ => 0.0,
+        };
+        Checkpoint::new(self.task_id, self.agent_id)
+            .with_progress(progress)
+            .with_resource_consumed(self.tokens() as f64)
+            .with_status(status)
+            .with_metadata(serde_json::json!({
+                "event": event.get("type"),
+                "session_id": self.session_id,
+            }))
+    }
+
+    /// Tokens consumed so far.
+    pub fn tokens(&self) -> u64 {
+        self.message_tokens.values().sum()
+    }
+
+    /// Number of agentic turns seen so far.
+    pub fn turns(&self) -> u32 {
+        self.turns
+    }
+
+    /// Final result text, once the `result` event has been seen.
+    pub fn result(&self) -> Option<&str> {
+        self.result.as_deref()
+    }
+
+    /// Usage reported by the final `result` event.
+    pub fn usage(&self) -> ResourceUsage {
+        self.usage
+    }
+
+    /// Whether the final `result` event reported an error.
+    pub fn is_error(&self) -> bool {
+        self.is_error
+    }
+
+    /// Subtype of the final `result` event (`success`, `error_max_turns`, ...).
+    pub fn subtype(&self) -> Option<&str> {
+        self.subtype.as_deref()
+    }
+
+    /// Session id reported by the CLI, used to resume the session later.
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser() -> StreamParser {
+        StreamParser::new(Uuid::new_v4(), Uuid::new_v4(), Some(4))
+    }
+
+    #[test]
+    fn test_init_event() {
+        let mut p = parser();
+        let cp = p
+            .feed(r#"{"type":"system","subtype":"init","model":"sonnet","session_id":"s1"}"#)
+            .unwrap();
+        assert!(cp.status_message.contains("sonnet"));
+        assert_eq!(cp.progress_pct, 0.0);
+        assert_eq!(p.session_id(), Some("s1"));
+        assert_eq!(cp.metadata["session_id"], "s1");
+    }
+
+    #[test]
+    fn test_tool_use_counts_turns_and_tokens() {
+        let mut p = parser();
+        let cp = p
+            .feed(
+                r#"{"type":"assistant","message":{"id":"m1","content":[{"type":"tool_use","name":"Bash","input":{}}],"usage":{"input_tokens":100,"output_tokens":20}}}"#,
+            )
+            .unwrap();
+        assert_eq!(cp.status_message, "Turn 1: using Bash");
+        assert!((cp.progress_pct - 0.25).abs() < f64::EPSILON);
+        assert!((cp.resource_consumed - 120.0).abs() < f64::EPSILON);
+        assert_eq!(cp.metadata["tools"][0], "Bash");
+
+        // The same message streamed again (next content block) is not a new turn.
+        p.feed(
+            r#"{"type":"assistant","message":{"id":"m1","content":[{"type":"text","text":"hi"}],"usage":{"input_tokens":100,"output_tokens":30}}}"#,
+        );
+        assert_eq!(p.turns(), 1);
+        assert_eq!(p.tokens(), 130);
+    }
+
+    #[test]
+    fn test_result_event() {
+        let mut p = parser();
+        p.feed(r#"{"type":"assistant","message":{"id":"m1","content":[{"type":"text","text":"working"}]}}"#);
+        let cp = p
+            .feed(r#"{"type":"result","subtype":"success","is_error":false,"num_turns":2,"total_cost_usd":0.01,"usage":{"input_tokens":5,"output_tokens":6},"result":"{\"result\":\"ok\"}"}"#)
+            .unwrap();
+        assert_eq!(cp.progress_pct, 1.0);
+        assert_eq!(p.turns(), 2);
+        assert_eq!(p.result(), Some("{\"result\":\"ok\"}"));
+        assert!(!p.is_error());
+        assert_eq!(p.usage().total_tokens(), 11);
+        assert_eq!(p.usage().num_turns, 2);
+    }
+
+    #[test]
+    fn test_ignores_noise() {
+        let mut p = parser();
+        assert!(p.feed("not json").is_none());
+        assert!(
+            p.feed(r#"{"type":"user","message":{"content":[]}}"#)
+                .is_none()
+        );
+        assert!(p.feed("").is_none());
+    }
+
+    #[test]
+    fn test_progress_never_completes_before_result() {
+        let mut p = StreamParser::new(Uuid::new_v4(), Uuid::new_v4(), Some(1));
+        p.feed(r#"{"type":"assistant","message":{"id":"m1","content":[]}}"#);
+        let cp = p
+            .feed(r#"{"type":"assistant","message":{"id":"m2","content":[{"type":"text","text":"x"}]}}"#)
+            .unwrap();
+        assert!(cp.progress_pct < 1.0);
+    }
+}
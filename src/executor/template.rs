@@ -0,0 +1,462 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::ExecutionContext;
+use super::schema::render_repair;
+use super::upstream::render_upstream;
+use crate::types::Task;
+use crate::types::error::PanopticonError;
+
+/// The built-in `task` template, used when an execution context carries no template.
+pub const BUILTIN_TASK_TEMPLATE: &str = include_str!("templates/task.md");
+
+/// Built-in templates, used unless the state dir's `templates/` directory overrides them.
+pub const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("task", BUILTIN_TASK_TEMPLATE),
+    ("plan", include_str!("templates/plan.md")),
+    ("router", include_str!("templates/router.md")),
+    ("aggregate", include_str!("templates/aggregate.md")),
+];
+
+/// A parsed prompt template.
+///
+/// Templates are plain text with `{{path.to.value}}` substitutions and
+/// `{{#if path}} ... {{else}} ... {{/if}}` / `{{#unless path}} ... {{/unless}}` blocks.
+/// Missing values render as empty; arrays of scalars render comma-separated and other
+/// structured values as JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    Block {
+        path: String,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// An `#if`/`#unless` block still being parsed.
+struct OpenBlock {
+    path: String,
+    negate: bool,
+    then: Vec<Node>,
+    otherwise: Option<Vec<Node>>,
+}
+
+impl OpenBlock {
+    fn new(path: &str, negate: bool) -> Result<Self, PanopticonError> {
+        Ok(Self {
+            path: parse_path(path.trim())?,
+            negate,
+            then: Vec::new(),
+            otherwise: None,
+        })
+    }
+
+    fn nodes(&mut self) -> &mut Vec<Node> {
+        self.otherwise.as_mut().unwrap_or(&mut self.then)
+    }
+
+    fn closing_tag(&self) -> &'static str {
+        if self.negate { "/unless" } else { "/if" }
+    }
+}
+
+impl PromptTemplate {
+    pub fn parse(source: &str) -> Result<Self, PanopticonError> {
+        let mut root = Vec::new();
+        let mut open: Vec<OpenBlock> = Vec::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            let text = &rest[..start];
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| {
+                PanopticonError::Template(format!("Unclosed '{{{{' near: {}", preview(after)))
+            })?;
+            let tag = after[..end].trim();
+            rest = &after[end + 2..];
+
+            let nodes = open.last_mut().map_or(&mut root, |b| b.nodes());
+            if !text.is_empty() {
+                nodes.push(Node::Text(text.to_string()));
+            }
+
+            if let Some(path) = tag.strip_prefix("#if ") {
+                open.push(OpenBlock::new(path, false)?);
+            } else if let Some(path) = tag.strip_prefix("#unless ") {
+                open.push(OpenBlock::new(path, true)?);
+            } else if tag == "else" {
+                let block = open.last_mut().ok_or_else(|| {
+                    PanopticonError::Template("'{{else}}' outside of a block".to_string())
+                })?;
+                if block.otherwise.is_some() {
+                    return Err(PanopticonError::Template(format!(
+                        "Duplicate '{{{{else}}}}' in block '{}'",
+                        block.path
+                    )));
+                }
+                block.otherwise = Some(Vec::new());
+            } else if tag == "/if" || tag == "/unless" {
+                let block = open.pop().ok_or_else(|| {
+                    PanopticonError::Template(format!("Unexpected '{{{{{tag}}}}}'"))
+                })?;
+                if tag != block.closing_tag() {
+                    return Err(PanopticonError::Template(format!(
+                        "Block '{}' closed with '{{{{{tag}}}}}'",
+                        block.path
+                    )));
+                }
+                let node = Node::Block {
+                    path: block.path,
+                    negate: block.negate,
+                    then: block.then,
+                    otherwise: block.otherwise.unwrap_or_default(),
+                };
+                open.last_mut().map_or(&mut root, |b| b.nodes()).push(node);
+            } else {
+                nodes.push(Node::Var(parse_path(tag)?));
+            }
+        }
+
+        if let Some(block) = open.last() {
+            return Err(PanopticonError::Template(format!(
+                "Block '{}' is never closed with '{{{{{}}}}}'",
+                block.path,
+                block.closing_tag()
+            )));
+        }
+        if !rest.is_empty() {
+            root.push(Node::Text(rest.to_string()));
+        }
+        Ok(Self { nodes: root })
+    }
+
+    pub fn render(&self, vars: &Value) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, vars, &mut out);
+        out
+    }
+}
+
+fn parse_path(path: &str) -> Result<String, PanopticonError> {
+    let valid = !path.is_empty()
+        && path
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if valid {
+        Ok(path.to_string())
+    } else {
+        Err(PanopticonError::Template(format!(
+            "Invalid tag '{{{{{path}}}}}'"
+        )))
+    }
+}
+
+fn preview(text: &str) -> String {
+    text.chars().take(30).collect()
+}
+
+fn render_nodes(nodes: &[Node], vars: &Value, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(path) => out.push_str(&display(lookup(vars, path))),
+            Node::Block {
+                path,
+                negate,
+                then,
+                otherwise,
+            } => {
+                if truthy(lookup(vars, path)) != *negate {
+                    render_nodes(then, vars, out);
+                } else {
+                    render_nodes(otherwise, vars, out);
+                }
+            }
+        }
+    }
+}
+
+fn lookup<'a>(vars: &'a Value, path: &str) -> &'a Value {
+    path.split('.').fold(vars, |value, key| match value {
+        Value::Array(items) => key
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| items.get(i))
+            .unwrap_or(&Value::Null),
+        _ => value.get(key).unwrap_or(&Value::Null),
+    })
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) if items.iter().all(|v| !v.is_array() && !v.is_object()) => {
+            items.iter().map(display).collect::<Vec<_>>().join(", ")
+        }
+        Value::Array(_) | Value::Object(_) => {
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Variables available to task templates.
+///
+/// - `task`: the full task (`task.name`, `task.description`, `task.required_capabilities`,
+///   `task.characteristics.complexity`, `task.deadline`, ...)
+/// - `upstream`: dependency outputs rendered as a prompt section, `dependencies`: the raw list
+/// - `repair`: why the previous output violated the task's schema, rendered as a prompt section
+/// - `feedback`: why the previous run failed verification, or a follow-up request
+/// - `contract`: the delegation contract, when there is one (`contract.monitoring.max_latency_ms`, ...)
+/// - `system_prompt`, `working_dir`, `agent_id`: from the execution context
+pub fn task_variables(task: &Task, context: &ExecutionContext) -> Value {
+    serde_json::json!({
+        "task": task,
+        "upstream": render_upstream(&context.upstream),
+        "dependencies": context.upstream,
+        "repair": render_repair(context.repair.as_ref()),
+        "feedback": context.feedback,
+        "contract": context.contract,
+        "system_prompt": context.system_prompt,
+        "working_dir": context.working_dir,
+        "agent_id": context.agent_id,
+    })
+}
+
+/// Named templates: files in `<state_dir>/templates/<name>.md`, falling back to the built-ins.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateStore {
+    dir: Option<PathBuf>,
+}
+
+impl TemplateStore {
+    pub fn new(state_dir: Option<&Path>) -> Self {
+        Self {
+            dir: state_dir.map(|d| d.join("templates")),
+        }
+    }
+
+    /// Directory holding template overrides, if any.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    fn file(&self, name: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|d| d.join(format!("{name}.md")))
+            .filter(|p| p.is_file())
+    }
+
+    /// The source of a template, checked to parse.
+    pub fn source(&self, name: &str) -> Result<String, PanopticonError> {
+        let source = match self.file(name) {
+            Some(path) => std::fs::read_to_string(&path).map_err(|e| {
+                PanopticonError::Template(format!("Failed to read {}: {e}", path.display()))
+            })?,
+            None => BUILTIN_TEMPLATES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, s)| s.to_string())
+                .ok_or_else(|| PanopticonError::Template(format!("Unknown template '{name}'")))?,
+        };
+        PromptTemplate::parse(&source)
+            .map_err(|e| PanopticonError::Template(format!("Template '{name}': {e}")))?;
+        Ok(source)
+    }
+
+    /// Load a template and render it with `vars`.
+    pub fn render(&self, name: &str, vars: &Value) -> Result<String, PanopticonError> {
+        Ok(PromptTemplate::parse(&self.source(name)?)?.render(vars))
+    }
+
+    /// All template names with whether each is overridden by (or only exists as) a file.
+    pub fn list(&self) -> Vec<(String, bool)> {
+        let mut names: Vec<(String, bool)> = BUILTIN_TEMPLATES
+            .iter()
+            .map(|(n, _)| (n.to_string(), self.file(n).is_some()))
+            .collect();
+        if let Some(Ok(entries)) = self.dir.as_ref().map(std::fs::read_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "md")
+                    && let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+                    && !names.iter().any(|(n, _)| n == stem)
+                {
+                    names.push((stem.to_string(), true));
+                }
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// Write the built-in templates into the templates directory so they can be edited.
+    /// Existing files are left alone. Returns the paths written.
+    pub fn init(&self) -> Result<Vec<PathBuf>, PanopticonError> {
+        let dir = self.dir.as_ref().ok_or_else(|| {
+            PanopticonError::Template("No state directory to write templates to".to_string())
+        })?;
+        std::fs::create_dir_all(dir).map_err(|e| {
+            PanopticonError::Template(format!("Failed to create {}: {e}", dir.display()))
+        })?;
+        let mut written = Vec::new();
+        for (name, source) in BUILTIN_TEMPLATES {
+            let path = dir.join(format!("{name}.md"));
+            if path.exists() {
+                continue;
+            }
+            std::fs::write(&path, source).map_err(|e| {
+                PanopticonError::Template(format!("Failed to write {}: {e}", path.display()))
+            })?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str, vars: Value) -> String {
+        PromptTemplate::parse(source).unwrap().render(&vars)
+    }
+
+    #[test]
+    fn test_variables_and_paths() {
+        let vars = serde_json::json!({
+            "task": {"name": "audit", "caps": ["web", "seo"], "chars": {"complexity": 0.7}},
+            "items": [{"n": 1}],
+        });
+        assert_eq!(render("# {{task.name}}", vars.clone()), "# audit");
+        assert_eq!(render("{{ task.caps }}", vars.clone()), "web, seo");
+        assert_eq!(render("{{task.chars.complexity}}", vars.clone()), "0.7");
+        assert_eq!(render("{{items.0.n}}", vars.clone()), "1");
+        assert_eq!(render("[{{missing.value}}]", vars), "[]");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let vars = serde_json::json!({"yes": true, "empty": [], "name": "x"});
+        assert_eq!(render("{{#if yes}}A{{else}}B{{/if}}", vars.clone()), "A");
+        assert_eq!(render("{{#if empty}}A{{else}}B{{/if}}", vars.clone()), "B");
+        assert_eq!(
+            render("{{#unless empty}}none{{/unless}}", vars.clone()),
+            "none"
+        );
+        assert_eq!(
+            render("{{#if yes}}<{{#if name}}{{name}}{{/if}}>{{/if}}", vars),
+            "<x>"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for source in [
+            "{{#if a}}open",
+            "{{/if}}",
+            "{{#if a}}x{{/unless}}",
+            "{{name",
+            "{{bad tag}}",
+            "{{else}}",
+            "{{#if a}}1{{else}}2{{else}}3{{/if}}",
+        ] {
+            let err = PromptTemplate::parse(source).unwrap_err();
+            assert!(matches!(err, PanopticonError::Template(_)), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_builtin_templates_parse() {
+        let store = TemplateStore::default();
+        for (name, _) in BUILTIN_TEMPLATES {
+            store.source(name).unwrap();
+        }
+        assert!(store.source("nope").is_err());
+    }
+
+    #[test]
+    fn test_task_template_renders_task_and_contract() {
+        let mut task = Task::new("audit", "Audit the site");
+        task.required_capabilities = vec!["web".to_string()];
+        let contract = serde_json::json!({
+            "monitoring": {"min_quality_score": 0.8, "max_latency_ms": 5000},
+            "permitted_actions": ["read"],
+        });
+        let mut vars = task_variables(&task, &ExecutionContext::default());
+        vars["contract"] = contract;
+        let prompt = TemplateStore::default().render("task", &vars).unwrap();
+        assert!(prompt.starts_with("# Task: audit\n\nAudit the site\n\n"));
+        assert!(prompt.contains("Required capabilities: web"));
+        assert!(prompt.contains("Maximum latency: 5000 ms"));
+        assert!(prompt.contains("Permitted actions: read"));
+        assert!(prompt.contains("\"result\" key"));
+    }
+
+    #[test]
+    fn test_task_template_renders_schema_and_repair() {
+        let task = Task::new("audit", "Audit the site")
+            .with_output_schema(serde_json::json!({"type": "object", "required": ["issues"]}));
+        let context = ExecutionContext {
+            repair: Some(crate::executor::SchemaRepair {
+                round: 1,
+                previous_output: serde_json::json!({"result": "none"}),
+                violations: crate::verification::CompiledSchema::compile(
+                    task.output_schema.as_ref().unwrap(),
+                )
+                .unwrap()
+                .validate(&serde_json::json!({"result": "none"})),
+            }),
+            ..Default::default()
+        };
+        let vars = task_variables(&task, &context);
+        let prompt = TemplateStore::default().render("task", &vars).unwrap();
+        assert!(prompt.contains("## Output schema"));
+        assert!(prompt.contains("\"required\": [\n    \"issues\"\n  ]"));
+        assert!(prompt.contains("- /: missing required property 'issues'"));
+    }
+
+    #[test]
+    fn test_store_overrides_and_init() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TemplateStore::new(Some(dir.path()));
+        assert_eq!(store.list().iter().filter(|(_, file)| *file).count(), 0);
+
+        let written = store.init().unwrap();
+        assert_eq!(written.len(), BUILTIN_TEMPLATES.len());
+        assert!(store.init().unwrap().is_empty());
+
+        let templates = dir.path().join("templates");
+        std::fs::write(templates.join("task.md"), "Do {{task.name}} carefully").unwrap();
+        std::fs::write(templates.join("review.md"), "Review {{task.name}}").unwrap();
+        let vars = serde_json::json!({"task": {"name": "x"}});
+        assert_eq!(store.render("task", &vars).unwrap(), "Do x carefully");
+        assert_eq!(store.render("review", &vars).unwrap(), "Review x");
+        assert!(store.list().contains(&("review".to_string(), true)));
+
+        std::fs::write(templates.join("broken.md"), "{{#if x}}").unwrap();
+        assert!(store.source("broken").is_err());
+    }
+}
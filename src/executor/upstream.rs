@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::types::{Artifact, Task};
+
+/// The result of a completed dependency, handed to the task that depends on it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpstreamOutput {
+    pub task_id: Uuid,
+    pub name: String,
+    /// The dependency's output (`Task.metadata`), possibly condensed to fit the size limits.
+    pub output: Value,
+    /// Whether `output` was shortened.
+    #[serde(default)]
+    pub condensed: bool,
+    /// Directory the dependency ran in; its artifacts are relative to it.
+    #[serde(default)]
+    pub workspace: Option<String>,
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+}
+
+impl UpstreamOutput {
+    pub fn from_task(task: &Task) -> Self {
+        Self {
+            task_id: task.id,
+            name: task.name.clone(),
+            output: task.output(),
+            condensed: false,
+            workspace: task.workspace.clone(),
+            artifacts: task.artifacts.clone(),
+        }
+    }
+}
+
+/// Size limits on the dependency outputs injected into a task's prompt, in characters
+/// of serialized JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpstreamLimits {
+    /// Budget for any single dependency's output.
+    pub max_chars_per_task: usize,
+    /// Budget for all dependency outputs together, split evenly between them.
+    pub max_chars_total: usize,
+}
+
+impl Default for UpstreamLimits {
+    fn default() -> Self {
+        Self {
+            max_chars_per_task: 4000,
+            max_chars_total: 12_000,
+        }
+    }
+}
+
+impl UpstreamLimits {
+    /// Condense outputs so each fits its share of the budget.
+    pub fn apply(&self, outputs: Vec<UpstreamOutput>) -> Vec<UpstreamOutput> {
+        if outputs.is_empty() {
+            return outputs;
+        }
+        let share = self
+            .max_chars_per_task
+            .min(self.max_chars_total / outputs.len());
+        outputs
+            .into_iter()
+            .map(|mut upstream| {
+                if let Some(output) = condense(&upstream.output, share) {
+                    upstream.output = output;
+                    upstream.condensed = true;
+                }
+                upstream
+            })
+            .collect()
+    }
+}
+
+/// Shrink `output` to roughly `limit` characters of JSON, or `None` if it already fits.
+///
+/// Outputs that carry a `summary` keep it and as much of `result` as still fits;
+/// anything else is cut down to a prefix of its serialized form.
+fn condense(output: &Value, limit: usize) -> Option<Value> {
+    let full = output.to_string();
+    if full.chars().count() <= limit {
+        return None;
+    }
+
+    if let Some(summary) = output.get("summary").and_then(|s| s.as_str()) {
+        let summary = truncate(summary, limit / 2);
+        let result = match output.get("result") {
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+        let room = limit.saturating_sub(summary.chars().count());
+        return Some(serde_json::json!({
+            "summary": summary,
+            "result": truncate(&result, room),
+        }));
+    }
+
+    Some(Value::String(truncate(&full, limit)))
+}
+
+/// The first `limit` characters of `text`, with a marker saying how much was dropped.
+fn truncate(text: &str, limit: usize) -> String {
+    let total = text.chars().count();
+    if total <= limit {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(limit).collect();
+    format!("{kept}... [{} more characters omitted]", total - limit)
+}
+
+/// Render dependency outputs as a prompt section. Empty when there are none.
+pub fn render_upstream(outputs: &[UpstreamOutput]) -> String {
+    if outputs.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("## Results of prerequisite tasks\n\n");
+    for upstream in outputs {
+        section.push_str(&format!("### {}\n\n", upstream.name));
+        if upstream.condensed {
+            section.push_str("(condensed to fit the context budget)\n\n");
+        }
+        let body =
+            serde_json::to_string_pretty(&upstream.output).unwrap_or_else(|_| "null".to_string());
+        section.push_str(&format!("```json\n{body}\n```\n\n"));
+        if !upstream.artifacts.is_empty() {
+            match &upstream.workspace {
+                Some(dir) => section.push_str(&format!("Files produced (in {dir}):\n")),
+                None => section.push_str("Files produced:\n"),
+            }
+            for artifact in &upstream.artifacts {
+                section.push_str(&format!("- {} ({} bytes)\n", artifact.path, artifact.size));
+            }
+            section.push('\n');
+        }
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upstream(name: &str, output: Value) -> UpstreamOutput {
+        let mut task = Task::new(name, "desc");
+        task.metadata = output;
+        UpstreamOutput::from_task(&task)
+    }
+
+    #[test]
+    fn test_small_outputs_pass_through() {
+        let outputs = UpstreamLimits::default().apply(vec![upstream(
+            "audit",
+            serde_json::json!({"result": "3 issues", "summary": "ok"}),
+        )]);
+        assert!(!outputs[0].condensed);
+        assert_eq!(outputs[0].output["result"], "3 issues");
+    }
+
+    #[test]
+    fn test_large_output_keeps_summary() {
+        let limits = UpstreamLimits {
+            max_chars_per_task: 200,
+            max_chars_total: 1000,
+        };
+        let outputs = limits.apply(vec![upstream(
+            "audit",
+            serde_json::json!({"result": "x".repeat(5000), "summary": "found 3 issues"}),
+        )]);
+        assert!(outputs[0].condensed);
+        assert_eq!(outputs[0].output["summary"], "found 3 issues");
+        let result = outputs[0].output["result"].as_str().unwrap();
+        assert!(result.len() < 300);
+        assert!(result.contains("more characters omitted"));
+    }
+
+    #[test]
+    fn test_total_budget_is_split_between_dependencies() {
+        let limits = UpstreamLimits {
+            max_chars_per_task: 10_000,
+            max_chars_total: 300,
+        };
+        let big = serde_json::json!(["y".repeat(200)]);
+        let outputs = limits.apply(vec![upstream("a", big.clone()), upstream("b", big)]);
+        for output in &outputs {
+            assert!(output.condensed);
+            assert!(output.output.as_str().unwrap().starts_with("[\"yyy"));
+        }
+    }
+
+    #[test]
+    fn test_render_lists_outputs_and_artifacts() {
+        let mut audit = upstream("audit", serde_json::json!({"result": "3 issues"}));
+        audit.workspace = Some("/tmp/ws".to_string());
+        audit.artifacts.push(Artifact {
+            path: "report.md".to_string(),
+            size: 42,
+            sha256: "00".repeat(32),
+        });
+        let section = render_upstream(&[audit]);
+        assert!(section.contains("### audit"));
+        assert!(section.contains("3 issues"));
+        assert!(section.contains("report.md (42 bytes)"));
+        assert!(section.contains("/tmp/ws"));
+        assert!(render_upstream(&[]).is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::types::Artifact;
+use crate::types::error::PanopticonError;
+use crate::util::glob_match;
+
+/// Which files of a workspace are hashed and captured as artifacts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceConfig {
+    /// Paths never captured, in `.gitignore` syntax: a pattern matches file and directory
+    /// names at any depth, or paths relative to the workspace when it contains a `/`; a
+    /// trailing `/` matches directories only; a leading `!` captures what an earlier pattern
+    /// ignored (the last matching pattern wins). `*` and `?` are wildcards.
+    pub ignore: Vec<String>,
+    /// Whether the patterns of the workspace's top-level `.gitignore` are skipped too.
+    pub gitignore: bool,
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        Self {
+            ignore: vec![
+                ".git/".to_string(),
+                "target/".to_string(),
+                "node_modules/".to_string(),
+            ],
+            gitignore: true,
+        }
+    }
+}
+
+/// One ignore pattern.
+#[derive(Debug, Clone, PartialEq)]
+struct IgnoreRule {
+    pattern: String,
+    /// Matched against the relative path rather than the name.
+    anchored: bool,
+    dir_only: bool,
+    /// A `!pattern`: what it matches is captured again.
+    negated: bool,
+}
+
+impl IgnoreRule {
+    /// Parse a `.gitignore` line; blank lines and comments yield nothing.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let dir_only = line.ends_with('/');
+        let line = line.trim_end_matches('/');
+        let anchored = line.contains('/');
+        let pattern = line.trim_start_matches('/').to_string();
+        (!pattern.is_empty()).then_some(Self {
+            pattern,
+            anchored,
+            dir_only,
+            negated,
+        })
+    }
+
+    fn matches(&self, relative: &str, name: &str, is_dir: bool) -> bool {
+        (is_dir || !self.dir_only)
+            && glob_match(&self.pattern, if self.anchored { relative } else { name })
+    }
+}
+
+/// The directory a task executes in, and the files it contains.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+    ignore: Vec<IgnoreRule>,
+}
+
+/// The files of a workspace at one point in time, keyed by relative path.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSnapshot {
+    files: BTreeMap<String, Artifact>,
+}
+
+impl WorkspaceSnapshot {
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl Workspace {
+    /// Use an existing directory (e.g. an agent's configured `working_dir`) as the workspace,
+    /// ignoring what the default `WorkspaceConfig` ignores.
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            ignore: Vec::new(),
+        }
+        .with_config(&WorkspaceConfig::default())
+    }
+
+    /// Ignore the paths `config` lists, and those of the top-level `.gitignore` if it says so.
+    /// The `.gitignore` is read once, here, so that every snapshot skips the same files.
+    pub fn with_config(mut self, config: &WorkspaceConfig) -> Self {
+        self.ignore = config
+            .ignore
+            .iter()
+            .filter_map(|line| IgnoreRule::parse(line))
+            .collect();
+        if config.gitignore
+            && let Ok(gitignore) = std::fs::read_to_string(self.root.join(".gitignore"))
+        {
+            self.ignore
+                .extend(gitignore.lines().filter_map(IgnoreRule::parse));
+        }
+        self
+    }
+
+    /// The task's own workspace, `<state_dir>/workspaces/<task_id>`, created if missing.
+    pub fn for_task(state_dir: &Path, task_id: Uuid) -> Result<Self, PanopticonError> {
+        let root = state_dir.join("workspaces").join(task_id.to_string());
+        std::fs::create_dir_all(&root).map_err(|e| {
+            PanopticonError::Internal(format!(
+                "Failed to create workspace {}: {e}",
+                root.display()
+            ))
+        })?;
+        Ok(Self::at(root))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Hash every regular file under the workspace. Symlinks and ignored paths are skipped.
+    pub fn snapshot(&self) -> Result<WorkspaceSnapshot, PanopticonError> {
+        let mut snapshot = WorkspaceSnapshot::default();
+        if self.root.is_dir() {
+            self.walk(&self.root, &mut snapshot)?;
+        }
+        Ok(snapshot)
+    }
+
+    /// Files created or modified since `before` was taken, sorted by path.
+    pub fn artifacts_since(
+        &self,
+        before: &WorkspaceSnapshot,
+    ) -> Result<Vec<Artifact>, PanopticonError> {
+        let after = self.snapshot()?;
+        Ok(after
+            .files
+            .into_values()
+            .filter(|a| before.files.get(&a.path) != Some(a))
+            .collect())
+    }
+
+    fn walk(&self, dir: &Path, snapshot: &mut WorkspaceSnapshot) -> Result<(), PanopticonError> {
+        let entries = std::fs::read_dir(dir).map_err(|e| io_error(dir, e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| io_error(dir, e))?;
+            let path = entry.path();
+            let file_type = entry.file_type().map_err(|e| io_error(&path, e))?;
+            if self.is_ignored(&path, file_type.is_dir()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                self.walk(&path, snapshot)?;
+            } else if file_type.is_file() {
+                let artifact = self.hash_file(&path)?;
+                snapshot.files.insert(artifact.path.clone(), artifact);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the last rule matching the path ignores it. As in git, a file under an ignored
+    /// directory cannot be captured again, since the directory is never walked.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.ignore.is_empty() {
+            return false;
+        }
+        let relative = self.relative(path);
+        let name = relative.rsplit('/').next().unwrap_or(&relative);
+        self.ignore
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&relative, name, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// `path` relative to the root, with `/` separators.
+    fn relative(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn hash_file(&self, path: &Path) -> Result<Artifact, PanopticonError> {
+        let mut file = std::fs::File::open(path).map_err(|e| io_error(path, e))?;
+        let mut hasher = Sha256::new();
+        let size = std::io::copy(&mut file, &mut hasher).map_err(|e| io_error(path, e))?;
+        Ok(Artifact {
+            path: self.relative(path),
+            size,
+            sha256: format!("{:x}", hasher.finalize()),
+        })
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> PanopticonError {
+    PanopticonError::Internal(format!("Failed to read {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_task_creates_directory_under_state_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let task_id = Uuid::new_v4();
+        let workspace = Workspace::for_task(dir.path(), task_id).unwrap();
+        assert!(workspace.root().is_dir());
+        assert!(workspace.root().ends_with(task_id.to_string()));
+        assert!(workspace.root().starts_with(dir.path().join("workspaces")));
+    }
+
+    #[test]
+    fn test_artifacts_since_reports_created_and_modified_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("unchanged.txt"), "same").unwrap();
+        std::fs::write(root.join("edited.txt"), "before").unwrap();
+        let workspace = Workspace::at(root);
+        let before = workspace.snapshot().unwrap();
+        assert_eq!(before.len(), 2);
+
+        std::fs::write(root.join("edited.txt"), "after").unwrap();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join("src/nested/new.rs"), "fn main() {}").unwrap();
+
+        let artifacts = workspace.artifacts_since(&before).unwrap();
+        let paths: Vec<&str> = artifacts.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["edited.txt", "src/nested/new.rs"]);
+        assert_eq!(artifacts[0].size, 5);
+        // sha256("after")
+        assert_eq!(
+            artifacts[0].sha256,
+            "f39592393ef0859cb196a52693d2cea00fb2df784b3c04ae54aa7cadb8e562f8"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_skips_git_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".git/HEAD"), "ref").unwrap();
+        std::fs::write(dir.path().join("README.md"), "hi").unwrap();
+        let snapshot = Workspace::at(dir.path()).snapshot().unwrap();
+        assert_eq!(snapshot.len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_skips_ignored_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in [
+            "target/debug/app",
+            "web/node_modules/lib/index.js",
+            "build/out.o",
+            "logs/run.log",
+            "logs/keep.log",
+            "src/main.rs",
+            "docs/build",
+        ] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), "x").unwrap();
+        }
+        std::fs::write(
+            root.join(".gitignore"),
+            "# generated\n/build/\n*.log\n!keep.log\n",
+        )
+        .unwrap();
+
+        let paths = |workspace: Workspace| -> Vec<String> {
+            workspace.snapshot().unwrap().files.into_keys().collect()
+        };
+        assert_eq!(
+            paths(Workspace::at(root)),
+            vec![".gitignore", "docs/build", "logs/keep.log", "src/main.rs"]
+        );
+
+        let config = WorkspaceConfig {
+            ignore: vec!["src/*.rs".to_string()],
+            gitignore: false,
+        };
+        let all = paths(Workspace::at(root).with_config(&config));
+        assert!(all.contains(&"target/debug/app".to_string()));
+        assert!(all.contains(&"logs/run.log".to_string()));
+        assert!(!all.contains(&"src/main.rs".to_string()));
+    }
+
+    #[test]
+    fn test_snapshot_of_missing_directory_is_empty() {
+        let snapshot = Workspace::at("/nonexistent/panopticon/workspace")
+            .snapshot()
+            .unwrap();
+        assert!(snapshot.is_empty());
+    }
+}
@@ -7,12 +7,24 @@ use uuid::Uuid;
 pub enum LedgerEntryKind {
     TaskCreated,
     TaskStateChanged,
+    /// A task was given to an agent, or taken away from one.
+    TaskAssigned,
+    /// Fields of a task other than its state and assignee were changed.
+    TaskUpdated,
+    /// A task was deleted (replaced by a new decomposition).
+    TaskRemoved,
     AgentRegistered,
+    /// An agent's capabilities, permitted actions, or capacity were changed.
+    AgentUpdated,
     DelegationRequested,
     BidSubmitted,
     ContractCreated,
     ContractSigned,
+    /// A contract's terms were changed.
+    ContractUpdated,
     CheckpointRecorded,
+    ExecutionCancelled,
+    ExecutionAttempt,
     VerificationResult,
     DisputeOpened,
     DisputeResolved,
@@ -21,6 +33,8 @@ pub enum LedgerEntryKind {
     PermissionRevoked,
     SecurityAlert,
     PaymentProcessed,
+    /// The outcome of a coordinator response action.
+    ResponseExecuted,
 }
 
 /// An immutable ledger entry.
@@ -74,6 +88,47 @@ impl LedgerEntry {
     }
 }
 
+/// Payload of an entry recording a change to the domain: the subject's state before and after
+/// the change (`null` where it did not exist), the command that triggered it, and `details`.
+pub fn mutation_payload(
+    previous: serde_json::Value,
+    new: serde_json::Value,
+    command: Option<&str>,
+    details: serde_json::Value,
+) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "previous": previous,
+        "new": new,
+        "command": command,
+    });
+    if let (Some(payload), serde_json::Value::Object(details)) = (payload.as_object_mut(), details)
+    {
+        payload.extend(details);
+    }
+    payload
+}
+
+/// The top-level fields that differ between two snapshots of an object, as `(previous, new)`
+/// objects holding only those fields, or `None` if nothing but the `ignored` fields changed.
+pub fn changed_fields(
+    previous: &serde_json::Value,
+    new: &serde_json::Value,
+    ignored: &[&str],
+) -> Option<(serde_json::Value, serde_json::Value)> {
+    let (Some(before), Some(after)) = (previous.as_object(), new.as_object()) else {
+        return (previous != new).then(|| (previous.clone(), new.clone()));
+    };
+    let (mut old, mut changed) = (serde_json::Map::new(), serde_json::Map::new());
+    for key in before.keys().chain(after.keys().filter(|k| !before.contains_key(*k))) {
+        let (a, b) = (before.get(key), after.get(key));
+        if a != b && !ignored.contains(&key.as_str()) {
+            old.insert(key.clone(), a.cloned().unwrap_or_default());
+            changed.insert(key.clone(), b.cloned().unwrap_or_default());
+        }
+    }
+    (!changed.is_empty()).then(|| (old.into(), changed.into()))
+}
+
 /// A simple non-cryptographic hash for the default (non-merkle) ledger.
 fn md5_like_hash(data: &[u8]) -> u128 {
     let mut h: u128 = 0xcbf29ce484222325;
@@ -101,6 +156,30 @@ mod tests {
         assert!(entry.previous_hash.is_none());
     }
 
+    #[test]
+    fn test_mutation_payload() {
+        let payload = mutation_payload(
+            serde_json::json!("Pending"),
+            serde_json::json!("InProgress"),
+            Some("/execute"),
+            serde_json::json!({"event": "StartExecution"}),
+        );
+        assert_eq!(payload["previous"], "Pending");
+        assert_eq!(payload["new"], "InProgress");
+        assert_eq!(payload["command"], "/execute");
+        assert_eq!(payload["event"], "StartExecution");
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let before = serde_json::json!({"priority": 0.5, "name": "t", "updated_at": 1});
+        let after = serde_json::json!({"priority": 0.9, "name": "t", "updated_at": 2});
+        let (previous, new) = changed_fields(&before, &after, &["updated_at"]).unwrap();
+        assert_eq!(previous, serde_json::json!({"priority": 0.5}));
+        assert_eq!(new, serde_json::json!({"priority": 0.9}));
+        assert!(changed_fields(&before, &before, &[]).is_none());
+    }
+
     #[test]
     fn test_chained_entries() {
         let first = LedgerEntry::new(
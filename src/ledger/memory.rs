@@ -24,6 +24,19 @@ impl InMemoryLedger {
             index_by_subject: Arc::new(DashMap::new()),
         }
     }
+
+    /// Index and push an entry; the caller holds the write lock.
+    fn push(&self, entries: &mut Vec<LedgerEntry>, entry: LedgerEntry) {
+        let idx = entries.len();
+
+        self.index_by_id.insert(entry.id, idx);
+        self.index_by_subject
+            .entry(entry.subject_id)
+            .or_default()
+            .push(idx);
+
+        entries.push(entry);
+    }
 }
 
 impl Default for InMemoryLedger {
@@ -36,18 +49,24 @@ impl Default for InMemoryLedger {
 impl Ledger for InMemoryLedger {
     async fn append(&self, entry: LedgerEntry) -> Result<(), PanopticonError> {
         let mut entries = self.entries.write().await;
-        let idx = entries.len();
-
-        self.index_by_id.insert(entry.id, idx);
-        self.index_by_subject
-            .entry(entry.subject_id)
-            .or_default()
-            .push(idx);
-
-        entries.push(entry);
+        self.push(&mut entries, entry);
         Ok(())
     }
 
+    async fn append_chained(
+        &self,
+        kind: LedgerEntryKind,
+        actor_id: Uuid,
+        subject_id: Uuid,
+        payload: serde_json::Value,
+    ) -> Result<LedgerEntry, PanopticonError> {
+        let mut entries = self.entries.write().await;
+        let previous_hash = entries.last().map(|e| e.hash.clone());
+        let entry = LedgerEntry::new(kind, actor_id, subject_id, payload, previous_hash);
+        self.push(&mut entries, entry.clone());
+        Ok(entry)
+    }
+
     async fn get(&self, id: Uuid) -> Result<Option<LedgerEntry>, PanopticonError> {
         let entries = self.entries.read().await;
         Ok(self
@@ -91,6 +110,11 @@ impl Ledger for InMemoryLedger {
         Ok(entries.clone())
     }
 
+    async fn entries_since(&self, count: usize) -> Result<Vec<LedgerEntry>, PanopticonError> {
+        let entries = self.entries.read().await;
+        Ok(entries.get(count..).unwrap_or_default().to_vec())
+    }
+
     async fn verify_integrity(&self) -> Result<bool, PanopticonError> {
         let entries = self.entries.read().await;
         for (i, entry) in entries.iter().enumerate() {
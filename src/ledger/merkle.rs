@@ -66,6 +66,24 @@ impl Ledger for MerkleLedger {
         Ok(())
     }
 
+    async fn append_chained(
+        &self,
+        kind: LedgerEntryKind,
+        actor_id: Uuid,
+        subject_id: Uuid,
+        payload: serde_json::Value,
+    ) -> Result<LedgerEntry, PanopticonError> {
+        let mut entries = self.entries.write().await;
+        let mut tree = self.tree.write().await;
+
+        let previous_hash = entries.last().map(|e| e.hash.clone());
+        let entry = LedgerEntry::new(kind, actor_id, subject_id, payload, previous_hash);
+        tree.insert(entry_to_leaf(&entry));
+        tree.commit();
+        entries.push(entry.clone());
+        Ok(entry)
+    }
+
     async fn get(&self, id: Uuid) -> Result<Option<LedgerEntry>, PanopticonError> {
         let entries = self.entries.read().await;
         Ok(entries.iter().find(|e| e.id == id).cloned())
@@ -10,6 +10,16 @@ pub trait Ledger: Send + Sync {
     /// Append an entry to the ledger.
     async fn append(&self, entry: LedgerEntry) -> Result<(), PanopticonError>;
 
+    /// Create an entry chained onto the latest one and append it, reading the latest hash and
+    /// appending under one lock so that concurrent writers cannot chain onto the same entry.
+    async fn append_chained(
+        &self,
+        kind: LedgerEntryKind,
+        actor_id: Uuid,
+        subject_id: Uuid,
+        payload: serde_json::Value,
+    ) -> Result<LedgerEntry, PanopticonError>;
+
     /// Get an entry by its ID.
     async fn get(&self, id: Uuid) -> Result<Option<LedgerEntry>, PanopticonError>;
 
@@ -29,6 +39,11 @@ pub trait Ledger: Send + Sync {
     /// Get all entries (for auditing).
     async fn all_entries(&self) -> Result<Vec<LedgerEntry>, PanopticonError>;
 
+    /// Entries appended after the first `count`, in order.
+    async fn entries_since(&self, count: usize) -> Result<Vec<LedgerEntry>, PanopticonError> {
+        Ok(self.all_entries().await?.into_iter().skip(count).collect())
+    }
+
     /// Verify the chain integrity.
     async fn verify_integrity(&self) -> Result<bool, PanopticonError>;
 }
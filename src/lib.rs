@@ -1,3 +1,4 @@
+pub mod aggregation;
 pub mod assignment;
 pub mod cli;
 pub mod config;
@@ -11,6 +12,8 @@ pub mod permissions;
 pub mod persistence;
 pub mod repl;
 pub mod reputation;
+pub mod scheduler;
 pub mod security;
 pub mod types;
+pub mod util;
 pub mod verification;
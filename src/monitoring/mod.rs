@@ -1,7 +1,9 @@
 pub mod checkpoint;
 pub mod monitor;
 pub mod slo;
+pub mod watch;
 
 pub use checkpoint::*;
 pub use monitor::*;
 pub use slo::*;
+pub use watch::*;
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, watch};
 use uuid::Uuid;
 
-use crate::ledger::{Ledger, LedgerEntry, LedgerEntryKind};
+use crate::ledger::{Ledger, LedgerEntryKind};
 
 use super::checkpoint::Checkpoint;
 use super::slo::{SloChecker, SloViolation};
@@ -61,6 +61,8 @@ pub struct MonitoringLoop {
     config: MonitoringConfig,
     /// Tracks last checkpoint time per agent.
     agent_heartbeats: HashMap<Uuid, DateTime<Utc>>,
+    /// Whether agents that go silent are reported unresponsive.
+    check_heartbeats: bool,
 }
 
 impl MonitoringLoop {
@@ -80,10 +82,26 @@ impl MonitoringLoop {
             ledger,
             config,
             agent_heartbeats: HashMap::new(),
+            check_heartbeats: true,
         }
     }
 
-    /// Run the monitoring loop until shutdown is signalled.
+    /// Expect checkpoints from an agent from now on, so it is reported unresponsive even if
+    /// it never sends one.
+    pub fn watch_agent(mut self, agent_id: Uuid) -> Self {
+        self.agent_heartbeats.insert(agent_id, Utc::now());
+        self
+    }
+
+    /// Only check checkpoints against the SLOs, for agents that report none between the start
+    /// and the end of their work: their silence says nothing about whether they stalled.
+    pub fn without_heartbeats(mut self) -> Self {
+        self.check_heartbeats = false;
+        self
+    }
+
+    /// Run the monitoring loop until shutdown is signalled. Checkpoints sent before the
+    /// signal are still handled.
     pub async fn run(mut self) {
         let mut heartbeat_interval = tokio::time::interval(self.config.heartbeat_check_interval);
 
@@ -92,11 +110,14 @@ impl MonitoringLoop {
                 Some(checkpoint) = self.checkpoint_rx.recv() => {
                     self.handle_checkpoint(checkpoint).await;
                 }
-                _ = heartbeat_interval.tick() => {
+                _ = heartbeat_interval.tick(), if self.check_heartbeats => {
                     self.check_heartbeats().await;
                 }
                 Ok(()) = self.shutdown_rx.changed() => {
                     if *self.shutdown_rx.borrow() {
+                        while let Ok(checkpoint) = self.checkpoint_rx.try_recv() {
+                            self.handle_checkpoint(checkpoint).await;
+                        }
                         tracing::info!("Monitoring loop shutting down");
                         break;
                     }
@@ -135,15 +156,16 @@ impl MonitoringLoop {
 
         // Record to ledger
         let payload = serde_json::to_value(&checkpoint).unwrap_or_default();
-        let prev_hash = self.ledger.latest_hash().await.unwrap_or(None);
-        let entry = LedgerEntry::new(
-            LedgerEntryKind::CheckpointRecorded,
-            checkpoint.agent_id,
-            checkpoint.task_id,
-            payload,
-            prev_hash,
-        );
-        if let Err(e) = self.ledger.append(entry).await {
+        let recorded = self
+            .ledger
+            .append_chained(
+                LedgerEntryKind::CheckpointRecorded,
+                checkpoint.agent_id,
+                checkpoint.task_id,
+                payload,
+            )
+            .await;
+        if let Err(e) = recorded {
             tracing::error!("Failed to record checkpoint to ledger: {}", e);
         }
     }
@@ -201,6 +223,20 @@ mod tests {
             Ok(())
         }
 
+        async fn append_chained(
+            &self,
+            kind: LedgerEntryKind,
+            actor_id: Uuid,
+            subject_id: Uuid,
+            payload: serde_json::Value,
+        ) -> Result<LedgerEntry, PanopticonError> {
+            let mut entries = self.entries.lock().unwrap();
+            let previous_hash = entries.last().map(|e| e.hash.clone());
+            let entry = LedgerEntry::new(kind, actor_id, subject_id, payload, previous_hash);
+            entries.push(entry.clone());
+            Ok(entry)
+        }
+
         async fn get(&self, id: Uuid) -> Result<Option<LedgerEntry>, PanopticonError> {
             Ok(self
                 .entries
@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::checkpoint::Checkpoint;
+use crate::types::MonitoringTerms;
 
 /// Comparison operator for SLO threshold checks.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -20,6 +21,31 @@ pub struct SloDefinition {
     pub window_secs: u64,
 }
 
+impl SloDefinition {
+    /// The objectives set by a contract's monitoring terms: resources consumed below
+    /// `max_resource_budget`, time since the execution started (`elapsed_ms`) below
+    /// `max_latency_ms`, and a reported `quality_score` above `min_quality_score`, each
+    /// evaluated every checkpoint interval. Terms left at zero set no objective.
+    pub fn from_terms(terms: &MonitoringTerms) -> Vec<Self> {
+        let window_secs = terms.checkpoint_interval_secs;
+        let objectives = [
+            ("resource_consumed", terms.max_resource_budget, Comparison::LessThan),
+            ("elapsed_ms", terms.max_latency_ms as f64, Comparison::LessThan),
+            ("quality_score", terms.min_quality_score, Comparison::GreaterThan),
+        ];
+        objectives
+            .into_iter()
+            .filter(|(_, threshold, _)| *threshold > 0.0)
+            .map(|(metric_name, threshold, comparison)| Self {
+                metric_name: metric_name.to_string(),
+                threshold,
+                comparison,
+                window_secs,
+            })
+            .collect()
+    }
+}
+
 /// A detected SLO violation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SloViolation {
@@ -38,14 +64,19 @@ impl SloChecker {
         Self { definitions }
     }
 
-    /// Check a checkpoint against all SLO definitions and return any violations.
+    /// Check a checkpoint against all SLO definitions and return any violations. Metrics
+    /// other than `progress_pct` and `resource_consumed` are read from the checkpoint's
+    /// metadata, and skipped when it does not report them.
     pub fn check(&self, checkpoint: &Checkpoint) -> Vec<SloViolation> {
         let mut violations = Vec::new();
         for def in &self.definitions {
             let actual_value = match def.metric_name.as_str() {
                 "progress_pct" => checkpoint.progress_pct,
                 "resource_consumed" => checkpoint.resource_consumed,
-                _ => continue,
+                metric => match checkpoint.metadata.get(metric).and_then(|v| v.as_f64()) {
+                    Some(value) => value,
+                    None => continue,
+                },
             };
 
             let violated = match def.comparison {
@@ -113,6 +144,28 @@ mod tests {
         assert_eq!(violations.len(), 1);
     }
 
+    #[test]
+    fn test_slo_from_contract_terms() {
+        let terms = MonitoringTerms {
+            checkpoint_interval_secs: 30,
+            max_latency_ms: 1_000,
+            min_quality_score: 0.0,
+            max_resource_budget: 500.0,
+        };
+        let defs = SloDefinition::from_terms(&terms);
+        let metrics: Vec<&str> = defs.iter().map(|d| d.metric_name.as_str()).collect();
+        assert_eq!(metrics, vec!["resource_consumed", "elapsed_ms"]);
+        assert!(defs.iter().all(|d| d.window_secs == 30));
+
+        let checker = SloChecker::new(defs);
+        let cp = Checkpoint::new(Uuid::new_v4(), Uuid::new_v4())
+            .with_resource_consumed(100.0)
+            .with_metadata(serde_json::json!({ "elapsed_ms": 2_500 }));
+        let violations = checker.check(&cp);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].definition.metric_name, "elapsed_ms");
+    }
+
     #[test]
     fn test_slo_unknown_metric_ignored() {
         let defs = vec![SloDefinition {
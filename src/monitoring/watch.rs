@@ -0,0 +1,226 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, watch};
+use uuid::Uuid;
+
+use super::checkpoint::Checkpoint;
+use super::monitor::{MonitoringConfig, MonitoringEvent, MonitoringLoop};
+use super::slo::{SloChecker, SloDefinition};
+use crate::ledger::Ledger;
+use crate::types::MonitoringTerms;
+
+/// How task executions are monitored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Whether executions are checked against their contract's monitoring terms; when off,
+    /// checkpoints are only shown.
+    pub enabled: bool,
+    /// Checkpoint intervals an agent may go without reporting before it is considered
+    /// unresponsive.
+    pub missed_checkpoints: u32,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            missed_checkpoints: 3,
+        }
+    }
+}
+
+impl WatchConfig {
+    /// Loop settings for a contract: heartbeats are checked every checkpoint interval.
+    pub fn monitoring_config(&self, terms: &MonitoringTerms) -> MonitoringConfig {
+        let interval = Duration::from_secs(terms.checkpoint_interval_secs.max(1));
+        MonitoringConfig {
+            heartbeat_timeout: interval * self.missed_checkpoints.max(1),
+            heartbeat_check_interval: interval,
+        }
+    }
+}
+
+/// Monitor an agent's execution of a task under its contract's monitoring terms.
+///
+/// Every checkpoint received on `checkpoints` is handed to `on_checkpoint`, stamped with the
+/// time since the watch started (`metadata.elapsed_ms`), and passed to a `MonitoringLoop`
+/// that records it in the ledger and checks it against `SloDefinition::from_terms`. When
+/// `heartbeats` is set (the executor reports checkpoints throughout, see
+/// `AgentExecutor::reports_checkpoints`) the agent is expected to report from the start, so one
+/// that never does is still reported unresponsive; otherwise it is never reported unresponsive.
+///
+/// Returns the loop's events, which end once `checkpoints` closes and everything sent on it
+/// has been checked.
+pub fn watch_execution(
+    agent_id: Uuid,
+    terms: &MonitoringTerms,
+    config: &WatchConfig,
+    heartbeats: bool,
+    mut checkpoints: mpsc::Receiver<Checkpoint>,
+    on_checkpoint: impl Fn(&Checkpoint) + Send + 'static,
+    ledger: Arc<dyn Ledger>,
+) -> mpsc::Receiver<MonitoringEvent> {
+    let (event_tx, event_rx) = mpsc::channel(64);
+    if !config.enabled {
+        tokio::spawn(async move {
+            while let Some(checkpoint) = checkpoints.recv().await {
+                on_checkpoint(&checkpoint);
+            }
+            drop(event_tx);
+        });
+        return event_rx;
+    }
+
+    let (checkpoint_tx, checkpoint_rx) = mpsc::channel(64);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let monitor = MonitoringLoop::new(
+        checkpoint_rx,
+        event_tx,
+        shutdown_rx,
+        SloChecker::new(SloDefinition::from_terms(terms)),
+        ledger,
+        config.monitoring_config(terms),
+    );
+    let monitor = if heartbeats {
+        monitor.watch_agent(agent_id)
+    } else {
+        monitor.without_heartbeats()
+    };
+    tokio::spawn(monitor.run());
+
+    let started = Utc::now();
+    tokio::spawn(async move {
+        while let Some(mut checkpoint) = checkpoints.recv().await {
+            on_checkpoint(&checkpoint);
+            let elapsed_ms = (checkpoint.timestamp - started).num_milliseconds().max(0);
+            match &mut checkpoint.metadata {
+                serde_json::Value::Object(metadata) => {
+                    metadata.insert("elapsed_ms".to_string(), elapsed_ms.into());
+                }
+                serde_json::Value::Null => {
+                    checkpoint.metadata = serde_json::json!({ "elapsed_ms": elapsed_ms });
+                }
+                _ => {}
+            }
+            if checkpoint_tx.send(checkpoint).await.is_err() {
+                break;
+            }
+        }
+        let _ = shutdown_tx.send(true);
+    });
+    event_rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::{InMemoryLedger, LedgerEntryKind};
+
+    #[tokio::test]
+    async fn test_watch_execution_checks_contract_terms() {
+        let terms = MonitoringTerms {
+            checkpoint_interval_secs: 60,
+            max_latency_ms: 0,
+            min_quality_score: 0.0,
+            max_resource_budget: 100.0,
+        };
+        let ledger = Arc::new(InMemoryLedger::new());
+        let (task_id, agent_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let (tx, rx) = mpsc::channel(8);
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let on_checkpoint = {
+            let seen = seen.clone();
+            move |cp: &Checkpoint| seen.lock().unwrap().push(cp.status_message.clone())
+        };
+        let mut events = watch_execution(
+            agent_id,
+            &terms,
+            &WatchConfig::default(),
+            true,
+            rx,
+            on_checkpoint,
+            ledger.clone(),
+        );
+
+        for (status, consumed) in [("Started", 0.0), ("Finished", 150.0)] {
+            let cp = Checkpoint::new(task_id, agent_id)
+                .with_status(status)
+                .with_resource_consumed(consumed);
+            tx.send(cp).await.unwrap();
+        }
+        drop(tx);
+
+        let mut received = Vec::new();
+        while let Some(event) = events.recv().await {
+            received.push(event);
+        }
+        assert_eq!(received.len(), 3);
+        assert!(matches!(
+            &received[2],
+            MonitoringEvent::SloViolation { violation, .. }
+                if violation.definition.metric_name == "resource_consumed"
+        ));
+        assert_eq!(*seen.lock().unwrap(), vec!["Started", "Finished"]);
+
+        let recorded = ledger
+            .query_by_kind(LedgerEntryKind::CheckpointRecorded)
+            .await
+            .unwrap();
+        assert_eq!(recorded.len(), 2);
+        assert!(recorded[0].payload["metadata"]["elapsed_ms"].is_number());
+    }
+
+    #[tokio::test]
+    async fn test_silence_is_only_reported_when_heartbeats_are_expected() {
+        let terms = MonitoringTerms {
+            checkpoint_interval_secs: 1,
+            max_latency_ms: 0,
+            min_quality_score: 0.0,
+            max_resource_budget: 100.0,
+        };
+        let config = WatchConfig {
+            enabled: true,
+            missed_checkpoints: 1,
+        };
+        let ledger = Arc::new(InMemoryLedger::new());
+        let agent_id = Uuid::new_v4();
+        let (streaming_tx, streaming_rx) = mpsc::channel(8);
+        let (silent_tx, silent_rx) = mpsc::channel(8);
+        let mut streaming = watch_execution(
+            agent_id,
+            &terms,
+            &config,
+            true,
+            streaming_rx,
+            |_: &Checkpoint| {},
+            ledger.clone(),
+        );
+        let mut silent = watch_execution(
+            agent_id,
+            &terms,
+            &config,
+            false,
+            silent_rx,
+            |_: &Checkpoint| {},
+            ledger,
+        );
+
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        drop((streaming_tx, silent_tx));
+
+        let mut streaming_events = Vec::new();
+        while let Some(event) = streaming.recv().await {
+            streaming_events.push(event);
+        }
+        assert!(
+            streaming_events
+                .iter()
+                .any(|e| matches!(e, MonitoringEvent::AgentUnresponsive { .. }))
+        );
+        assert!(silent.recv().await.is_none());
+    }
+}
@@ -8,7 +8,7 @@ use uuid::Uuid;
 
 use crate::ledger::LedgerEntry;
 use crate::reputation::AgentReputation;
-use crate::types::{Agent, Task};
+use crate::types::{Agent, DelegationContract, Task};
 
 /// The top-level persisted state.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -17,4 +17,19 @@ pub struct PersistedState {
     pub agents: HashMap<Uuid, Agent>,
     pub ledger_entries: Vec<LedgerEntry>,
     pub reputation_scores: HashMap<Uuid, AgentReputation>,
+    #[serde(default)]
+    pub contracts: HashMap<Uuid, DelegationContract>,
+}
+
+/// One change appended to the journal between two snapshots of the state. Each record
+/// carries the whole object as it was after the change, so replaying the journal in order
+/// over the last snapshot gives the latest state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalRecord {
+    Task(Box<Task>),
+    TaskRemoved(Uuid),
+    Agent(Agent),
+    Contract(DelegationContract),
+    Ledger(LedgerEntry),
 }
@@ -1,17 +1,23 @@
 use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use super::PersistedState;
+use super::{JournalRecord, PersistedState};
 
-/// File-based state store with atomic writes.
+/// File-based state store: a snapshot written atomically, plus a journal of the changes
+/// made since, appended as they happen and folded into the next snapshot.
+#[derive(Debug, Clone)]
 pub struct FileStore {
     path: PathBuf,
+    journal_path: PathBuf,
 }
 
 impl FileStore {
     pub fn new(state_dir: &Path) -> Self {
         Self {
             path: state_dir.join("state.json"),
+            journal_path: state_dir.join("state.journal"),
         }
     }
 
@@ -26,19 +32,70 @@ impl FileStore {
         }
     }
 
-    /// Load state from disk. Returns default state if file doesn't exist.
+    /// Load state from disk: the snapshot, with the journal replayed over it. Returns default
+    /// state if neither exists.
     pub fn load(&self) -> Result<PersistedState> {
-        if !self.path.exists() {
-            return Ok(PersistedState::default());
-        }
-        let content =
-            std::fs::read_to_string(&self.path).context("Failed to read state file")?;
-        let state: PersistedState =
-            serde_json::from_str(&content).context("Failed to parse state file")?;
+        let mut state = if self.path.exists() {
+            let content =
+                std::fs::read_to_string(&self.path).context("Failed to read state file")?;
+            serde_json::from_str(&content).context("Failed to parse state file")?
+        } else {
+            PersistedState::default()
+        };
+        self.replay(&mut state)?;
         Ok(state)
     }
 
-    /// Save state to disk using atomic write (.tmp → rename).
+    /// Apply the journal to a loaded snapshot. Ledger entries the snapshot already holds are
+    /// skipped. A final record without its newline was cut short by a crash and is dropped;
+    /// any other record that does not parse fails the load, naming its line, rather than
+    /// silently losing the changes after it.
+    fn replay(&self, state: &mut PersistedState) -> Result<()> {
+        let content = match std::fs::read_to_string(&self.journal_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e).context("Failed to read state journal"),
+        };
+        let mut known: HashSet<_> = state.ledger_entries.iter().map(|e| e.id).collect();
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            let record = match serde_json::from_str::<JournalRecord>(line) {
+                Ok(record) => record,
+                Err(_) if !line.ends_with('\n') => break,
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Corrupt record on line {} of {}",
+                            index + 1,
+                            self.journal_path.display()
+                        )
+                    });
+                }
+            };
+            match record {
+                JournalRecord::Task(task) => {
+                    state.tasks.insert(task.id, *task);
+                }
+                JournalRecord::TaskRemoved(id) => {
+                    state.tasks.remove(&id);
+                }
+                JournalRecord::Agent(agent) => {
+                    state.agents.insert(agent.id, agent);
+                }
+                JournalRecord::Contract(contract) => {
+                    state.contracts.insert(contract.id, contract);
+                }
+                JournalRecord::Ledger(entry) => {
+                    if known.insert(entry.id) {
+                        state.ledger_entries.push(entry);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Save state to disk using atomic write (.tmp → rename). The snapshot holds everything
+    /// the journal did, so the journal is started afresh.
     pub fn save(&self, state: &PersistedState) -> Result<()> {
         if let Some(parent) = self.path.parent() {
             std::fs::create_dir_all(parent).context("Failed to create state directory")?;
@@ -48,19 +105,90 @@ impl FileStore {
             serde_json::to_string_pretty(state).context("Failed to serialize state")?;
         std::fs::write(&tmp_path, content).context("Failed to write temp state file")?;
         std::fs::rename(&tmp_path, &self.path).context("Failed to rename temp state file")?;
-        Ok(())
+        match std::fs::remove_file(&self.journal_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context("Failed to clear state journal")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Append changes to the journal, one JSON record per line.
+    pub fn append(&self, records: &[JournalRecord]) -> Result<()> {
+        if let Some(parent) = self.journal_path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        let mut content = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut content, record).context("Failed to serialize change")?;
+            content.push(b'\n');
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.journal_path)
+            .and_then(|mut file| file.write_all(&content))
+            .context("Failed to append to state journal")
     }
 
     pub fn path(&self) -> &Path {
         &self.path
     }
+
+    /// Take the state directory for this process: an exclusive lock on `panopticon.lock` in
+    /// it, holding the pid. Fails, naming the holder, while another live process holds it;
+    /// the lock is released when the returned guard is dropped or the process dies.
+    pub fn lock(&self) -> Result<StateLock> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir).context("Failed to create state directory")?;
+        StateLock::acquire(&dir.join("panopticon.lock"))
+    }
+}
+
+/// Exclusive hold on a state directory (see `FileStore::lock`). While it is held, tasks in
+/// flight in the state belong to this process; once its holder is gone, they are orphans.
+#[derive(Debug)]
+pub struct StateLock {
+    _file: std::fs::File,
+}
+
+impl StateLock {
+    fn acquire(path: &Path) -> Result<Self> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+            // SAFETY: locks a descriptor we own; the lock goes with it.
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+                let holder = std::fs::read_to_string(path).unwrap_or_default();
+                anyhow::bail!(
+                    "{} is in use by another session (pid {}); set PANOPTICON_STATE_DIR to use \
+                     another state directory",
+                    path.parent().unwrap_or(path).display(),
+                    holder.trim()
+                );
+            }
+        }
+        file.set_len(0)
+            .and_then(|()| write!(file, "{}", std::process::id()))
+            .context("Failed to write the state lock")?;
+        Ok(Self { _file: file })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ledger::{LedgerEntry, LedgerEntryKind};
     use crate::types::Task;
     use tempfile::tempdir;
+    use uuid::Uuid;
 
     #[test]
     fn test_load_nonexistent_returns_default() {
@@ -85,4 +213,100 @@ mod tests {
         assert_eq!(loaded.tasks.len(), 1);
         assert_eq!(loaded.tasks[&task.id].name, "test-task");
     }
+
+    #[test]
+    fn test_journal_replays_over_snapshot() {
+        let dir = tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+
+        let mut state = PersistedState::default();
+        let mut task = Task::new("test-task", "a description");
+        let removed = Task::new("removed", "r");
+        state.tasks.insert(task.id, task.clone());
+        state.tasks.insert(removed.id, removed.clone());
+        let entry = LedgerEntry::new(
+            LedgerEntryKind::TaskCreated,
+            Uuid::nil(),
+            task.id,
+            serde_json::json!({}),
+            None,
+        );
+        state.ledger_entries.push(entry.clone());
+        store.save(&state).unwrap();
+
+        task.name = "renamed".to_string();
+        let later = LedgerEntry::new(
+            LedgerEntryKind::TaskUpdated,
+            Uuid::nil(),
+            task.id,
+            serde_json::json!({}),
+            Some(entry.hash.clone()),
+        );
+        store
+            .append(&[
+                JournalRecord::Task(Box::new(task.clone())),
+                JournalRecord::TaskRemoved(removed.id),
+                // Already in the snapshot: not duplicated.
+                JournalRecord::Ledger(entry),
+                JournalRecord::Ledger(later.clone()),
+            ])
+            .unwrap();
+        // A record cut short by a crash ends the replay.
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("state.journal"))
+            .unwrap()
+            .write_all(b"{\"task\": {\"id\"")
+            .unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.tasks.len(), 1);
+        assert_eq!(loaded.tasks[&task.id].name, "renamed");
+        let ids: Vec<_> = loaded.ledger_entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![state.ledger_entries[0].id, later.id]);
+
+        // A new snapshot absorbs the journal.
+        store.save(&loaded).unwrap();
+        assert!(!dir.path().join("state.journal").exists());
+        assert_eq!(store.load().unwrap().ledger_entries.len(), 2);
+    }
+
+    #[test]
+    fn test_corrupt_journal_record_fails_the_load() {
+        let dir = tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+        let first = Task::new("first", "f");
+        store
+            .append(&[JournalRecord::Task(Box::new(first))])
+            .unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("state.journal"))
+            .unwrap()
+            .write_all(b"{\"task\": {\"id\"\n")
+            .unwrap();
+        store
+            .append(&[JournalRecord::Task(Box::new(Task::new("third", "t")))])
+            .unwrap();
+
+        let err = format!("{:#}", store.load().unwrap_err());
+        assert!(err.contains("Corrupt record on line 2"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lock_is_exclusive_until_released() {
+        let dir = tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+
+        let lock = store.lock().unwrap();
+        let pid = std::process::id().to_string();
+        let err = store.lock().unwrap_err().to_string();
+        assert!(err.contains(&format!("pid {pid}")), "{err}");
+
+        drop(lock);
+        let _lock = store.lock().unwrap();
+        let holder = std::fs::read_to_string(dir.path().join("panopticon.lock")).unwrap();
+        assert_eq!(holder, pid);
+    }
 }
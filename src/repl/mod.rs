@@ -7,6 +7,7 @@ use anyhow::Result;
 use rustyline::DefaultEditor;
 use std::path::PathBuf;
 
+use crate::cli::commands;
 use crate::cli::state::AppState;
 use crate::persistence::FileStore;
 use crate::repl::session::Session;
@@ -20,15 +21,23 @@ pub async fn run() -> Result<()> {
     };
 
     let store = FileStore::new(&state_dir);
+    // Held for the whole session: tasks in flight in the state are this session's until it
+    // ends, and only orphans once it is gone.
+    let _lock = store.lock()?;
     let state = AppState::load_from(&store).await?;
 
-    // Load config to get max_context_messages.
-    let config = crate::config::PanopticonConfig::load(&state_dir).unwrap_or_default();
-
-    let mut session = Session::new(config.max_context_messages as usize);
+    let mut session = Session::new(state.config.max_context_messages as usize);
 
     output::print_welcome();
 
+    // With the lock held, no other session runs: tasks a killed run left in flight are dealt
+    // with before anything else runs.
+    state.begin_command("/recover");
+    if let Err(e) = commands::recover::handle(&state).await {
+        output::print_error(&format!("Recovery failed: {e:#}"));
+    }
+    state.save_to(&store).await?;
+
     // Set up rustyline editor — use spawn_blocking to coexist with tokio.
     let mut rl = DefaultEditor::new()?;
 
@@ -57,6 +66,8 @@ pub async fn run() -> Result<()> {
                 // Add to history on the main editor (best-effort).
                 let _ = rl.add_history_entry(trimmed);
 
+                // Every change the command makes is recorded as triggered by it.
+                state.begin_command(trimmed);
                 let result = if trimmed.starts_with('/') {
                     // Slash command — dispatch directly.
                     match slash::dispatch(trimmed, &state, &state_dir).await {
@@ -53,6 +53,18 @@ pub fn print_help() {
         "  {}  Execute tasks (by ID or --all)",
         "/execute [id|--all]".cyan()
     );
+    println!(
+        "    {}  fail_fast, skip_dependents, continue_independent, retry_then_redelegate",
+        "--on-failure <policy>".cyan()
+    );
+    println!(
+        "    {}              Forecast assignees, approvals, cost, and the critical path",
+        "--dry-run".cyan()
+    );
+    println!(
+        "  {}          Resume, retry, or fail tasks an interrupted run left in flight",
+        "/recover".cyan()
+    );
     println!("  {}           Show task/agent dashboard", "/status".cyan());
     println!("  {}       Task management subcommands", "/task <sub>".cyan());
     println!("  {}      Agent management subcommands", "/agent <sub>".cyan());
@@ -60,6 +72,10 @@ pub fn print_help() {
         "  {}     Configuration management",
         "/config <sub>".cyan()
     );
+    println!(
+        "  {}   Prompt templates (list, show, init)",
+        "/template <sub>".cyan()
+    );
     println!("  {}             Run a demo delegation lifecycle", "/demo".cyan());
     println!("  {}             Show this help", "/help".cyan());
     println!("  {}             Exit the REPL", "/quit".cyan());
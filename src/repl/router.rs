@@ -1,8 +1,9 @@
 use anyhow::Result;
 use colored::Colorize;
 
+use crate::cli::commands::execute::ExecuteOptions;
 use crate::cli::state::AppState;
-use crate::executor::{AgentExecutor, ClaudeExecutor, ExecutionContext};
+use crate::executor::{self, ExecutionContext, TemplateStore};
 use crate::repl::session::Session;
 use crate::types::Task;
 
@@ -17,25 +18,10 @@ pub async fn route_natural_language(
 
     let context_history = session.format_for_claude();
 
-    let system_prompt = format!(
-        "You are Panopticon's intent router. Given the user's message and conversation history, \
-         classify the intent and respond with a JSON object.\n\n\
-         Available actions:\n\
-         - {{\"action\": \"plan\", \"goal\": \"<goal description>\"}}\n\
-         - {{\"action\": \"execute\", \"all\": true}}\n\
-         - {{\"action\": \"execute\", \"id\": \"<task-uuid>\"}}\n\
-         - {{\"action\": \"status\"}}\n\
-         - {{\"action\": \"task_list\"}}\n\
-         - {{\"action\": \"agent_list\"}}\n\
-         - {{\"action\": \"help\"}}\n\
-         - {{\"action\": \"conversation\", \"response\": \"<your helpful response>\"}}\n\n\
-         If the user is asking you to plan, decompose, or analyze something into tasks, use \"plan\".\n\
-         If the user wants to run/execute tasks, use \"execute\".\n\
-         If the user is asking about current state, use \"status\".\n\
-         If none of the above match, use \"conversation\" and provide a helpful response.\n\n\
-         {context_history}\n\
-         Respond with ONLY the JSON object, no markdown, no explanation."
-    );
+    let system_prompt = TemplateStore::new(state.state_dir.as_deref()).render(
+        &state.config.templates.router,
+        &serde_json::json!({ "history": context_history, "input": input }),
+    )?;
 
     let task = Task::new(
         "intent_classification",
@@ -47,17 +33,20 @@ pub async fn route_natural_language(
         ..Default::default()
     };
 
-    let executor = ClaudeExecutor::default().with_model("haiku");
+    let executor = executor::from_config(&state.config, "haiku", state.state_dir.as_deref())?;
 
     println!("{}", "Thinking...".dimmed());
 
     match executor.health_check().await {
         Ok(true) => {}
         _ => {
-            // Claude CLI not available — provide a helpful fallback.
-            let msg = "Claude CLI is not available. Use slash commands instead (/help for list).";
+            // Executor backend not available — provide a helpful fallback.
+            let msg = format!(
+                "{} is not available. Use slash commands instead (/help for list).",
+                executor.name()
+            );
             println!("{}", msg.yellow());
-            session.push_assistant(msg);
+            session.push_assistant(&msg);
             return Ok(());
         }
     }
@@ -94,23 +83,39 @@ pub async fn route_natural_language(
                         .get("id")
                         .and_then(|v| v.as_str())
                         .and_then(|s| s.parse().ok());
+                    let options = ExecuteOptions {
+                        dry_run: result
+                            .output
+                            .get("dry_run")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false),
+                        ..Default::default()
+                    };
+                    let verb = if options.dry_run { "Forecasting" } else { "Executing" };
 
                     match id {
                         Some(task_id) if !all => {
                             session
-                                .push_assistant(&format!("Executing task {task_id}..."));
+                                .push_assistant(&format!("{verb} task {task_id}..."));
                             crate::cli::commands::execute::handle(
                                 Some(task_id),
                                 false,
                                 "sonnet",
+                                &options,
                                 state,
                             )
                             .await?;
                         }
                         _ => {
-                            session.push_assistant("Executing all pending tasks...");
-                            crate::cli::commands::execute::handle(None, true, "sonnet", state)
-                                .await?;
+                            session.push_assistant(&format!("{verb} all pending tasks..."));
+                            crate::cli::commands::execute::handle(
+                                None,
+                                true,
+                                "sonnet",
+                                &options,
+                                state,
+                            )
+                            .await?;
                         }
                     }
                 }
@@ -3,6 +3,7 @@ use std::path::Path;
 use uuid::Uuid;
 
 use crate::cli::commands;
+use crate::cli::commands::execute::ExecuteOptions;
 use crate::cli::state::AppState;
 use crate::repl::output;
 
@@ -47,14 +48,12 @@ pub async fn dispatch(input: &str, state: &AppState, state_dir: &Path) -> Result
         }
 
         "/execute" | "/exec" => {
-            if args_str.is_empty() || args_str == "--all" {
-                commands::execute::handle(None, true, "sonnet", state).await?;
-            } else {
-                let id: Uuid = args_str
-                    .parse()
-                    .map_err(|_| anyhow::anyhow!("Invalid UUID: {args_str}"))?;
-                commands::execute::handle(Some(id), false, "sonnet", state).await?;
-            }
+            let (id, options) = parse_execute_args(args_str)?;
+            commands::execute::handle(id, id.is_none(), "sonnet", &options, state).await?;
+        }
+
+        "/recover" => {
+            commands::recover::handle(state).await?;
         }
 
         "/task" => {
@@ -69,6 +68,10 @@ pub async fn dispatch(input: &str, state: &AppState, state_dir: &Path) -> Result
             dispatch_config(args_str, state_dir).await?;
         }
 
+        "/template" => {
+            dispatch_template(args_str, state).await?;
+        }
+
         other => {
             output::print_error(&format!("Unknown command: {other}. Type /help for a list."));
         }
@@ -77,6 +80,68 @@ pub async fn dispatch(input: &str, state: &AppState, state_dir: &Path) -> Result
     Ok(SlashResult::Continue)
 }
 
+/// Parse the arguments of `/task priority <uuid> <0.0-1.0>`.
+pub(crate) fn parse_priority_args(args: &str) -> Result<(Uuid, f64)> {
+    let usage = "Usage: /task priority <uuid> <0.0-1.0>";
+    let mut parts = args.split_whitespace();
+    let id: Uuid = parts
+        .next()
+        .unwrap_or("")
+        .parse()
+        .map_err(|_| anyhow::anyhow!(usage))?;
+    let priority: f64 = parts
+        .next()
+        .and_then(|p| p.parse().ok())
+        .filter(|p| (0.0..=1.0).contains(p))
+        .ok_or_else(|| anyhow::anyhow!(usage))?;
+    Ok((id, priority))
+}
+
+/// Parse `/execute [id|--all] [--on-failure <policy>] [--dry-run]`; no id means all pending tasks.
+fn parse_execute_args(args: &str) -> Result<(Option<Uuid>, ExecuteOptions)> {
+    let mut id = None;
+    let mut options = ExecuteOptions::default();
+    let mut words = args.split_whitespace();
+    while let Some(word) = words.next() {
+        match word {
+            "--all" => id = None,
+            "--dry-run" => options.dry_run = true,
+            "--on-failure" => {
+                let policy = words.next().ok_or_else(|| {
+                    anyhow::anyhow!("Usage: /execute [id|--all] [--on-failure <policy>] [--dry-run]")
+                })?;
+                options.on_failure = Some(policy.parse().map_err(|e: String| anyhow::anyhow!(e))?);
+            }
+            other => {
+                id = Some(
+                    other
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid UUID: {other}"))?,
+                );
+            }
+        }
+    }
+    Ok((id, options))
+}
+
+/// Parse a deadline: an RFC 3339 timestamp, or a time from now such as `+30m` (`s`, `m`, `h`,
+/// or `d`).
+fn parse_deadline(text: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let Some(offset) = text.strip_prefix('+') else {
+        return text.parse().ok();
+    };
+    let (amount, unit) = offset.split_at(offset.char_indices().last()?.0);
+    let amount: i64 = amount.parse().ok()?;
+    let offset = match unit {
+        "s" => chrono::Duration::try_seconds(amount)?,
+        "m" => chrono::Duration::try_minutes(amount)?,
+        "h" => chrono::Duration::try_hours(amount)?,
+        "d" => chrono::Duration::try_days(amount)?,
+        _ => return None,
+    };
+    chrono::Utc::now().checked_add_signed(offset)
+}
+
 /// Dispatch `/task` subcommands.
 async fn dispatch_task(args: &str, state: &AppState) -> Result<()> {
     let mut parts = args.splitn(2, char::is_whitespace);
@@ -95,6 +160,84 @@ async fn dispatch_task(args: &str, state: &AppState) -> Result<()> {
             use crate::cli::TaskAction;
             commands::task::handle(TaskAction::Get { id }, state).await?;
         }
+        "schema" => {
+            let usage = "Usage: /task schema <uuid> <json | @file | none>";
+            let mut parts = rest.splitn(2, char::is_whitespace);
+            let id: Uuid = parts
+                .next()
+                .unwrap_or("")
+                .parse()
+                .map_err(|_| anyhow::anyhow!(usage))?;
+            let schema = match parts.next().unwrap_or("").trim() {
+                "" => bail!(usage),
+                "none" => None,
+                text => {
+                    let text = match text.strip_prefix('@') {
+                        Some(path) => std::fs::read_to_string(path)
+                            .map_err(|e| anyhow::anyhow!("Failed to read {path}: {e}"))?,
+                        None => text.to_string(),
+                    };
+                    let schema: serde_json::Value = serde_json::from_str(&text)
+                        .map_err(|e| anyhow::anyhow!("Invalid JSON schema: {e}"))?;
+                    if !schema.is_object() {
+                        bail!("A JSON schema must be an object");
+                    }
+                    Some(schema)
+                }
+            };
+            use crate::cli::TaskAction;
+            commands::task::handle(TaskAction::Schema { id, schema }, state).await?;
+        }
+        "priority" => {
+            let (id, priority) = parse_priority_args(rest)?;
+            use crate::cli::TaskAction;
+            commands::task::handle(TaskAction::Priority { id, priority }, state).await?;
+        }
+        "deadline" => {
+            let usage = "Usage: /task deadline <uuid> <RFC 3339 time | +<n>s/m/h/d | none>";
+            let mut parts = rest.split_whitespace();
+            let id: Uuid = parts
+                .next()
+                .unwrap_or("")
+                .parse()
+                .map_err(|_| anyhow::anyhow!(usage))?;
+            let deadline = match parts.next() {
+                None => bail!(usage),
+                Some("none") => None,
+                Some(when) => Some(parse_deadline(when).ok_or_else(|| anyhow::anyhow!(usage))?),
+            };
+            use crate::cli::TaskAction;
+            commands::task::handle(TaskAction::Deadline { id, deadline }, state).await?;
+        }
+        "decompose" => {
+            let usage = "Usage: /task decompose <uuid> [sequential|parallel|hybrid]";
+            let mut parts = rest.split_whitespace();
+            let id: Uuid = parts
+                .next()
+                .unwrap_or("")
+                .parse()
+                .map_err(|_| anyhow::anyhow!(usage))?;
+            let strategy = parts
+                .next()
+                .map(str::to_string)
+                .unwrap_or_else(|| state.config.decomposition_strategy.clone());
+            use crate::cli::TaskAction;
+            commands::task::handle(TaskAction::Decompose { id, strategy }, state).await?;
+        }
+        "followup" => {
+            let usage = "Usage: /task followup <uuid> <message>";
+            let mut parts = rest.splitn(2, char::is_whitespace);
+            let id: Uuid = parts
+                .next()
+                .unwrap_or("")
+                .parse()
+                .map_err(|_| anyhow::anyhow!(usage))?;
+            let message = parts.next().unwrap_or("").trim();
+            if message.is_empty() {
+                bail!(usage);
+            }
+            commands::execute::followup(id, message.to_string(), "sonnet", state).await?;
+        }
         "create" => {
             bail!(
                 "Use /plan <goal> to create tasks via Claude, or:\n  \
@@ -102,7 +245,7 @@ async fn dispatch_task(args: &str, state: &AppState) -> Result<()> {
             );
         }
         other => {
-            bail!("Unknown task subcommand: {other}\nAvailable: list, get, create");
+            bail!("Unknown task subcommand: {other}\nAvailable: list, get, schema, priority, deadline, decompose, followup, create");
         }
     }
     Ok(())
@@ -140,6 +283,31 @@ async fn dispatch_agent(args: &str, state: &AppState) -> Result<()> {
     Ok(())
 }
 
+/// Dispatch `/template` subcommands.
+async fn dispatch_template(args: &str, state: &AppState) -> Result<()> {
+    let mut parts = args.splitn(2, char::is_whitespace);
+    let sub = parts.next().unwrap_or("");
+    let rest = parts.next().unwrap_or("").trim();
+
+    use crate::cli::TemplateAction;
+    let action = match sub {
+        "list" | "ls" | "" => TemplateAction::List,
+        "show" => {
+            if rest.is_empty() {
+                bail!("Usage: /template show <name>");
+            }
+            TemplateAction::Show {
+                name: rest.to_string(),
+            }
+        }
+        "init" => TemplateAction::Init,
+        other => {
+            bail!("Unknown template subcommand: {other}\nAvailable: list, show, init");
+        }
+    };
+    commands::template::handle(action, state).await
+}
+
 /// Dispatch `/config` subcommands.
 async fn dispatch_config(args: &str, state_dir: &Path) -> Result<()> {
     let sub = args.split_whitespace().next().unwrap_or("");
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use dashmap::DashMap;
 use uuid::Uuid;
 
-use crate::ledger::{Ledger, LedgerEntry, LedgerEntryKind};
+use crate::ledger::{Ledger, LedgerEntryKind};
 use crate::types::{PanopticonError, ReputationScore, TrustLevel};
 
 use super::score::{AgentReputation, ReputationObservation};
@@ -58,28 +58,19 @@ impl ReputationEngine {
         };
 
         // Record the update on the ledger.
-        let previous_hash = self
-            .ledger
-            .latest_hash()
-            .await
-            .map_err(|e| PanopticonError::LedgerError(e.to_string()))?;
-
         let payload = serde_json::json!({
             "dimension": observation.dimension,
             "observed_value": observation.value,
             "new_score": new_score,
         });
 
-        let entry = LedgerEntry::new(
-            LedgerEntryKind::ReputationUpdated,
-            agent_id,
-            observation.task_id,
-            payload,
-            previous_hash,
-        );
-
         self.ledger
-            .append(entry)
+            .append_chained(
+                LedgerEntryKind::ReputationUpdated,
+                agent_id,
+                observation.task_id,
+                payload,
+            )
             .await
             .map_err(|e| PanopticonError::LedgerError(e.to_string()))?;
 
@@ -157,6 +148,20 @@ mod tests {
             Ok(())
         }
 
+        async fn append_chained(
+            &self,
+            kind: LedgerEntryKind,
+            actor_id: Uuid,
+            subject_id: Uuid,
+            payload: serde_json::Value,
+        ) -> Result<LedgerEntry, PanopticonError> {
+            let mut entries = self.entries.lock().unwrap();
+            let previous_hash = entries.last().map(|e| e.hash.clone());
+            let entry = LedgerEntry::new(kind, actor_id, subject_id, payload, previous_hash);
+            entries.push(entry.clone());
+            Ok(entry)
+        }
+
         async fn get(&self, id: Uuid) -> Result<Option<LedgerEntry>, PanopticonError> {
             Ok(self
                 .entries
@@ -298,6 +303,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::explicit_counter_loop)]
     async fn test_composite_score_calculation() {
         let ledger = Arc::new(FakeLedger::new());
         let engine = ReputationEngine::new(ledger);
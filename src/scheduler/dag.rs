@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a subtask is in a scheduled run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeStatus {
+    /// Not started; runs once all of its prerequisites have completed.
+    Waiting,
+    Running,
+    Completed,
+    Failed,
+    /// Not run because a prerequisite failed (or the run was stopped).
+    Skipped,
+}
+
+/// The dependency graph of a parent task's subtasks, indexed by position in `subtask_ids`,
+/// tracking which subtasks are ready to run.
+#[derive(Debug, Clone)]
+pub struct TaskDag {
+    prerequisites: Vec<Vec<usize>>,
+    status: Vec<NodeStatus>,
+}
+
+impl TaskDag {
+    /// Build a graph of `len` nodes from `(from, to)` edges, where `to` depends on `from`.
+    /// Edges that reference nodes out of range, and self-loops, are ignored.
+    pub fn new(len: usize, edges: &[(usize, usize)]) -> Self {
+        let mut prerequisites = vec![Vec::new(); len];
+        for &(from, to) in edges {
+            if from < len && to < len && from != to && !prerequisites[to].contains(&from) {
+                prerequisites[to].push(from);
+            }
+        }
+        Self {
+            prerequisites,
+            status: vec![NodeStatus::Waiting; len],
+        }
+    }
+
+    /// Build the graph from a parent task's subtask count and `metadata.dependencies`.
+    pub fn from_metadata(len: usize, metadata: &serde_json::Value) -> Self {
+        Self::new(len, &dependencies_from_metadata(metadata))
+    }
+
+    pub fn len(&self) -> usize {
+        self.status.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.status.is_empty()
+    }
+
+    /// Direct prerequisites of a node.
+    pub fn prerequisites(&self, idx: usize) -> &[usize] {
+        &self.prerequisites[idx]
+    }
+
+    pub fn status(&self, idx: usize) -> NodeStatus {
+        self.status[idx]
+    }
+
+    /// Waiting nodes whose prerequisites have all completed, in index order.
+    pub fn ready(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| self.status[i] == NodeStatus::Waiting)
+            .filter(|&i| {
+                self.prerequisites[i]
+                    .iter()
+                    .all(|&p| self.status[p] == NodeStatus::Completed)
+            })
+            .collect()
+    }
+
+    pub fn start(&mut self, idx: usize) {
+        self.status[idx] = NodeStatus::Running;
+    }
+
+    pub fn complete(&mut self, idx: usize) {
+        self.status[idx] = NodeStatus::Completed;
+    }
+
+    pub fn fail(&mut self, idx: usize) {
+        self.status[idx] = NodeStatus::Failed;
+    }
+
+    /// Put a finished node back in line to run again.
+    pub fn requeue(&mut self, idx: usize) {
+        self.status[idx] = NodeStatus::Waiting;
+    }
+
+    /// Nodes that depend on `idx`, directly or transitively, in index order.
+    pub fn dependents(&self, idx: usize) -> Vec<usize> {
+        let mut found = vec![false; self.len()];
+        let mut stack = vec![idx];
+        while let Some(node) = stack.pop() {
+            for (next, prerequisites) in self.prerequisites.iter().enumerate() {
+                if !found[next] && prerequisites.contains(&node) {
+                    found[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        (0..self.len()).filter(|&i| found[i] && i != idx).collect()
+    }
+
+    /// Mark the waiting dependents of `idx` as skipped and return them.
+    pub fn skip_dependents(&mut self, idx: usize) -> Vec<usize> {
+        let skipped: Vec<usize> = self
+            .dependents(idx)
+            .into_iter()
+            .filter(|&i| self.status[i] == NodeStatus::Waiting)
+            .collect();
+        for &i in &skipped {
+            self.status[i] = NodeStatus::Skipped;
+        }
+        skipped
+    }
+
+    /// Mark every waiting node as skipped and return them.
+    pub fn skip_waiting(&mut self) -> Vec<usize> {
+        let skipped: Vec<usize> = (0..self.len())
+            .filter(|&i| self.status[i] == NodeStatus::Waiting)
+            .collect();
+        for &i in &skipped {
+            self.status[i] = NodeStatus::Skipped;
+        }
+        skipped
+    }
+
+    /// Number of nodes with the given status.
+    pub fn count(&self, status: NodeStatus) -> usize {
+        self.status.iter().filter(|&&s| s == status).count()
+    }
+
+    /// Waiting nodes that can never become ready because they sit on (or behind) a
+    /// dependency cycle. Only meaningful once nothing is running and the dependents of
+    /// every failed node have been skipped.
+    pub fn stuck(&self) -> Vec<usize> {
+        if self.count(NodeStatus::Running) > 0 {
+            return Vec::new();
+        }
+        let ready = self.ready();
+        (0..self.len())
+            .filter(|&i| self.status[i] == NodeStatus::Waiting && !ready.contains(&i))
+            .collect()
+    }
+}
+
+/// Parse `[[from, to], ...]` dependency pairs (subtask indices) from a parent's metadata.
+pub fn dependencies_from_metadata(metadata: &serde_json::Value) -> Vec<(usize, usize)> {
+    metadata
+        .get("dependencies")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|d| {
+                    let a = d.as_array()?;
+                    Some((a.first()?.as_u64()? as usize, a.get(1)?.as_u64()? as usize))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// How many of a parent's subtasks may run at once: `max_parallel` (at least 1), capped by the
+/// decomposition's `parallelism_factor` (rounded up) when one is recorded, or 1 when the parent
+/// was decomposed with a `Sequential` execution order.
+pub fn concurrency_limit(metadata: &serde_json::Value, max_parallel: u32) -> usize {
+    let sequential = metadata
+        .get("execution_order")
+        .and_then(|v| v.as_str())
+        .is_some_and(|order| order == "Sequential");
+    if sequential {
+        return 1;
+    }
+    let limit = max_parallel.max(1) as usize;
+    match metadata
+        .get("parallelism_factor")
+        .and_then(|v| v.as_f64())
+        .filter(|f| f.is_finite() && *f > 0.0)
+    {
+        Some(factor) => limit.min(factor.ceil() as usize),
+        None => limit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_follows_dependencies() {
+        // 0 -> 1, 0 -> 2, {1, 2} -> 3
+        let mut dag = TaskDag::new(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        assert_eq!(dag.ready(), vec![0]);
+
+        dag.start(0);
+        assert!(dag.ready().is_empty());
+        dag.complete(0);
+        assert_eq!(dag.ready(), vec![1, 2]);
+
+        dag.start(1);
+        dag.start(2);
+        dag.complete(1);
+        assert!(dag.ready().is_empty());
+        dag.complete(2);
+        assert_eq!(dag.ready(), vec![3]);
+    }
+
+    #[test]
+    fn test_failed_prerequisite_blocks_dependents() {
+        let mut dag = TaskDag::new(3, &[(0, 1)]);
+        assert_eq!(dag.ready(), vec![0, 2]);
+        dag.start(0);
+        dag.fail(0);
+        assert_eq!(dag.ready(), vec![2]);
+        assert_eq!(dag.count(NodeStatus::Failed), 1);
+    }
+
+    #[test]
+    fn test_skip_dependents_is_transitive() {
+        // 0 -> 1 -> 2, 0 -> 3, 4 independent
+        let mut dag = TaskDag::new(5, &[(0, 1), (1, 2), (0, 3)]);
+        assert_eq!(dag.dependents(0), vec![1, 2, 3]);
+        assert_eq!(dag.dependents(1), vec![2]);
+
+        dag.start(0);
+        dag.start(4);
+        dag.fail(0);
+        assert_eq!(dag.skip_dependents(0), vec![1, 2, 3]);
+        assert!(dag.ready().is_empty());
+        dag.complete(4);
+        assert!(dag.stuck().is_empty());
+
+        dag.requeue(0);
+        assert_eq!(dag.ready(), vec![0]);
+        assert_eq!(dag.skip_waiting(), vec![0]);
+        assert_eq!(dag.count(NodeStatus::Skipped), 4);
+    }
+
+    #[test]
+    fn test_invalid_edges_are_ignored() {
+        let dag = TaskDag::new(2, &[(0, 5), (1, 1), (0, 1), (0, 1)]);
+        assert_eq!(dag.prerequisites(1), &[0]);
+        assert!(dag.prerequisites(0).is_empty());
+    }
+
+    #[test]
+    fn test_cycle_is_stuck() {
+        let mut dag = TaskDag::new(3, &[(0, 1), (1, 0)]);
+        assert_eq!(dag.ready(), vec![2]);
+        dag.start(2);
+        assert!(dag.stuck().is_empty());
+        dag.complete(2);
+        assert_eq!(dag.stuck(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_metadata_parsing_and_limits() {
+        let metadata = serde_json::json!({
+            "dependencies": [[0, 1], [1, 2], "junk"],
+            "execution_order": "Parallel",
+        });
+        assert_eq!(dependencies_from_metadata(&metadata), vec![(0, 1), (1, 2)]);
+        assert_eq!(concurrency_limit(&metadata, 4), 4);
+        assert_eq!(concurrency_limit(&metadata, 0), 1);
+        let sequential = serde_json::json!({"execution_order": "Sequential"});
+        assert_eq!(concurrency_limit(&sequential, 4), 1);
+        assert_eq!(concurrency_limit(&serde_json::Value::Null, 2), 2);
+
+        let factor = |f: serde_json::Value| {
+            let mut metadata = serde_json::json!({"execution_order": "Parallel"});
+            metadata["parallelism_factor"] = f;
+            metadata
+        };
+        assert_eq!(concurrency_limit(&factor(2.0.into()), 4), 2);
+        assert_eq!(concurrency_limit(&factor(1.5.into()), 4), 2);
+        assert_eq!(concurrency_limit(&factor(8.0.into()), 4), 4);
+        assert_eq!(concurrency_limit(&factor(0.0.into()), 4), 4);
+        assert_eq!(concurrency_limit(&factor("junk".into()), 4), 4);
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// What a task tree does when one of its subtasks fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Cancel the subtasks still running and start no others.
+    FailFast,
+    /// Mark the failed subtask's transitive dependents as skipped; everything else continues.
+    #[default]
+    SkipDependents,
+    /// Leave the failed subtask's dependents pending (blocked until it succeeds) and keep
+    /// running everything that does not depend on it.
+    ContinueIndependent,
+    /// Retry the subtask on the same agent, then hand it to another capable agent; if that
+    /// fails too, skip its dependents.
+    RetryThenRedelegate,
+}
+
+impl FailurePolicy {
+    pub const ALL: [FailurePolicy; 4] = [
+        FailurePolicy::FailFast,
+        FailurePolicy::SkipDependents,
+        FailurePolicy::ContinueIndependent,
+        FailurePolicy::RetryThenRedelegate,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailurePolicy::FailFast => "fail_fast",
+            FailurePolicy::SkipDependents => "skip_dependents",
+            FailurePolicy::ContinueIndependent => "continue_independent",
+            FailurePolicy::RetryThenRedelegate => "retry_then_redelegate",
+        }
+    }
+}
+
+impl fmt::Display for FailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FailurePolicy {
+    type Err = String;
+
+    /// Parse a policy name; dashes and underscores are interchangeable.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().replace('-', "_");
+        Self::ALL
+            .into_iter()
+            .find(|p| p.as_str() == normalized)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|p| p.as_str()).collect();
+                format!("Unknown failure policy: {s} (use {})", names.join(", "))
+            })
+    }
+}
+
+/// How task trees react to failing subtasks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailureConfig {
+    /// Policy for trees that do not set `failure_policy` in their metadata.
+    pub policy: FailurePolicy,
+    /// Retries on the same agent before `retry_then_redelegate` hands a subtask to another agent.
+    pub max_retries: u32,
+}
+
+impl Default for FailureConfig {
+    fn default() -> Self {
+        Self {
+            policy: FailurePolicy::default(),
+            max_retries: 1,
+        }
+    }
+}
+
+impl FailureConfig {
+    /// The policy for a tree: an explicit override, else the parent's
+    /// `metadata.failure_policy`, else the configured default.
+    pub fn policy_for(
+        &self,
+        metadata: &serde_json::Value,
+        explicit: Option<FailurePolicy>,
+    ) -> FailurePolicy {
+        explicit
+            .or_else(|| {
+                metadata
+                    .get("failure_policy")
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| s.parse().ok())
+            })
+            .unwrap_or(self.policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy_names() {
+        for policy in FailurePolicy::ALL {
+            assert_eq!(policy.as_str().parse::<FailurePolicy>().unwrap(), policy);
+        }
+        assert_eq!(
+            "fail-fast".parse::<FailurePolicy>().unwrap(),
+            FailurePolicy::FailFast
+        );
+        assert!(
+            "yolo"
+                .parse::<FailurePolicy>()
+                .unwrap_err()
+                .contains("skip_dependents")
+        );
+    }
+
+    #[test]
+    fn test_policy_precedence() {
+        let config = FailureConfig::default();
+        let metadata = serde_json::json!({"failure_policy": "continue-independent"});
+        assert_eq!(
+            config.policy_for(&serde_json::Value::Null, None),
+            FailurePolicy::SkipDependents
+        );
+        assert_eq!(
+            config.policy_for(&metadata, None),
+            FailurePolicy::ContinueIndependent
+        );
+        assert_eq!(
+            config.policy_for(&metadata, Some(FailurePolicy::FailFast)),
+            FailurePolicy::FailFast
+        );
+    }
+
+    #[test]
+    fn test_failure_config_toml() {
+        let config: FailureConfig = toml::from_str("policy = \"retry_then_redelegate\"").unwrap();
+        assert_eq!(config.policy, FailurePolicy::RetryThenRedelegate);
+        assert_eq!(config.max_retries, 1);
+    }
+}
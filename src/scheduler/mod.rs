@@ -0,0 +1,11 @@
+pub mod dag;
+pub mod failure;
+pub mod priority;
+pub mod recovery;
+pub mod simulation;
+
+pub use dag::*;
+pub use failure::*;
+pub use priority::*;
+pub use recovery::*;
+pub use simulation::*;
@@ -0,0 +1,121 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::types::Task;
+
+/// Order in which ready tasks are started: higher priority first, then earlier deadline
+/// (tasks without one last), then higher time sensitivity. Equally urgent tasks keep their
+/// order when sorted with this (`sort_by` is stable).
+pub fn urgency_order(a: &Task, b: &Task) -> Ordering {
+    b.priority
+        .total_cmp(&a.priority)
+        .then_with(|| match (a.deadline, b.deadline) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        })
+        .then_with(|| {
+            b.characteristics
+                .time_sensitivity
+                .total_cmp(&a.characteristics.time_sensitivity)
+        })
+}
+
+/// How a task stands against its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeadlineStatus {
+    /// The task has no deadline.
+    Unset,
+    OnTrack,
+    /// The deadline is within the warning window.
+    AtRisk,
+    Missed,
+}
+
+/// When unfinished tasks are reported at risk of missing their deadlines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeadlineConfig {
+    /// Seconds before its deadline at which an unfinished task is reported at risk.
+    pub warning_secs: u64,
+}
+
+impl Default for DeadlineConfig {
+    fn default() -> Self {
+        Self { warning_secs: 300 }
+    }
+}
+
+impl DeadlineConfig {
+    /// Where a task stands against its deadline at `now`; whether it has finished is up to
+    /// the caller.
+    pub fn status(&self, task: &Task, now: DateTime<Utc>) -> DeadlineStatus {
+        let Some(deadline) = task.deadline else {
+            return DeadlineStatus::Unset;
+        };
+        let warning = Duration::seconds(self.warning_secs.min(i64::MAX as u64) as i64);
+        if now >= deadline {
+            DeadlineStatus::Missed
+        } else if now + warning >= deadline {
+            DeadlineStatus::AtRisk
+        } else {
+            DeadlineStatus::OnTrack
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(priority: f64, deadline_mins: Option<i64>, time_sensitivity: f64) -> Task {
+        let mut task = Task::new("t", "d");
+        task.priority = priority;
+        task.deadline = deadline_mins.map(|m| Utc::now() + Duration::minutes(m));
+        task.characteristics.time_sensitivity = time_sensitivity;
+        task
+    }
+
+    #[test]
+    fn test_urgency_order() {
+        let mut tasks = [
+            task(0.5, None, 0.5),
+            task(0.5, None, 0.9),
+            task(0.5, Some(60), 0.1),
+            task(0.5, Some(10), 0.1),
+            task(0.9, None, 0.1),
+        ];
+        let ids: Vec<_> = tasks.iter().map(|t| t.id).collect();
+        tasks.sort_by(urgency_order);
+        let order: Vec<usize> = tasks
+            .iter()
+            .map(|t| ids.iter().position(|id| *id == t.id).unwrap())
+            .collect();
+        assert_eq!(order, vec![4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_deadline_status() {
+        let config = DeadlineConfig { warning_secs: 600 };
+        let now = Utc::now();
+        assert_eq!(
+            config.status(&task(0.5, None, 0.5), now),
+            DeadlineStatus::Unset
+        );
+        assert_eq!(
+            config.status(&task(0.5, Some(60), 0.5), now),
+            DeadlineStatus::OnTrack
+        );
+        assert_eq!(
+            config.status(&task(0.5, Some(5), 0.5), now),
+            DeadlineStatus::AtRisk
+        );
+        assert_eq!(
+            config.status(&task(0.5, Some(-1), 0.5), now),
+            DeadlineStatus::Missed
+        );
+    }
+}
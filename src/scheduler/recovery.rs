@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::ledger::{LedgerEntry, LedgerEntryKind};
+use crate::types::{Task, TaskState};
+
+/// What is done with a task left in flight by a run that ended without finishing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    /// Run it again on the same agent, continuing the interrupted session when there is one.
+    #[default]
+    Resume,
+    /// Run it again from scratch, on whichever agent wins it.
+    Retry,
+    /// Give up on it: the task fails and its tree reacts as to any other failure.
+    MarkFailed,
+}
+
+impl RecoveryAction {
+    pub const ALL: [RecoveryAction; 3] = [
+        RecoveryAction::Resume,
+        RecoveryAction::Retry,
+        RecoveryAction::MarkFailed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecoveryAction::Resume => "resume",
+            RecoveryAction::Retry => "retry",
+            RecoveryAction::MarkFailed => "mark_failed",
+        }
+    }
+}
+
+impl fmt::Display for RecoveryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RecoveryAction {
+    type Err = String;
+
+    /// Parse an action name; dashes and underscores are interchangeable, and `fail` is short
+    /// for `mark_failed`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase().replace('-', "_");
+        if normalized == "fail" {
+            return Ok(RecoveryAction::MarkFailed);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|a| a.as_str() == normalized)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|a| a.as_str()).collect();
+                format!("Unknown recovery action: {s} (use {})", names.join(", "))
+            })
+    }
+}
+
+/// How tasks left in flight by an interrupted run are recovered on startup.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecoveryConfig {
+    /// Action applied without asking when the session is not interactive, and offered as the
+    /// default when it is.
+    pub policy: RecoveryAction,
+}
+
+/// A task left in flight by a run that is no longer running, with what the ledger says about
+/// how far it got.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orphan {
+    pub task_id: Uuid,
+    pub name: String,
+    pub state: TaskState,
+    pub agent_id: Option<Uuid>,
+    /// When the task entered its current state.
+    pub since: DateTime<Utc>,
+    /// Execution attempts recorded for the task.
+    pub attempts: usize,
+    /// Progress and status of the last checkpoint the agent reported, if any.
+    pub last_checkpoint: Option<(f64, String)>,
+    /// The agent session the interrupted run was working in: the latest one reported in a
+    /// checkpoint, else the one stored on the task.
+    pub session_id: Option<String>,
+}
+
+/// Whether a task is somewhere between being picked up and being settled.
+fn in_flight(state: TaskState) -> bool {
+    matches!(
+        state,
+        TaskState::Decomposing
+            | TaskState::AwaitingAssignment
+            | TaskState::Negotiating
+            | TaskState::Contracted
+            | TaskState::InProgress
+            | TaskState::AwaitingVerification
+    )
+}
+
+/// Find the tasks an interrupted run left in flight. Call it only while holding the state
+/// directory's lock (`FileStore::lock`), so that no other live process runs them: then every
+/// leaf task in flight is an orphan, as is every parent in flight whose subtasks
+/// are all settled (interrupted while its results were being aggregated). Parents with
+/// subtasks still in flight are not: their state follows from their subtasks'.
+///
+/// Orphans are returned oldest first.
+pub fn find_orphans(tasks: &[Task], entries: &[LedgerEntry]) -> Vec<Orphan> {
+    let states: HashMap<Uuid, TaskState> = tasks.iter().map(|t| (t.id, t.state)).collect();
+    let mut orphans: Vec<Orphan> = tasks
+        .iter()
+        .filter(|t| in_flight(t.state))
+        .filter(|t| {
+            !t.subtask_ids
+                .iter()
+                .any(|id| states.get(id).is_some_and(|s| in_flight(*s)))
+        })
+        .map(|task| {
+            let history: Vec<&LedgerEntry> =
+                entries.iter().filter(|e| e.subject_id == task.id).collect();
+            let since = history
+                .iter()
+                .rev()
+                .find(|e| e.kind == LedgerEntryKind::TaskStateChanged)
+                .map(|e| e.timestamp)
+                .unwrap_or(task.updated_at);
+            let attempts = history
+                .iter()
+                .filter(|e| e.kind == LedgerEntryKind::ExecutionAttempt && e.timestamp >= since)
+                .count();
+            let checkpoints: Vec<&&LedgerEntry> = history
+                .iter()
+                .filter(|e| e.kind == LedgerEntryKind::CheckpointRecorded && e.timestamp >= since)
+                .collect();
+            let last_checkpoint = checkpoints.last().map(|e| {
+                let progress = e.payload["progress_pct"].as_f64().unwrap_or(0.0);
+                let status = e.payload["status_message"].as_str().unwrap_or_default();
+                (progress, status.to_string())
+            });
+            let session_id = checkpoints
+                .iter()
+                .rev()
+                .find_map(|e| e.payload["metadata"]["session_id"].as_str())
+                .map(str::to_string)
+                .or_else(|| task.session_id.clone());
+            Orphan {
+                task_id: task.id,
+                name: task.name.clone(),
+                state: task.state,
+                agent_id: task.assigned_agent_id,
+                since,
+                attempts,
+                last_checkpoint,
+                session_id,
+            }
+        })
+        .collect();
+    orphans.sort_by_key(|o| o.since);
+    orphans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::Checkpoint;
+
+    fn entry(kind: LedgerEntryKind, subject: Uuid, payload: serde_json::Value) -> LedgerEntry {
+        LedgerEntry::new(kind, Uuid::new_v4(), subject, payload, None)
+    }
+
+    #[test]
+    fn test_parse_action_names() {
+        for action in RecoveryAction::ALL {
+            assert_eq!(action.as_str().parse::<RecoveryAction>().unwrap(), action);
+        }
+        assert_eq!(
+            "mark-failed".parse::<RecoveryAction>().unwrap(),
+            RecoveryAction::MarkFailed
+        );
+        assert_eq!(
+            "fail".parse::<RecoveryAction>().unwrap(),
+            RecoveryAction::MarkFailed
+        );
+        assert!("later".parse::<RecoveryAction>().is_err());
+    }
+
+    #[test]
+    fn test_find_orphans_reads_the_ledger() {
+        let agent_id = Uuid::new_v4();
+        let mut parent = Task::new("parent", "p");
+        let mut running = Task::new("running", "r");
+        let mut negotiating = Task::new("negotiating", "n");
+        let mut done = Task::new("done", "d");
+        running.state = TaskState::InProgress;
+        running.assigned_agent_id = Some(agent_id);
+        negotiating.state = TaskState::Negotiating;
+        done.state = TaskState::Completed;
+        parent.state = TaskState::InProgress;
+        parent.subtask_ids = vec![running.id, negotiating.id, done.id];
+
+        let checkpoint = Checkpoint::new(running.id, agent_id)
+            .with_progress(0.4)
+            .with_status("Turn 2: using Bash")
+            .with_metadata(serde_json::json!({ "session_id": "abc" }));
+        let entries = vec![
+            entry(
+                LedgerEntryKind::TaskStateChanged,
+                running.id,
+                serde_json::json!({}),
+            ),
+            entry(
+                LedgerEntryKind::ExecutionAttempt,
+                running.id,
+                serde_json::json!({}),
+            ),
+            entry(
+                LedgerEntryKind::CheckpointRecorded,
+                running.id,
+                serde_json::to_value(&checkpoint).unwrap(),
+            ),
+        ];
+
+        let tasks = vec![parent, running.clone(), negotiating.clone(), done];
+        let orphans = find_orphans(&tasks, &entries);
+        let ids: Vec<Uuid> = orphans.iter().map(|o| o.task_id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&running.id) && ids.contains(&negotiating.id));
+
+        let running = orphans.iter().find(|o| o.task_id == running.id).unwrap();
+        assert_eq!(running.agent_id, Some(agent_id));
+        assert_eq!(running.attempts, 1);
+        assert_eq!(
+            running.last_checkpoint,
+            Some((0.4, "Turn 2: using Bash".to_string()))
+        );
+        assert_eq!(running.session_id.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_parent_interrupted_while_aggregating_is_an_orphan() {
+        let mut parent = Task::new("parent", "p");
+        let mut child = Task::new("child", "c");
+        child.state = TaskState::Completed;
+        parent.state = TaskState::AwaitingVerification;
+        parent.subtask_ids = vec![child.id];
+        let orphans = find_orphans(&[parent.clone(), child], &[]);
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].task_id, parent.id);
+        assert_eq!(orphans[0].since, parent.updated_at);
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use super::{TaskDag, concurrency_limit, urgency_order};
+use crate::assignment::{Auction, CapabilityMatcher, MarketConfig};
+use crate::permissions::ApprovalRequirement;
+use crate::types::{Agent, DelegationContract, ResourceUsage, Task, TaskState};
+
+/// What a dry run predicts for one task. A parent's cost, overhead, and timing are those of
+/// its subtasks.
+#[derive(Debug, Clone)]
+pub struct TaskForecast {
+    pub task_id: Uuid,
+    pub name: String,
+    /// Hierarchical number as `/execute` prints it (`""` for a root).
+    pub number: String,
+    pub is_parent: bool,
+    /// Completed by an earlier run, so not run again.
+    pub done: bool,
+    /// Agent expected to take the task, and whether it keeps the contract it already holds
+    /// rather than winning an auction.
+    pub agent_id: Option<Uuid>,
+    pub under_contract: bool,
+    /// Approval the task calls for; `None` for parents and finished tasks.
+    pub approval: Option<ApprovalRequirement>,
+    /// Expected spend in US dollars: the winning bid (or contracted payment), and the
+    /// delegation overhead on top of it.
+    pub cost: f64,
+    pub overhead: f64,
+    /// Monitoring checkpoints expected while the task runs.
+    pub checkpoints: u32,
+    /// When the task is expected to start and finish, in seconds into the run.
+    pub start_secs: u64,
+    pub finish_secs: u64,
+    /// What may keep the task from running as forecast.
+    pub risks: Vec<String>,
+}
+
+impl TaskForecast {
+    pub fn duration_secs(&self) -> u64 {
+        self.finish_secs - self.start_secs
+    }
+}
+
+/// A dry run of one or more task trees: every task's forecast (parents before their
+/// subtasks), the critical path, and the totals.
+#[derive(Debug, Clone, Default)]
+pub struct Forecast {
+    pub tasks: Vec<TaskForecast>,
+    /// Longest chain of dependent leaf tasks, and how long it takes: no run can be faster.
+    pub critical_path: Vec<Uuid>,
+    pub critical_path_secs: u64,
+    /// How long the run is expected to take within the concurrency limits.
+    pub duration_secs: u64,
+    pub cost: f64,
+    pub overhead: f64,
+}
+
+impl Forecast {
+    pub fn total_cost(&self) -> f64 {
+        self.cost + self.overhead
+    }
+
+    /// Number of tasks with at least one risk.
+    pub fn at_risk(&self) -> usize {
+        self.tasks.iter().filter(|t| !t.risks.is_empty()).count()
+    }
+}
+
+/// A chain of leaf tasks and its length in seconds.
+type Path = (u64, Vec<Uuid>);
+
+/// Walks task trees the way `/execute` runs them, without running anything: subtasks start
+/// in dependency and urgency order within each tree's concurrency limit, and each leaf goes
+/// to the agent that would win its auction (or keep its contract), for the bid's cost and
+/// duration. Agent capacity is not modelled, and aggregation is assumed to take no time.
+pub struct Simulation<'a> {
+    pub tasks: &'a HashMap<Uuid, Task>,
+    /// Agents a run would have.
+    pub agents: &'a [Agent],
+    pub contracts: &'a HashMap<Uuid, DelegationContract>,
+    /// Agent that takes every task, as when no agents are configured.
+    pub default_agent: Option<Uuid>,
+    pub market: &'a MarketConfig,
+    pub matcher: CapabilityMatcher,
+    pub max_parallel: u32,
+    /// An agent's completed-task usage, which its bids are based on.
+    pub track_record: &'a dyn Fn(Uuid) -> Vec<ResourceUsage>,
+    /// An agent's predicted quality in [0, 1], which its bids are scored by.
+    pub quality: &'a dyn Fn(Uuid) -> f64,
+    /// When the run starts; deadlines are checked against it.
+    pub now: DateTime<Utc>,
+}
+
+impl Simulation<'_> {
+    /// Forecast running `roots` one after another, as `/execute --all` does.
+    pub fn run(&self, roots: &[Uuid]) -> Forecast {
+        let mut forecast = Forecast::default();
+        let mut clock = 0;
+        for id in roots {
+            let Some(task) = self.tasks.get(id) else {
+                continue;
+            };
+            let (finish, (secs, path)) = self.walk(task, String::new(), clock, &mut forecast);
+            clock = finish;
+            forecast.critical_path.extend(path);
+            forecast.critical_path_secs += secs;
+        }
+        forecast.duration_secs = clock;
+        for task in forecast.tasks.iter().filter(|t| !t.is_parent) {
+            forecast.cost += task.cost;
+            forecast.overhead += task.overhead;
+        }
+        forecast
+    }
+
+    /// Forecast a task starting at `start`, returning when it finishes and its critical path.
+    fn walk(
+        &self,
+        task: &Task,
+        number: String,
+        start: u64,
+        forecast: &mut Forecast,
+    ) -> (u64, Path) {
+        if task.state == TaskState::Completed {
+            forecast.tasks.push(TaskForecast {
+                done: true,
+                ..self.blank(task, number, start)
+            });
+            return (start, (0, Vec::new()));
+        }
+        if task.subtask_ids.is_empty() {
+            let leaf = self.leaf(task, number, start);
+            let (finish, secs) = (leaf.finish_secs, leaf.duration_secs());
+            forecast.tasks.push(leaf);
+            return (finish, (secs, vec![task.id]));
+        }
+
+        let slot = forecast.tasks.len();
+        forecast.tasks.push(TaskForecast {
+            is_parent: true,
+            ..self.blank(task, number.clone(), start)
+        });
+        let first = forecast.tasks.len();
+        let ids = &task.subtask_ids;
+        let mut dag = TaskDag::from_metadata(ids.len(), &task.metadata);
+        let limit = concurrency_limit(&task.metadata, self.max_parallel);
+        let sub_number = |idx: usize| {
+            if number.is_empty() {
+                (idx + 1).to_string()
+            } else {
+                format!("{number}.{}", idx + 1)
+            }
+        };
+        let mut paths: Vec<Path> = vec![(0, Vec::new()); ids.len()];
+        let mut running: Vec<(u64, usize)> = Vec::new();
+        let mut now = start;
+        for (idx, id) in ids.iter().enumerate() {
+            match self.tasks.get(id) {
+                Some(subtask) if subtask.state == TaskState::Completed => {
+                    self.walk(subtask, sub_number(idx), now, forecast);
+                    dag.complete(idx);
+                }
+                Some(_) => {}
+                None => {
+                    forecast.tasks[slot]
+                        .risks
+                        .push(format!("subtask {id} no longer exists"));
+                    dag.complete(idx);
+                }
+            }
+        }
+        loop {
+            let mut ready: Vec<(usize, &Task)> = dag
+                .ready()
+                .into_iter()
+                .filter_map(|idx| Some((idx, self.tasks.get(&ids[idx])?)))
+                .collect();
+            ready.sort_by(|(_, a), (_, b)| urgency_order(a, b));
+            for (idx, subtask) in ready {
+                if running.len() >= limit {
+                    break;
+                }
+                dag.start(idx);
+                let (finish, (secs, path)) = self.walk(subtask, sub_number(idx), now, forecast);
+                let (before, mut chain) = dag
+                    .prerequisites(idx)
+                    .iter()
+                    .rev()
+                    .map(|&p| paths[p].clone())
+                    .max_by_key(|(secs, _)| *secs)
+                    .unwrap_or_default();
+                chain.extend(path);
+                paths[idx] = (before + secs, chain);
+                running.push((finish, idx));
+            }
+            let Some(next) = (0..running.len()).min_by_key(|&i| running[i].0) else {
+                break;
+            };
+            let (finish, idx) = running.swap_remove(next);
+            now = now.max(finish);
+            dag.complete(idx);
+        }
+
+        let mut parent = TaskForecast {
+            finish_secs: now,
+            ..forecast.tasks[slot].clone()
+        };
+        let stuck = dag.stuck();
+        if !stuck.is_empty() {
+            let names: Vec<String> = stuck
+                .iter()
+                .map(|&i| {
+                    self.tasks
+                        .get(&ids[i])
+                        .map_or_else(|| ids[i].to_string(), |t| t.name.clone())
+                })
+                .collect();
+            parent.risks.push(format!(
+                "subtasks on a dependency cycle will not run: {}",
+                names.join(", ")
+            ));
+        }
+        for sub in forecast.tasks[first..].iter().filter(|t| !t.is_parent) {
+            parent.cost += sub.cost;
+            parent.overhead += sub.overhead;
+            parent.checkpoints += sub.checkpoints;
+        }
+        self.check_deadline(task, &mut parent);
+        forecast.tasks[slot] = parent;
+        // The first of equally long paths, as `max_by_key` keeps the last.
+        let critical = paths.into_iter().rev().max_by_key(|(secs, _)| *secs);
+        (now, critical.unwrap_or_default())
+    }
+
+    /// Forecast a leaf task: who takes it, what it needs approved, and what it costs.
+    fn leaf(&self, task: &Task, number: String, start: u64) -> TaskForecast {
+        let approval = ApprovalRequirement::from_characteristics(&task.characteristics);
+        let mut leaf = TaskForecast {
+            approval: Some(approval.clone()),
+            ..self.blank(task, number, start)
+        };
+        if approval.human_required {
+            leaf.risks.push(format!(
+                "needs just-in-time approval from {} approvers and a human",
+                approval.required_approvers
+            ));
+        }
+
+        let contractor = task
+            .contract_id
+            .and_then(|id| self.contracts.get(&id))
+            .filter(|c| c.is_fully_signed())
+            .filter(|c| c.expires_at.is_none_or(|at| at > self.now))
+            .and_then(|c| Some((self.agent(c.delegatee_id)?, c.payment.total_amount)));
+        let (agent_id, cost, duration_secs) = if let Some((agent, payment)) = contractor {
+            let bid = self.market.bid(agent, task, &(self.track_record)(agent.id));
+            leaf.under_contract = true;
+            (agent.id, payment, bid.proposed_duration_secs)
+        } else {
+            let candidates: Vec<Agent> = match self.default_agent.or(task
+                .assigned_agent_id
+                .filter(|id| self.agent(*id).is_some()))
+            {
+                Some(id) => self.agent(id).cloned().into_iter().collect(),
+                None => self.matcher.filter_agents(task, self.agents),
+            };
+            if candidates.is_empty() {
+                leaf.risks.push(format!(
+                    "no available agent can take it (requires: {})",
+                    if task.required_capabilities.is_empty() {
+                        "nothing".to_string()
+                    } else {
+                        task.required_capabilities.join(", ")
+                    }
+                ));
+                return leaf;
+            }
+            let auction = Auction::run(
+                self.market,
+                task,
+                &candidates,
+                self.track_record,
+                self.quality,
+            );
+            let Some(winner) = auction.winner() else {
+                leaf.risks.push(format!(
+                    "no bid is within its ${:.2} budget",
+                    auction.rfp.max_cost
+                ));
+                return leaf;
+            };
+            let bid = &winner.bid;
+            (bid.agent_id, bid.proposed_cost, bid.proposed_duration_secs)
+        };
+
+        let overhead = self.market.overhead(duration_secs, leaf.under_contract);
+        leaf.agent_id = Some(agent_id);
+        leaf.cost = cost;
+        leaf.overhead = overhead.total();
+        leaf.checkpoints = overhead.expected_checkpoints;
+        leaf.finish_secs = start + duration_secs;
+        self.check_deadline(task, &mut leaf);
+        leaf
+    }
+
+    /// Note when a task is expected to finish after its deadline.
+    fn check_deadline(&self, task: &Task, forecast: &mut TaskForecast) {
+        let Some(deadline) = task.deadline else {
+            return;
+        };
+        let finish = self.now + Duration::seconds(forecast.finish_secs.min(i64::MAX as u64) as i64);
+        if finish > deadline {
+            let late = (finish - deadline).num_seconds();
+            forecast.risks.push(format!(
+                "expected to finish {}m {:02}s after its deadline",
+                late / 60,
+                late % 60
+            ));
+        }
+    }
+
+    fn agent(&self, id: Uuid) -> Option<&Agent> {
+        self.agents.iter().find(|a| a.id == id)
+    }
+
+    /// A forecast of nothing for a task starting at `start`.
+    fn blank(&self, task: &Task, number: String, start: u64) -> TaskForecast {
+        TaskForecast {
+            task_id: task.id,
+            name: task.name.clone(),
+            number,
+            is_parent: false,
+            done: false,
+            agent_id: None,
+            under_contract: false,
+            approval: None,
+            cost: 0.0,
+            overhead: 0.0,
+            checkpoints: 0,
+            start_secs: start,
+            finish_secs: start,
+            risks: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::ApprovalLevel;
+    use crate::types::Capability;
+
+    fn agent(name: &str, caps: &[(&str, f64)]) -> Agent {
+        let mut agent = Agent::new(name);
+        agent.capabilities.capabilities = caps
+            .iter()
+            .map(|(n, p)| Capability {
+                name: n.to_string(),
+                proficiency: *p,
+                certified: false,
+                last_verified: None,
+            })
+            .collect();
+        agent
+    }
+
+    fn usage(cost_usd: f64, duration_ms: u64) -> ResourceUsage {
+        ResourceUsage {
+            cost_usd,
+            duration_ms,
+            ..Default::default()
+        }
+    }
+
+    /// A parent with subtasks `a`, `b`, `c`, `d`, where `c` depends on `a` and `b` on `d`.
+    fn tree() -> (Task, Vec<Task>) {
+        let mut parent = Task::new("parent", "p");
+        let subtasks: Vec<Task> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|name| {
+                let mut task = Task::new(name, name).with_capabilities(vec![name.into()]);
+                task.parent_id = Some(parent.id);
+                task
+            })
+            .collect();
+        parent.subtask_ids = subtasks.iter().map(|t| t.id).collect();
+        parent.metadata = serde_json::json!({ "dependencies": [[0, 2], [3, 1]] });
+        (parent, subtasks)
+    }
+
+    fn forecast(tasks: &[Task], agents: &[Agent], max_parallel: u32) -> Forecast {
+        let tasks: HashMap<Uuid, Task> = tasks.iter().map(|t| (t.id, t.clone())).collect();
+        let contracts = HashMap::new();
+        let market = MarketConfig {
+            checkpoint_interval_secs: 10,
+            checkpoint_cost: 0.01,
+            ..Default::default()
+        };
+        // Agent `slow` takes 60s and $0.50 a task, everyone else 20s and $0.10.
+        let slow = agents.iter().find(|a| a.name == "slow").map(|a| a.id);
+        let track_record = move |id: Uuid| {
+            if Some(id) == slow {
+                vec![usage(0.5, 60_000)]
+            } else {
+                vec![usage(0.1, 20_000)]
+            }
+        };
+        let roots: Vec<Uuid> = tasks
+            .values()
+            .filter(|t| t.parent_id.is_none())
+            .map(|t| t.id)
+            .collect();
+        Simulation {
+            tasks: &tasks,
+            agents,
+            contracts: &contracts,
+            default_agent: None,
+            market: &market,
+            matcher: CapabilityMatcher::new(0.0),
+            max_parallel,
+            track_record: &track_record,
+            quality: &|_| 0.5,
+            now: Utc::now(),
+        }
+        .run(&roots)
+    }
+
+    fn find<'a>(forecast: &'a Forecast, name: &str) -> &'a TaskForecast {
+        forecast.tasks.iter().find(|t| t.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_forecast_assigns_costs_and_finds_the_critical_path() {
+        let (parent, subtasks) = tree();
+        let fast = agent("fast", &[("a", 0.9), ("b", 0.9), ("c", 0.9)]);
+        let slow = agent("slow", &[("d", 0.9)]);
+        let mut tasks = subtasks.clone();
+        tasks.push(parent);
+        let forecast = forecast(&tasks, &[fast.clone(), slow.clone()], 4);
+
+        // a (20s) -> c (20s) runs alongside d (60s) -> b (20s).
+        assert_eq!(find(&forecast, "a").agent_id, Some(fast.id));
+        assert_eq!(find(&forecast, "d").agent_id, Some(slow.id));
+        assert_eq!(find(&forecast, "c").start_secs, 20);
+        assert_eq!(find(&forecast, "b").start_secs, 60);
+        assert_eq!(forecast.duration_secs, 80);
+        assert_eq!(forecast.critical_path, vec![subtasks[3].id, subtasks[1].id]);
+        assert_eq!(forecast.critical_path_secs, 80);
+
+        // Bids of $0.10 x 3 + $0.50, and a $0.01 checkpoint every 10s of the 120s of work.
+        assert!((forecast.cost - 0.8).abs() < 1e-9);
+        assert!((forecast.overhead - 0.12).abs() < 1e-9);
+        let parent = find(&forecast, "parent");
+        assert!(parent.is_parent && parent.approval.is_none());
+        assert!((parent.cost - 0.8).abs() < 1e-9);
+        assert_eq!(parent.finish_secs, 80);
+        assert_eq!(
+            find(&forecast, "a").approval.as_ref().map(|a| a.level),
+            Some(ApprovalLevel::ContextualPermission)
+        );
+    }
+
+    #[test]
+    fn test_forecast_honours_concurrency_and_reports_risks() {
+        let (parent, mut subtasks) = tree();
+        subtasks[2].state = TaskState::Completed;
+        subtasks[0].deadline = Some(Utc::now() + Duration::seconds(70));
+        subtasks[0].priority = 0.1;
+        let fast = agent("fast", &[("a", 0.9), ("b", 0.9), ("d", 0.9)]);
+        let mut tasks = subtasks.clone();
+        tasks.push(parent.clone());
+        let forecast = forecast(&tasks, &[fast], 1);
+
+        // One at a time, most urgent first: d, then b (once d is done) ahead of a.
+        assert!(find(&forecast, "c").done);
+        assert_eq!(find(&forecast, "d").start_secs, 0);
+        assert_eq!(find(&forecast, "b").start_secs, 20);
+        assert_eq!(find(&forecast, "a").start_secs, 40);
+        assert_eq!(forecast.duration_secs, 60);
+        assert_eq!(forecast.critical_path_secs, 40);
+        assert_eq!(forecast.at_risk(), 0);
+
+        // Without an agent for b, and with a's deadline before it can finish.
+        let mut tasks = subtasks.clone();
+        tasks[0].priority = 0.5;
+        tasks[0].deadline = Some(Utc::now() + Duration::seconds(10));
+        tasks.push(parent);
+        let forecast = self::forecast(&tasks, &[agent("fast", &[("a", 0.9), ("d", 0.9)])], 1);
+        assert_eq!(forecast.at_risk(), 2);
+        assert!(find(&forecast, "b").risks[0].starts_with("no available agent"));
+        assert!(find(&forecast, "a").risks[0].contains("after its deadline"));
+    }
+}
@@ -51,6 +51,7 @@ impl ReputationScore {
 pub struct PermissionSet {
     pub allowed_actions: Vec<String>,
     pub max_delegation_depth: u32,
+    /// Maximum total spend in US dollars, checked against recorded `ResourceUsage::cost_usd`.
     pub max_cost_budget: f64,
     pub allowed_data_classifications: Vec<String>,
 }
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// A file an agent created or modified while executing a task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// Path relative to the task's workspace, with `/` separators.
+    pub path: String,
+    /// Size in bytes.
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+impl std::fmt::Display for Artifact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} bytes, sha256 {})",
+            self.path,
+            self.size,
+            &self.sha256[..self.sha256.len().min(12)]
+        )
+    }
+}
@@ -27,7 +27,8 @@ pub struct MonitoringTerms {
     pub max_latency_ms: u64,
     /// Minimum quality score threshold.
     pub min_quality_score: f64,
-    /// Maximum resource consumption.
+    /// Maximum resource consumption, in `TaskResult::resource_consumed` units
+    /// (total tokens for the built-in executors).
     pub max_resource_budget: f64,
 }
 
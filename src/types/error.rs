@@ -1,7 +1,45 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::task::{TaskEvent, TaskState};
 
+/// Classification of executor failures, used to decide whether a retry can help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExecutorErrorKind {
+    /// The backend process could not be started (program missing, not executable, ...).
+    Spawn,
+    /// Communication with the backend failed (connection refused, DNS, TLS, broken pipe, ...).
+    Transport,
+    /// The backend asked us to slow down (e.g. HTTP 429).
+    RateLimited,
+    /// The backend failed on its side (e.g. HTTP 5xx).
+    BackendUnavailable,
+    /// The backend rejected the request (authentication, invalid request, ...).
+    Rejected,
+    /// The backend process exited with a failure status.
+    NonZeroExit,
+    /// The backend replied but the response could not be interpreted.
+    InvalidResponse,
+    /// The agent's run ended in a failure of its own, such as running out of turns.
+    AgentFailed,
+    /// The execution ran past its deadline or latency budget.
+    TimedOut,
+    /// The execution was cancelled by the caller (e.g. Ctrl-C).
+    Cancelled,
+}
+
+impl ExecutorErrorKind {
+    /// Whether a failure of this kind is transient and worth retrying by default.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ExecutorErrorKind::Transport
+                | ExecutorErrorKind::RateLimited
+                | ExecutorErrorKind::BackendUnavailable
+        )
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum PanopticonError {
     #[error("State transition error: cannot transition from {from:?} via {event:?}")]
@@ -49,11 +87,49 @@ pub enum PanopticonError {
     #[error("Dispute error: {0}")]
     DisputeError(String),
 
+    #[error("Executor error ({kind:?}): {message}")]
+    Executor {
+        kind: ExecutorErrorKind,
+        message: String,
+    },
+
     #[error("Serialization error: {0}")]
     Serialization(String),
 
+    #[error("Template error: {0}")]
+    Template(String),
+
+    #[error("Conflicting change: {0}")]
+    Conflict(String),
+
+    #[error("Invalid schema: {0}")]
+    InvalidSchema(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
+impl PanopticonError {
+    /// Shorthand for an `Executor` error of the given kind.
+    pub fn executor(kind: ExecutorErrorKind, message: impl Into<String>) -> Self {
+        Self::Executor {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Whether retrying the failed operation may succeed.
+    pub fn is_retryable(&self) -> bool {
+        self.executor_kind().is_some_and(|kind| kind.is_retryable())
+    }
+
+    /// The executor failure class, if this is an executor error.
+    pub fn executor_kind(&self) -> Option<ExecutorErrorKind> {
+        match self {
+            Self::Executor { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, PanopticonError>;
@@ -1,9 +1,13 @@
 pub mod agent;
+pub mod artifact;
 pub mod contract;
 pub mod error;
 pub mod task;
+pub mod usage;
 
 pub use agent::*;
+pub use artifact::*;
 pub use contract::*;
 pub use error::*;
 pub use task::*;
+pub use usage::*;
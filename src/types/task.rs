@@ -2,7 +2,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::artifact::Artifact;
 use super::error::PanopticonError;
+use super::usage::ResourceUsage;
 
 /// 11-dimensional task characteristics from the paper.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -81,6 +83,8 @@ pub enum TaskEvent {
     DisputeResolved,
     TaskFailed,
     Retry,
+    /// Reopen a finished task to continue it with a follow-up request.
+    FollowUp,
 }
 
 impl TaskState {
@@ -130,10 +134,43 @@ impl TaskState {
             // From Failed — allow retry
             (TaskState::Failed, TaskEvent::Retry) => Ok(TaskState::Pending),
 
+            // Follow-ups reopen finished tasks
+            (TaskState::Completed | TaskState::Failed, TaskEvent::FollowUp) => {
+                Ok(TaskState::Pending)
+            }
+
             // All other transitions are invalid
             (state, event) => Err(PanopticonError::InvalidStateTransition { from: state, event }),
         }
     }
+
+    /// The state of a parent task as seen from its children: `Completed` once every child
+    /// has completed, `InProgress` while any child is underway, `Failed` once a child has
+    /// failed and none is underway, and `Pending` otherwise (including when there are no
+    /// children).
+    pub fn rollup(children: impl IntoIterator<Item = TaskState>) -> TaskState {
+        let (mut any, mut all_completed, mut underway, mut failed) = (false, true, false, false);
+        for state in children {
+            any = true;
+            all_completed &= state == TaskState::Completed;
+            match state {
+                TaskState::Pending | TaskState::Completed => {}
+                TaskState::Failed => failed = true,
+                _ => underway = true,
+            }
+        }
+        if !any {
+            TaskState::Pending
+        } else if all_completed {
+            TaskState::Completed
+        } else if underway {
+            TaskState::InProgress
+        } else if failed {
+            TaskState::Failed
+        } else {
+            TaskState::Pending
+        }
+    }
 }
 
 /// A delegation task.
@@ -153,6 +190,38 @@ pub struct Task {
     pub updated_at: DateTime<Utc>,
     pub deadline: Option<DateTime<Utc>>,
     pub metadata: serde_json::Value,
+    /// Usage accumulated across all executions of this task.
+    #[serde(default)]
+    pub usage: ResourceUsage,
+    /// Directory the task executed in.
+    #[serde(default)]
+    pub workspace: Option<String>,
+    /// Files created or modified by the latest execution.
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+    /// JSON Schema the task's output must conform to.
+    #[serde(default)]
+    pub output_schema: Option<serde_json::Value>,
+    /// Backend session of the latest execution, continued by retries and follow-ups.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Message for the agent on the next run: why the last result failed verification,
+    /// or a follow-up request.
+    #[serde(default)]
+    pub feedback: Option<String>,
+    /// Why the latest run failed.
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+    /// Failed sibling this task was skipped for; it stays pending until run again.
+    #[serde(default)]
+    pub blocked_by: Option<Uuid>,
+    /// Relative priority in [0, 1], adjusted by the coordinator on `PriorityChanged`.
+    #[serde(default = "default_priority")]
+    pub priority: f64,
+}
+
+fn default_priority() -> f64 {
+    0.5
 }
 
 impl Task {
@@ -173,6 +242,15 @@ impl Task {
             updated_at: now,
             deadline: None,
             metadata: serde_json::Value::Null,
+            usage: ResourceUsage::default(),
+            workspace: None,
+            artifacts: Vec::new(),
+            output_schema: None,
+            session_id: None,
+            feedback: None,
+            failure_reason: None,
+            blocked_by: None,
+            priority: default_priority(),
         }
     }
 
@@ -186,6 +264,24 @@ impl Task {
         self
     }
 
+    pub fn with_output_schema(mut self, schema: serde_json::Value) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+
+    /// The task's output: its `metadata` for a leaf, or `metadata.output` (its subtasks'
+    /// results combined) for a parent.
+    pub fn output(&self) -> serde_json::Value {
+        if self.subtask_ids.is_empty() {
+            self.metadata.clone()
+        } else {
+            self.metadata
+                .get("output")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null)
+        }
+    }
+
     /// Apply a state transition event.
     pub fn apply_event(&mut self, event: TaskEvent) -> super::error::Result<()> {
         self.state = self.state.transition(event)?;
@@ -198,6 +294,30 @@ impl Task {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_output_of_leaf_and_parent() {
+        let mut leaf = Task::new("leaf", "d");
+        leaf.metadata = serde_json::json!({"result": "x"});
+        assert_eq!(leaf.output(), leaf.metadata);
+
+        let mut parent = Task::new("parent", "d");
+        parent.subtask_ids.push(leaf.id);
+        parent.metadata = serde_json::json!({"dependencies": []});
+        assert!(parent.output().is_null());
+        parent.metadata["output"] = serde_json::json!({"result": "y"});
+        assert_eq!(parent.output()["result"], "y");
+    }
+
+    #[test]
+    fn test_rollup_from_children() {
+        use TaskState::*;
+        assert_eq!(TaskState::rollup([]), Pending);
+        assert_eq!(TaskState::rollup([Completed, Completed]), Completed);
+        assert_eq!(TaskState::rollup([Completed, InProgress, Failed]), InProgress);
+        assert_eq!(TaskState::rollup([Completed, Failed, Pending]), Failed);
+        assert_eq!(TaskState::rollup([Completed, Pending]), Pending);
+    }
+
     #[test]
     fn test_valid_full_lifecycle() {
         let mut state = TaskState::Pending;
@@ -248,6 +368,14 @@ mod tests {
         assert_eq!(state, TaskState::Pending);
     }
 
+    #[test]
+    fn test_follow_up_reopens_finished_tasks() {
+        for finished in [TaskState::Completed, TaskState::Failed] {
+            assert_eq!(finished.transition(TaskEvent::FollowUp).unwrap(), TaskState::Pending);
+        }
+        assert!(TaskState::InProgress.transition(TaskEvent::FollowUp).is_err());
+    }
+
     #[test]
     fn test_task_builder() {
         let task = Task::new("test", "a test task").with_capabilities(vec!["nlp".to_string()]);
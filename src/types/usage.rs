@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+/// Resources consumed by one or more executions: tokens, dollars, wall time, and turns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Tokens written to the prompt cache.
+    pub cache_creation_input_tokens: u64,
+    /// Tokens served from the prompt cache.
+    pub cache_read_input_tokens: u64,
+    /// Cost in US dollars, as reported by the backend (0.0 when unknown).
+    pub cost_usd: f64,
+    /// Wall-clock duration in milliseconds.
+    pub duration_ms: u64,
+    /// Number of agentic turns.
+    pub num_turns: u32,
+}
+
+impl ResourceUsage {
+    /// Parse the usage fields found in a claude CLI result envelope.
+    pub fn from_claude_envelope(envelope: &serde_json::Value) -> Self {
+        let usage = &envelope["usage"];
+        let tokens = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        Self {
+            input_tokens: tokens("input_tokens"),
+            output_tokens: tokens("output_tokens"),
+            cache_creation_input_tokens: tokens("cache_creation_input_tokens"),
+            cache_read_input_tokens: tokens("cache_read_input_tokens"),
+            // Older CLI versions report `cost_usd` instead of `total_cost_usd`.
+            cost_usd: envelope
+                .get("total_cost_usd")
+                .or_else(|| envelope.get("cost_usd"))
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0),
+            duration_ms: envelope
+                .get("duration_ms")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            num_turns: envelope
+                .get("num_turns")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32,
+        }
+    }
+
+    /// All tokens, including cache reads and writes.
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::ops::AddAssign for ResourceUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.cost_usd += other.cost_usd;
+        self.duration_ms += other.duration_ms;
+        self.num_turns += other.num_turns;
+    }
+}
+
+impl std::iter::Sum for ResourceUsage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |mut acc, u| {
+            acc += u;
+            acc
+        })
+    }
+}
+
+impl std::fmt::Display for ResourceUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} tokens ({} in, {} out, {} cached), ${:.4}, {:.1}s, {} turn(s)",
+            self.total_tokens(),
+            self.input_tokens,
+            self.output_tokens,
+            self.cache_creation_input_tokens + self.cache_read_input_tokens,
+            self.cost_usd,
+            self.duration_ms as f64 / 1000.0,
+            self.num_turns,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_claude_envelope() {
+        let envelope = serde_json::json!({
+            "type": "result",
+            "result": "done",
+            "total_cost_usd": 0.0123,
+            "duration_ms": 4500,
+            "num_turns": 3,
+            "usage": {
+                "input_tokens": 100,
+                "output_tokens": 50,
+                "cache_creation_input_tokens": 10,
+                "cache_read_input_tokens": 200
+            }
+        });
+        let usage = ResourceUsage::from_claude_envelope(&envelope);
+        assert_eq!(usage.input_tokens, 100);
+        assert_eq!(usage.cache_read_input_tokens, 200);
+        assert_eq!(usage.total_tokens(), 360);
+        assert_eq!(usage.num_turns, 3);
+        assert!((usage.cost_usd - 0.0123).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_from_envelope_without_usage() {
+        let usage = ResourceUsage::from_claude_envelope(&serde_json::json!({"result": "x"}));
+        assert!(usage.is_empty());
+    }
+
+    #[test]
+    fn test_sum() {
+        let a = ResourceUsage {
+            input_tokens: 10,
+            cost_usd: 0.5,
+            num_turns: 1,
+            ..Default::default()
+        };
+        let total: ResourceUsage = vec![a, a, a].into_iter().sum();
+        assert_eq!(total.input_tokens, 30);
+        assert_eq!(total.num_turns, 3);
+        assert!((total.cost_usd - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_deserialize_partial() {
+        let usage: ResourceUsage = serde_json::from_str(r#"{"output_tokens": 7}"#).unwrap();
+        assert_eq!(usage.output_tokens, 7);
+        assert_eq!(usage.input_tokens, 0);
+    }
+}
@@ -1,9 +1,11 @@
 pub mod credential;
 pub mod dispute;
+pub mod schema;
 pub mod traits;
 pub mod verifiers;
 
 pub use credential::*;
 pub use dispute::*;
+pub use schema::{CompiledSchema, SchemaViolation};
 pub use traits::*;
 pub use verifiers::*;
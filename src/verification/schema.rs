@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::error::PanopticonError;
+
+/// One way an output fails to match its JSON Schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON Pointer to the offending value (`""` for the root).
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{path}: {}", self.message)
+    }
+}
+
+/// Keywords whose values are subschemas, and how: a schema, a map of schemas, or a list.
+const SUBSCHEMAS: [(&str, Nesting); 6] = [
+    ("properties", Nesting::Map),
+    ("additionalProperties", Nesting::One),
+    ("items", Nesting::One),
+    ("allOf", Nesting::List),
+    ("anyOf", Nesting::List),
+    ("oneOf", Nesting::List),
+];
+
+#[derive(Clone, Copy)]
+enum Nesting {
+    One,
+    Map,
+    List,
+}
+
+/// A JSON Schema checked once, with its `pattern`s compiled, ready to validate any number of
+/// outputs.
+///
+/// Supports the commonly used keywords: `type`, `enum`, `const`, `properties`, `required`,
+/// `additionalProperties`, `items`, `minItems`, `maxItems`, `minLength`, `maxLength`,
+/// `pattern`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `allOf`,
+/// `anyOf`, and `oneOf`. Other keywords (including `$ref`) are ignored.
+#[derive(Debug, Clone)]
+pub struct CompiledSchema {
+    schema: Value,
+    patterns: HashMap<String, Regex>,
+}
+
+impl CompiledSchema {
+    /// Check a schema, failing on a `pattern` that is not a valid regular expression: such a
+    /// schema is a mistake in the task, which no output can repair.
+    pub fn compile(schema: &Value) -> Result<Self, PanopticonError> {
+        let mut patterns = HashMap::new();
+        compile_patterns(schema, "", &mut patterns)?;
+        Ok(Self {
+            schema: schema.clone(),
+            patterns,
+        })
+    }
+
+    /// Validate `instance` against the schema, returning every violation found.
+    pub fn validate(&self, instance: &Value) -> Vec<SchemaViolation> {
+        self.violations(&self.schema, instance, "")
+    }
+
+    fn violations(&self, schema: &Value, instance: &Value, path: &str) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        self.check(schema, instance, path, &mut violations);
+        violations
+    }
+
+    fn check(&self, schema: &Value, instance: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                out.push(violation(path, "no value is allowed here"));
+                return;
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(expected) = schema.get("type")
+            && !type_matches(expected, instance)
+        {
+            out.push(violation(
+                path,
+                format!(
+                    "expected {}, got {}",
+                    describe_type(expected),
+                    type_name(instance)
+                ),
+            ));
+            // Further keywords would only repeat the type mismatch.
+            return;
+        }
+
+        if let Some(Value::Array(options)) = schema.get("enum")
+            && !options.contains(instance)
+        {
+            out.push(violation(
+                path,
+                format!("must be one of {}", Value::Array(options.clone())),
+            ));
+        }
+        if let Some(expected) = schema.get("const")
+            && expected != instance
+        {
+            out.push(violation(path, format!("must equal {expected}")));
+        }
+
+        match instance {
+            Value::Object(map) => {
+                let properties = schema.get("properties").and_then(|p| p.as_object());
+                if let Some(Value::Array(required)) = schema.get("required") {
+                    for key in required.iter().filter_map(|k| k.as_str()) {
+                        if !map.contains_key(key) {
+                            out.push(violation(
+                                path,
+                                format!("missing required property '{key}'"),
+                            ));
+                        }
+                    }
+                }
+                for (key, value) in map {
+                    let child = format!("{path}/{}", escape(key));
+                    match properties.and_then(|p| p.get(key)) {
+                        Some(sub) => self.check(sub, value, &child, out),
+                        None => match schema.get("additionalProperties") {
+                            Some(Value::Bool(false)) => {
+                                out.push(violation(path, format!("unexpected property '{key}'")))
+                            }
+                            Some(sub @ Value::Object(_)) => self.check(sub, value, &child, out),
+                            _ => {}
+                        },
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64())
+                    && (items.len() as u64) < min
+                {
+                    out.push(violation(path, format!("must have at least {min} item(s)")));
+                }
+                if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64())
+                    && (items.len() as u64) > max
+                {
+                    out.push(violation(path, format!("must have at most {max} item(s)")));
+                }
+                if let Some(sub) = schema.get("items") {
+                    for (i, item) in items.iter().enumerate() {
+                        self.check(sub, item, &format!("{path}/{i}"), out);
+                    }
+                }
+            }
+            Value::String(s) => {
+                let len = s.chars().count() as u64;
+                if let Some(min) = schema.get("minLength").and_then(|v| v.as_u64())
+                    && len < min
+                {
+                    out.push(violation(
+                        path,
+                        format!("must be at least {min} character(s)"),
+                    ));
+                }
+                if let Some(max) = schema.get("maxLength").and_then(|v| v.as_u64())
+                    && len > max
+                {
+                    out.push(violation(
+                        path,
+                        format!("must be at most {max} character(s)"),
+                    ));
+                }
+                if let Some(pattern) = schema.get("pattern").and_then(|v| v.as_str())
+                    && self.patterns.get(pattern).is_some_and(|re| !re.is_match(s))
+                {
+                    out.push(violation(path, format!("must match pattern '{pattern}'")));
+                }
+            }
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                let bound = |key: &str| schema.get(key).and_then(|v| v.as_f64());
+                if let Some(min) = bound("minimum")
+                    && n < min
+                {
+                    out.push(violation(path, format!("must be >= {min}")));
+                }
+                if let Some(max) = bound("maximum")
+                    && n > max
+                {
+                    out.push(violation(path, format!("must be <= {max}")));
+                }
+                if let Some(min) = bound("exclusiveMinimum")
+                    && n <= min
+                {
+                    out.push(violation(path, format!("must be > {min}")));
+                }
+                if let Some(max) = bound("exclusiveMaximum")
+                    && n >= max
+                {
+                    out.push(violation(path, format!("must be < {max}")));
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            for sub in all {
+                self.check(sub, instance, path, out);
+            }
+        }
+        if let Some(Value::Array(any)) = schema.get("anyOf")
+            && !any
+                .iter()
+                .any(|sub| self.violations(sub, instance, path).is_empty())
+        {
+            out.push(violation(
+                path,
+                "does not match any of the allowed schemas (anyOf)",
+            ));
+        }
+        if let Some(Value::Array(one)) = schema.get("oneOf") {
+            let matching = one
+                .iter()
+                .filter(|sub| self.violations(sub, instance, path).is_empty())
+                .count();
+            if matching != 1 {
+                out.push(violation(
+                    path,
+                    format!("must match exactly one schema (oneOf), matched {matching}"),
+                ));
+            }
+        }
+    }
+}
+
+/// Compile every `pattern` in `schema` and its subschemas into `patterns`, keyed by source.
+fn compile_patterns(
+    schema: &Value,
+    path: &str,
+    patterns: &mut HashMap<String, Regex>,
+) -> Result<(), PanopticonError> {
+    let Value::Object(schema) = schema else {
+        return Ok(());
+    };
+    if let Some(pattern) = schema.get("pattern").and_then(|v| v.as_str())
+        && !patterns.contains_key(pattern)
+    {
+        let re = Regex::new(pattern).map_err(|e| {
+            PanopticonError::InvalidSchema(format!("{path}/pattern '{pattern}' is not valid: {e}"))
+        })?;
+        patterns.insert(pattern.to_string(), re);
+    }
+    for (keyword, nesting) in SUBSCHEMAS {
+        let Some(value) = schema.get(keyword) else {
+            continue;
+        };
+        let path = format!("{path}/{keyword}");
+        match (nesting, value) {
+            (Nesting::One, sub) => compile_patterns(sub, &path, patterns)?,
+            (Nesting::Map, Value::Object(subs)) => {
+                for (key, sub) in subs {
+                    compile_patterns(sub, &format!("{path}/{}", escape(key)), patterns)?;
+                }
+            }
+            (Nesting::List, Value::Array(subs)) => {
+                for (i, sub) in subs.iter().enumerate() {
+                    compile_patterns(sub, &format!("{path}/{i}"), patterns)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn violation(path: &str, message: impl Into<String>) -> SchemaViolation {
+    SchemaViolation {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Escape a property name for use in a JSON Pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn type_matches(expected: &Value, instance: &Value) -> bool {
+    match expected {
+        Value::String(name) => is_type(name, instance),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(|n| n.as_str())
+            .any(|n| is_type(n, instance)),
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, instance: &Value) -> bool {
+    match name {
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "boolean" => instance.is_boolean(),
+        "null" => instance.is_null(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn describe_type(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(|n| n.as_str())
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("?").to_string(),
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(schema: &Value, instance: &Value) -> Vec<SchemaViolation> {
+        CompiledSchema::compile(schema).unwrap().validate(instance)
+    }
+
+    fn report_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["result", "issues"],
+            "additionalProperties": false,
+            "properties": {
+                "result": {"type": "string", "minLength": 1},
+                "summary": {"type": "string"},
+                "issues": {
+                    "type": "array",
+                    "maxItems": 3,
+                    "items": {
+                        "type": "object",
+                        "required": ["severity"],
+                        "properties": {
+                            "severity": {"enum": ["low", "high"]},
+                            "line": {"type": "integer", "minimum": 1}
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_valid_instance() {
+        let output = json!({"result": "ok", "issues": [{"severity": "low", "line": 3}]});
+        assert!(validate(&report_schema(), &output).is_empty());
+    }
+
+    #[test]
+    fn test_reports_each_violation_with_path() {
+        let output = json!({
+            "result": "",
+            "extra": true,
+            "issues": [{"severity": "medium", "line": 0}, {"line": 2.5}]
+        });
+        let violations = validate(&report_schema(), &output);
+        let rendered: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        assert!(rendered.contains(&"/result: must be at least 1 character(s)".to_string()));
+        assert!(rendered.contains(&"/: unexpected property 'extra'".to_string()));
+        assert!(
+            rendered
+                .iter()
+                .any(|r| r.starts_with("/issues/0/severity: must be one of"))
+        );
+        assert!(rendered.contains(&"/issues/0/line: must be >= 1".to_string()));
+        assert!(rendered.contains(&"/issues/1: missing required property 'severity'".to_string()));
+        assert!(rendered.contains(&"/issues/1/line: expected integer, got number".to_string()));
+    }
+
+    #[test]
+    fn test_type_mismatch_at_root() {
+        let violations = validate(&report_schema(), &json!("just text"));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "expected object, got string");
+    }
+
+    #[test]
+    fn test_combinators_and_pattern() {
+        let schema = json!({
+            "anyOf": [{"type": "string", "pattern": "^v[0-9]+$"}, {"type": "integer"}]
+        });
+        assert!(validate(&schema, &json!("v12")).is_empty());
+        assert!(validate(&schema, &json!(3)).is_empty());
+        assert_eq!(validate(&schema, &json!("12")).len(), 1);
+
+        let one_of = json!({"oneOf": [{"type": "number"}, {"type": "integer"}]});
+        assert_eq!(validate(&one_of, &json!(1)).len(), 1);
+        assert!(validate(&one_of, &json!(1.5)).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected_at_compile_time() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"code": {"anyOf": [{"type": "string", "pattern": "([a-z]"}]}}
+        });
+        let err = CompiledSchema::compile(&schema).unwrap_err().to_string();
+        assert!(
+            err.contains("/properties/code/anyOf/0/pattern '([a-z]' is not valid"),
+            "{err}"
+        );
+
+        // A pattern that looks like one inside `const` is a value, not a schema.
+        let literal = json!({"const": {"pattern": "("}});
+        assert!(validate(&literal, &json!({"pattern": "("})).is_empty());
+    }
+}
@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::types::error::PanopticonError;
+use crate::types::artifact::Artifact;
 use crate::types::task::Task;
+use crate::types::usage::ResourceUsage;
+use crate::verification::schema::SchemaViolation;
 
 /// The result of a task execution, submitted for verification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +17,15 @@ pub struct TaskResult {
     pub output: serde_json::Value,
     pub completed_at: DateTime<Utc>,
     pub resource_consumed: f64,
+    /// Structured usage reported by the executor (tokens, cost, duration, turns).
+    #[serde(default)]
+    pub usage: ResourceUsage,
+    /// Files the execution created or modified in its workspace.
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+    /// Backend session the result was produced in, when the backend keeps sessions.
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 /// Outcome of a verification check.
@@ -21,6 +33,8 @@ pub struct TaskResult {
 pub enum VerificationOutcome {
     Passed { confidence: f64 },
     Failed { reason: String },
+    /// The output does not conform to the task's `output_schema`.
+    SchemaMismatch { violations: Vec<SchemaViolation> },
     Inconclusive,
 }
 
@@ -54,6 +54,9 @@ mod tests {
             output: serde_json::json!({}),
             completed_at: Utc::now(),
             resource_consumed: 1.0,
+            usage: Default::default(),
+            artifacts: Vec::new(),
+            session_id: None,
         };
         let outcome = verifier.verify(&task, &result).await.unwrap();
         assert!(matches!(
@@ -73,6 +73,9 @@ mod tests {
             output,
             completed_at: Utc::now(),
             resource_consumed: 1.0,
+            usage: Default::default(),
+            artifacts: Vec::new(),
+            session_id: None,
         }
     }
 
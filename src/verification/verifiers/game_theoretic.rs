@@ -101,6 +101,9 @@ mod tests {
             output: serde_json::json!({}),
             completed_at: Utc::now(),
             resource_consumed: 1.0,
+            usage: Default::default(),
+            artifacts: Vec::new(),
+            session_id: None,
         }
     }
 
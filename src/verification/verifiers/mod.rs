@@ -1,9 +1,11 @@
 pub mod cryptographic;
 pub mod direct;
 pub mod game_theoretic;
+pub mod schema;
 pub mod third_party;
 
 pub use cryptographic::CryptographicVerifier;
 pub use direct::DirectInspectionVerifier;
 pub use game_theoretic::GameTheoreticVerifier;
+pub use schema::SchemaVerifier;
 pub use third_party::ThirdPartyAuditVerifier;
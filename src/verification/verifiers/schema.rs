@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+
+use crate::types::error::PanopticonError;
+use crate::types::task::Task;
+
+use crate::verification::schema::CompiledSchema;
+use crate::verification::traits::{TaskResult, VerificationOutcome, Verifier};
+
+/// Verifier that checks the output against the task's `output_schema`,
+/// reporting every violation found.
+pub struct SchemaVerifier;
+
+#[async_trait]
+impl Verifier for SchemaVerifier {
+    async fn verify(
+        &self,
+        task: &Task,
+        result: &TaskResult,
+    ) -> Result<VerificationOutcome, PanopticonError> {
+        let Some(schema) = &task.output_schema else {
+            return Ok(VerificationOutcome::Inconclusive);
+        };
+        let violations = CompiledSchema::compile(schema)?.validate(&result.output);
+        if violations.is_empty() {
+            Ok(VerificationOutcome::Passed { confidence: 1.0 })
+        } else {
+            Ok(VerificationOutcome::SchemaMismatch { violations })
+        }
+    }
+
+    fn name(&self) -> &str {
+        "SchemaVerifier"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn make_result(output: serde_json::Value) -> TaskResult {
+        TaskResult {
+            task_id: Uuid::new_v4(),
+            agent_id: Uuid::new_v4(),
+            output,
+            completed_at: Utc::now(),
+            resource_consumed: 1.0,
+            usage: Default::default(),
+            artifacts: Vec::new(),
+            session_id: None,
+        }
+    }
+
+    fn make_task() -> Task {
+        Task::new("review", "Review the code").with_output_schema(serde_json::json!({
+            "type": "object",
+            "required": ["result", "score"],
+            "properties": {"score": {"type": "number", "maximum": 1}}
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_schema_passes() {
+        let result = make_result(serde_json::json!({"result": "ok", "score": 0.8}));
+        let outcome = SchemaVerifier.verify(&make_task(), &result).await.unwrap();
+        assert!(matches!(outcome, VerificationOutcome::Passed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_schema_mismatch_lists_violations() {
+        let result = make_result(serde_json::json!({"score": 3}));
+        let outcome = SchemaVerifier.verify(&make_task(), &result).await.unwrap();
+        let VerificationOutcome::SchemaMismatch { violations } = outcome else {
+            panic!("expected a schema mismatch, got {outcome:?}");
+        };
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[1].path, "/score");
+    }
+
+    #[tokio::test]
+    async fn test_no_schema_is_inconclusive() {
+        let result = make_result(serde_json::json!({"result": "ok"}));
+        let task = Task::new("t", "d");
+        let outcome = SchemaVerifier.verify(&task, &result).await.unwrap();
+        assert_eq!(outcome, VerificationOutcome::Inconclusive);
+    }
+}